@@ -9,6 +9,9 @@
 use crate::{
     error::RoutingError,
     location::{DstLocation, SrcLocation},
+    messages::MessageId,
+    time::Duration,
+    xor_space::XorName,
 };
 use bytes::Bytes;
 use hex_fmt::HexFmt;
@@ -33,6 +36,34 @@ pub enum Action {
         content: Vec<u8>,
         result_tx: Sender<Result<(), RoutingError>>,
     },
+    SendMessageWithReceipt {
+        src: SrcLocation,
+        dst: DstLocation,
+        id: MessageId,
+        content: Vec<u8>,
+        result_tx: Sender<Result<(), RoutingError>>,
+    },
+    SendRequest {
+        src: SrcLocation,
+        dst: DstLocation,
+        id: MessageId,
+        content: Vec<u8>,
+        timeout: Duration,
+        result_tx: Sender<Result<(), RoutingError>>,
+    },
+    SendResponse {
+        src: SrcLocation,
+        dst: DstLocation,
+        id: MessageId,
+        content: Vec<u8>,
+        result_tx: Sender<Result<(), RoutingError>>,
+    },
+    SendMessageToClientFromSection {
+        proxy: XorName,
+        client: XorName,
+        content: Vec<u8>,
+        result_tx: Sender<Result<(), RoutingError>>,
+    },
     HandleTimeout(u64),
     DisconnectClient {
         peer_addr: SocketAddr,
@@ -44,6 +75,9 @@ pub enum Action {
         token: Token,
         result_tx: Sender<Result<(), RoutingError>>,
     },
+    Leave {
+        result_tx: Sender<Result<(), RoutingError>>,
+    },
 }
 
 impl Debug for Action {
@@ -54,6 +88,46 @@ impl Debug for Action {
                 "Action::SendMessage {{ \"{:<8}\", result_tx }}",
                 HexFmt(content)
             ),
+            Self::SendMessageWithReceipt {
+                ref id,
+                ref content,
+                ..
+            } => write!(
+                formatter,
+                "Action::SendMessageWithReceipt {{ {:?}, \"{:<8}\", result_tx }}",
+                id,
+                HexFmt(content)
+            ),
+            Self::SendRequest {
+                ref id,
+                ref content,
+                ..
+            } => write!(
+                formatter,
+                "Action::SendRequest {{ {:?}, \"{:<8}\", result_tx }}",
+                id,
+                HexFmt(content)
+            ),
+            Self::SendResponse {
+                ref id,
+                ref content,
+                ..
+            } => write!(
+                formatter,
+                "Action::SendResponse {{ {:?}, \"{:<8}\", result_tx }}",
+                id,
+                HexFmt(content)
+            ),
+            Self::SendMessageToClientFromSection {
+                ref client,
+                ref content,
+                ..
+            } => write!(
+                formatter,
+                "Action::SendMessageToClientFromSection {{ {}, \"{:<8}\", result_tx }}",
+                client,
+                HexFmt(content)
+            ),
             Self::HandleTimeout(token) => write!(formatter, "Action::HandleTimeout({})", token),
             Self::DisconnectClient { peer_addr, .. } => {
                 write!(formatter, "Action::DisconnectClient: {}", peer_addr)
@@ -65,6 +139,7 @@ impl Debug for Action {
                 "Action::SendMessageToClient: {}, token: {}",
                 peer_addr, token
             ),
+            Self::Leave { .. } => write!(formatter, "Action::Leave"),
         }
     }
 }
@@ -0,0 +1,122 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    chain::MemberPersona,
+    relocation,
+    xor_space::{Prefix, XorName},
+};
+use std::collections::BTreeMap;
+
+/// A node joining or leaving our section, which ages the other members.
+#[derive(Clone, Copy, Debug)]
+pub struct Churn<'a> {
+    /// Prefix of our section.
+    pub prefix: &'a Prefix<XorName>,
+    /// Name of the node that joined or left.
+    pub trigger: &'a XorName,
+    /// Role of that node in our section, or `None` if it is unknown to us.
+    pub trigger_persona: Option<MemberPersona>,
+    /// Number of members of our section.
+    pub section_size: usize,
+    /// Number of members of each of our neighbour sections, as agreed by our section. Includes
+    /// the members already being relocated to them on this churn.
+    pub neighbour_sizes: &'a BTreeMap<Prefix<XorName>, usize>,
+    /// Number of members a section needs to be considered safe.
+    pub safe_section_size: usize,
+}
+
+/// Rules for ageing the members of our section and relocating them to other sections.
+///
+/// The age of a member is the base-2 logarithm (rounded down) of its age counter. The strategy is
+/// applied by all the elders of a section on each churn event, so it must be deterministic and the
+/// same on all of them, otherwise they would vote for different relocations.
+pub trait AgeingStrategy {
+    /// Returns the new age counter of our member `name` after the given churn.
+    fn increment_age_counter(&self, name: &XorName, age_counter: u32, churn: &Churn) -> u32;
+
+    /// Returns whether a member whose age counter changed from `old_age_counter` to
+    /// `new_age_counter` should be relocated.
+    fn should_relocate(&self, old_age_counter: u32, new_age_counter: u32) -> bool;
+
+    /// Returns the name the member `name` should be relocated close to. Relocations to our own
+    /// section are ignored.
+    fn relocation_destination(&self, name: &XorName, churn: &Churn) -> XorName;
+}
+
+/// The default strategy: every churn increments the counters of all the other members, and a
+/// member is relocated each time its age increases, to the section of the hash of its name and the
+/// name of the churning node.
+///
+/// Churn of infants ages the members too. Ignoring it would prevent churn spam, but also stop
+/// ageing in sections which only see infant churn.
+pub struct DefaultAgeing;
+
+impl AgeingStrategy for DefaultAgeing {
+    fn increment_age_counter(&self, _: &XorName, age_counter: u32, _: &Churn) -> u32 {
+        age_counter.saturating_add(1)
+    }
+
+    fn should_relocate(&self, old_age_counter: u32, new_age_counter: u32) -> bool {
+        new_age_counter > old_age_counter && new_age_counter.is_power_of_two()
+    }
+
+    fn relocation_destination(&self, name: &XorName, churn: &Churn) -> XorName {
+        relocation::compute_destination(churn.prefix, name, churn.trigger)
+    }
+}
+
+/// Ages members like the wrapped strategy, but relocates them to the neighbour section which is the
+/// furthest below `safe_section_size`, if any, at the position the wrapped strategy picked within
+/// it. Otherwise they go where the wrapped strategy sends them.
+pub struct BalancedRelocation<S> {
+    inner: S,
+}
+
+impl<S: AgeingStrategy> BalancedRelocation<S> {
+    /// Creates the strategy, wrapping `inner`.
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+}
+
+impl<S: AgeingStrategy> AgeingStrategy for BalancedRelocation<S> {
+    fn increment_age_counter(&self, name: &XorName, age_counter: u32, churn: &Churn) -> u32 {
+        self.inner.increment_age_counter(name, age_counter, churn)
+    }
+
+    fn should_relocate(&self, old_age_counter: u32, new_age_counter: u32) -> bool {
+        self.inner.should_relocate(old_age_counter, new_age_counter)
+    }
+
+    fn relocation_destination(&self, name: &XorName, churn: &Churn) -> XorName {
+        let destination = self.inner.relocation_destination(name, churn);
+        match neediest_section(churn.neighbour_sizes, churn.safe_section_size, &destination) {
+            Some(prefix) => prefix.substituted_in(destination),
+            None => destination,
+        }
+    }
+}
+
+// Returns the section which is the furthest below `safe_section_size`, preferring the one closest
+// to `name` among equally small sections, or `None` if no section is below that size.
+fn neediest_section(
+    section_sizes: &BTreeMap<Prefix<XorName>, usize>,
+    safe_section_size: usize,
+    name: &XorName,
+) -> Option<Prefix<XorName>> {
+    section_sizes
+        .iter()
+        .filter(|(_, size)| **size < safe_section_size)
+        .min_by(|(prefix0, size0), (prefix1, size1)| {
+            size0
+                .cmp(size1)
+                .then_with(|| prefix0.cmp_distance(prefix1, name))
+        })
+        .map(|(prefix, _)| *prefix)
+}
@@ -0,0 +1,288 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    error::RoutingError,
+    event::Event,
+    id::PublicId,
+    location::{DstLocation, SrcLocation},
+    messages::MessageId,
+    node::Node,
+    time::Duration,
+};
+use crossbeam_channel as mpmc;
+use futures::{
+    channel::{mpsc, oneshot},
+    Future, Stream,
+};
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+/// Asynchronous interface of a `Node`.
+///
+/// The node itself is owned by the [`NodeDriver`](struct.NodeDriver.html) which must be run for
+/// the futures returned by this handle to complete and for the events to be streamed. The handle
+/// is cheap to clone.
+#[derive(Clone)]
+pub struct AsyncNode {
+    command_tx: mpmc::Sender<Command>,
+}
+
+impl AsyncNode {
+    /// Wraps the given node and the receiver of its events.
+    pub fn new(
+        node: Node,
+        node_event_rx: mpmc::Receiver<Event>,
+    ) -> (Self, EventStream, NodeDriver) {
+        let (command_tx, command_rx) = mpmc::unbounded();
+        let (event_tx, event_rx) = mpsc::unbounded();
+        let wakeup = Wakeup::spawn(&node, command_rx.clone());
+
+        let driver = NodeDriver {
+            node,
+            command_rx: Some(command_rx),
+            node_event_rx,
+            event_tx,
+            wakeup,
+        };
+
+        (Self { command_tx }, EventStream(event_rx), driver)
+    }
+
+    /// Calls `f` with the node, once the driver gets to it. This gives access to the whole API of
+    /// `Node`, for which this handle has no shortcut.
+    ///
+    /// The returned future resolves with the result of `f`. Fails with
+    /// `RoutingError::InvalidState` if the driver stopped.
+    pub fn with_node<F, T>(&self, f: F) -> impl Future<Output = Result<T, RoutingError>>
+    where
+        F: FnOnce(&mut Node) -> Result<T, RoutingError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (result_tx, result_rx) = oneshot::channel();
+        let command: Command = Box::new(move |node| {
+            let _ = result_tx.send(f(node));
+        });
+        let sent = self.command_tx.send(command).is_ok();
+
+        async move {
+            if !sent {
+                return Err(RoutingError::InvalidState);
+            }
+
+            result_rx.await.unwrap_or(Err(RoutingError::InvalidState))
+        }
+    }
+
+    /// Returns the id of the node. See `Node::id`.
+    pub fn id(&self) -> impl Future<Output = Result<PublicId, RoutingError>> {
+        self.with_node(|node| node.id())
+    }
+
+    /// Send a message. See `Node::send_message`.
+    pub fn send_message(
+        &self,
+        src: SrcLocation,
+        dst: DstLocation,
+        content: Vec<u8>,
+    ) -> impl Future<Output = Result<(), RoutingError>> {
+        self.with_node(move |node| node.send_message(src, dst, content))
+    }
+
+    /// Send a message and ask its destination to confirm the delivery. See
+    /// `Node::send_message_with_receipt`.
+    pub fn send_message_with_receipt(
+        &self,
+        src: SrcLocation,
+        dst: DstLocation,
+        id: MessageId,
+        content: Vec<u8>,
+    ) -> impl Future<Output = Result<(), RoutingError>> {
+        self.with_node(move |node| node.send_message_with_receipt(src, dst, id, content))
+    }
+
+    /// Send a request with the given id. See `Node::send_request`.
+    pub fn send_request(
+        &self,
+        src: SrcLocation,
+        dst: DstLocation,
+        id: MessageId,
+        content: Vec<u8>,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<(), RoutingError>> {
+        self.with_node(move |node| node.send_request(src, dst, id, content, timeout))
+    }
+
+    /// Send the response to the request with the given id. See `Node::send_response`.
+    pub fn send_response(
+        &self,
+        src: SrcLocation,
+        dst: DstLocation,
+        id: MessageId,
+        content: Vec<u8>,
+    ) -> impl Future<Output = Result<(), RoutingError>> {
+        self.with_node(move |node| node.send_response(src, dst, id, content))
+    }
+
+    /// Leaves the network gracefully. See `Node::leave`.
+    pub fn leave(&self) -> impl Future<Output = Result<(), RoutingError>> {
+        self.with_node(|node| node.leave())
+    }
+}
+
+/// Stream of the events raised by an `AsyncNode`. Ends when its driver stops.
+pub struct EventStream(mpsc::UnboundedReceiver<Event>);
+
+impl Stream for EventStream {
+    type Item = Event;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Event>> {
+        Pin::new(&mut self.0).poll_next(cx)
+    }
+}
+
+/// Drives the state machine of the node behind an `AsyncNode`.
+///
+/// The driver is a future which completes when the node terminates. As `Node` is not `Send`, it
+/// has to be spawned on an executor running on the current thread, for example tokio's
+/// `LocalSet::spawn_local` or futures' `LocalPool`. A background thread waits for the node to have
+/// something to handle and wakes the driver up.
+///
+/// Alternatively, `run` drives the node on the current thread, blocking it until the node
+/// terminates, and `run_until_idle` never blocks and can be called periodically.
+pub struct NodeDriver {
+    node: Node,
+    // `None` once all the `AsyncNode` handles are dropped.
+    command_rx: Option<mpmc::Receiver<Command>>,
+    node_event_rx: mpmc::Receiver<Event>,
+    event_tx: mpsc::UnboundedSender<Event>,
+    wakeup: Wakeup,
+}
+
+impl NodeDriver {
+    /// Drives the node until it terminates.
+    pub fn run(mut self) {
+        while self.step(true).is_ok() {}
+    }
+
+    /// Handles all the commands and network events which are ready, without blocking. Returns
+    /// whether any was handled, or an error if the node terminated.
+    pub fn run_until_idle(&mut self) -> Result<bool, mpmc::RecvError> {
+        let mut handled = false;
+        while self.step(false)? {
+            handled = true;
+        }
+        Ok(handled)
+    }
+
+    // Handles one command or network event. Returns `false` if there was none ready and `block`
+    // is not set.
+    fn step(&mut self, block: bool) -> Result<bool, mpmc::RecvError> {
+        let mut select = mpmc::Select::new();
+        let command_rx_idx = self.command_rx.as_ref().map(|rx| select.recv(rx));
+        self.node.register(&mut select);
+
+        let op_index = if block {
+            select.ready()
+        } else if let Ok(op_index) = select.try_ready() {
+            op_index
+        } else {
+            return Ok(false);
+        };
+
+        if Some(op_index) == command_rx_idx {
+            match self.command_rx.as_ref().map(mpmc::Receiver::recv) {
+                Some(Ok(command)) => command(&mut self.node),
+                _ => self.command_rx = None,
+            }
+        } else {
+            let _ = self.node.handle_selected_operation(op_index)?;
+        }
+
+        // If the `EventStream` was dropped, the events are dropped too.
+        for event in self.node_event_rx.try_iter() {
+            let _ = self.event_tx.unbounded_send(event);
+        }
+
+        Ok(true)
+    }
+}
+
+impl Future for NodeDriver {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let this = self.get_mut();
+        this.wakeup.register(cx.waker());
+
+        if this.run_until_idle().is_err() {
+            return Poll::Ready(());
+        }
+
+        this.wakeup.arm(this.command_rx.is_some());
+        Poll::Pending
+    }
+}
+
+type Command = Box<dyn FnOnce(&mut Node) + Send>;
+
+// Wakes the driver up from a background thread once the node has something to handle.
+struct Wakeup {
+    waker: Arc<Mutex<Option<Waker>>>,
+    // Asks the thread to wait once more. The flag tells whether to wait for commands too.
+    arm_tx: mpmc::Sender<bool>,
+}
+
+impl Wakeup {
+    fn spawn(node: &Node, command_rx: mpmc::Receiver<Command>) -> Self {
+        let (network_rx, action_rx) = node.receivers();
+        let waker = Arc::new(Mutex::new(None::<Waker>));
+        let (arm_tx, arm_rx) = mpmc::unbounded::<bool>();
+
+        let thread_waker = Arc::clone(&waker);
+        let _ = thread::spawn(move || {
+            let mut command_rx = Some(command_rx);
+            // Stops once the driver is dropped.
+            while let Ok(with_commands) = arm_rx.recv() {
+                if !with_commands {
+                    // All the handles are gone, so the channel would always be ready.
+                    command_rx = None;
+                }
+
+                let mut select = mpmc::Select::new();
+                let _ = select.recv(&network_rx);
+                let _ = select.recv(&action_rx);
+                if let Some(command_rx) = &command_rx {
+                    let _ = select.recv(command_rx);
+                }
+                let _ = select.ready();
+
+                let waker = thread_waker.lock().ok().and_then(|mut waker| waker.take());
+                if let Some(waker) = waker {
+                    waker.wake();
+                }
+            }
+        });
+
+        Self { waker, arm_tx }
+    }
+
+    fn register(&self, waker: &Waker) {
+        if let Ok(mut current) = self.waker.lock() {
+            *current = Some(waker.clone());
+        }
+    }
+
+    fn arm(&self, with_commands: bool) {
+        let _ = self.arm_tx.send(with_commands);
+    }
+}
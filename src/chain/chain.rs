@@ -8,17 +8,25 @@
 
 use super::{
     chain_accumulator::{AccumulatingProof, ChainAccumulator, InsertError},
-    shared_state::{SectionKeyInfo, SectionProofBlock, SharedState, SplitCache},
-    AccumulatedEvent, AccumulatingEvent, AgeCounter, EldersChange, EldersInfo, GenesisPfxInfo,
-    MemberInfo, MemberPersona, MemberState, NetworkEvent, NetworkParams, Proof, ProofSet,
-    SectionProofSlice,
+    merge::is_absorber,
+    section_map::{self, SectionMap},
+    shared_state::{SectionKeyInfo, SectionProofBlock, SectionProofChain, SharedState, SplitCache},
+    AccumulatedEvent, AccumulatingEvent, AgeCounter, DepartedMember, EldersChange, EldersInfo,
+    GenesisPfxInfo, MemberInfo, MemberPersona, MemberState, MergeMember, MergeRequest,
+    Misbehaviour, MisbehaviourRecord, MisbehaviourReport, NetworkEvent, NetworkParams,
+    NetworkStats, Proof, ProofSet, Punishment, SectionProofSlice, SectionStats, Strategies,
 };
 use crate::{
+    ageing::Churn,
+    elder_selection::{ElderCandidate, ElderSelection, EldestFirst},
     error::RoutingError,
     id::{P2pNode, PublicId},
     location::{DstLocation, SrcLocation},
+    messages::{self, VersionRange},
     parsec::{DkgResult, DkgResultWrapper},
-    relocation::{self, RelocateDetails},
+    relocation::RelocateDetails,
+    rng::MainRng,
+    split::{LoadMetrics, SplitCandidate},
     utils::LogIdent,
     xor_space::Xorable,
     Prefix, XorName,
@@ -26,6 +34,7 @@ use crate::{
 use bincode::serialize;
 use itertools::Itertools;
 use log::LogLevel;
+use rand::Rng;
 use serde::Serialize;
 use std::{
     cmp::Ordering,
@@ -45,6 +54,7 @@ pub const fn delivery_group_size(n: usize) -> usize {
 }
 
 /// Data chain.
+#[derive(Serialize, Deserialize)]
 pub struct Chain {
     /// Network parameters
     network_cfg: NetworkParams,
@@ -71,7 +81,19 @@ pub struct Chain {
     /// first member in DKG participants and new ElderInfo. We only store 2 items during split, and
     /// then members are disjoint. We are working around not having access to the prefix for the
     /// DkgResult but only the list of participants.
+    #[serde(with = "serde_dkg_results")]
     new_section_bls_keys: BTreeMap<XorName, DkgResult>,
+    /// Local rules of the section. Not serialized, so they need to be set again on restore.
+    #[serde(skip)]
+    strategies: Strategies,
+    /// Sections other than ours and our neighbours we learnt about by querying them.
+    section_map: SectionMap,
+    /// The statistics of our section and the network we last agreed on.
+    network_stats: Option<NetworkStats>,
+    /// The latest statistics our neighbours sent us, by prefix.
+    neighbour_stats: BTreeMap<Prefix<XorName>, NetworkStats>,
+    /// The key of the merged section and its signature by the section it absorbs, once agreed.
+    merge_key_signature: Option<(SectionKeyInfo, bls::Signature)>,
 }
 
 #[allow(clippy::len_without_is_empty)]
@@ -91,6 +113,117 @@ impl Chain {
         self.network_cfg
     }
 
+    /// Returns the local rules of the section.
+    pub fn strategies(&self) -> &Strategies {
+        &self.strategies
+    }
+
+    /// Replaces the local rules of the section.
+    pub fn set_strategies(&mut self, strategies: Strategies) {
+        self.strategies = strategies;
+    }
+
+    /// Replaces the network parameters, e.g. with the ones given to a node restored from a
+    /// snapshot.
+    pub fn set_network_cfg(&mut self, network_cfg: NetworkParams) {
+        self.network_cfg = network_cfg;
+    }
+
+    /// Returns the protocol version our section agreed on.
+    pub fn protocol_version(&self) -> u16 {
+        self.state.protocol_version
+    }
+
+    /// Returns the newest protocol version all our members speak, if any.
+    pub fn newest_common_protocol_version(&self) -> Option<u16> {
+        messages::newest_common_version(
+            self.state
+                .our_joined_members()
+                .map(|(_, member)| &member.versions),
+        )
+    }
+
+    /// Returns the statistics of our section and the network we last agreed on, if any.
+    pub fn network_stats(&self) -> Option<&NetworkStats> {
+        self.network_stats.as_ref()
+    }
+
+    /// Returns the round to vote for to agree on new statistics.
+    pub fn next_stats_round(&self) -> u64 {
+        self.network_stats
+            .as_ref()
+            .map_or(0, |stats| stats.round)
+            .saturating_add(1)
+    }
+
+    /// Returns the report to vote for a new occurrence of the given misbehaviour.
+    pub fn misbehaviour_report(
+        &self,
+        offender: PublicId,
+        kind: Misbehaviour,
+    ) -> MisbehaviourReport {
+        let count = self
+            .state
+            .misbehaviour
+            .get(offender.name())
+            .map(|record| record.count(kind))
+            .unwrap_or(0);
+
+        MisbehaviourReport {
+            offender,
+            kind,
+            count,
+        }
+    }
+
+    /// Records an agreed misbehaviour of one of our members and returns the punishment it
+    /// deserves. Age penalties and demotions are applied here, evictions are left to the caller.
+    /// Returns `None` if the offender is not our member or the report is outdated.
+    pub fn record_misbehaviour(&mut self, report: &MisbehaviourReport) -> Option<Punishment> {
+        let member = self
+            .state
+            .our_members
+            .get_mut(report.offender.name())
+            .filter(|info| info.state != MemberState::Left)?;
+        let record = self
+            .state
+            .misbehaviour
+            .entry(*report.offender.name())
+            .or_default();
+        if record.count(report.kind) != report.count {
+            return None;
+        }
+
+        record.record(report.kind);
+        let punishment = Punishment::for_score(record.score(), &self.network_cfg);
+        if punishment >= Punishment::AgePenalty {
+            let age = member.age();
+            member.set_age(age.saturating_sub(1));
+            // Age and demotion both affect who should be elder.
+            self.members_changed = true;
+        }
+
+        Some(punishment)
+    }
+
+    /// Returns whether the member with the given name misbehaved enough to be demoted.
+    fn is_demoted(&self, name: &XorName) -> bool {
+        self.state
+            .misbehaviour
+            .get(name)
+            .map(|record| record.score() >= self.network_cfg.misbehaviour_demote_score)
+            .unwrap_or(false)
+    }
+
+    /// Returns whether the node with the given name was evicted from our section.
+    pub fn is_evicted(&self, name: &XorName) -> bool {
+        self.state
+            .misbehaviour
+            .get(name)
+            .map(|record| record.score() >= self.network_cfg.misbehaviour_evict_score)
+            .unwrap_or(false)
+    }
+
     pub fn our_section_bls_keys(&self) -> &bls::PublicKeySet {
         &self.our_section_bls_keys.public_key_set
     }
@@ -102,6 +235,21 @@ impl Chain {
             .ok_or(RoutingError::InvalidElderDkgResult)
     }
 
+    /// Returns the latest key info of our section.
+    pub fn our_section_key_info(&self) -> &SectionKeyInfo {
+        self.state.our_history.last_key_info()
+    }
+
+    /// Returns the history of the keys of our section, from the genesis key of the network.
+    pub fn our_history(&self) -> &SectionProofChain {
+        &self.state.our_history
+    }
+
+    /// Checks that our section's key history is a valid chain of signatures.
+    pub fn validate_our_history(&self) -> bool {
+        self.state.our_history.validate()
+    }
+
     /// Collects prefixes of all sections known by the routing table into a `BTreeSet`.
     pub fn prefixes(&self) -> BTreeSet<Prefix<XorName>> {
         self.other_prefixes()
@@ -141,9 +289,19 @@ impl Chain {
             relocation_in_progress: false,
             members_changed: false,
             new_section_bls_keys: Default::default(),
+            strategies: Default::default(),
+            section_map: Default::default(),
+            network_stats: None,
+            neighbour_stats: Default::default(),
+            merge_key_signature: None,
         }
     }
 
+    /// Sets the local rules of the section.
+    pub fn with_strategies(self, strategies: Strategies) -> Self {
+        Self { strategies, ..self }
+    }
+
     /// Handles an accumulated parsec Observation for genesis.
     ///
     /// The related_info is the serialized shared state that will be the starting
@@ -273,8 +431,11 @@ impl Chain {
             .map(|(event, _)| event.clone());
 
         opt_event.and_then(|event| {
-            self.chain_accumulator
-                .poll_event(event, self.our_info().member_ids().cloned().collect())
+            self.chain_accumulator.poll_event(
+                event,
+                self.our_info().member_ids().cloned().collect(),
+                self.network_cfg.unresponsive_window,
+            )
         })
     }
 
@@ -313,8 +474,71 @@ impl Chain {
             AccumulatingEvent::Relocate(_) => {
                 self.relocation_in_progress = false;
             }
+            AccumulatingEvent::ProtocolVersion(version) => {
+                if version <= self.state.protocol_version {
+                    return Ok(None);
+                }
+                self.state.protocol_version = version;
+            }
+            AccumulatingEvent::StatsRound(round) => {
+                if round < self.next_stats_round() {
+                    return Ok(None);
+                }
+                self.network_stats = Some(self.compute_network_stats(round));
+            }
+            AccumulatingEvent::NeighbourStats(ref stats) => {
+                if !self.add_neighbour_stats(stats) {
+                    return Ok(None);
+                }
+            }
+            AccumulatingEvent::ProposeMerge => {
+                if self.state.merge_proposed {
+                    return Ok(None);
+                }
+                self.state.merge_proposed = true;
+                self.members_changed = true;
+            }
+            AccumulatingEvent::WithdrawMerge => {
+                if !self.state.merge_proposed {
+                    return Ok(None);
+                }
+                self.state.merge_proposed = false;
+                self.state.sibling_merge = None;
+                self.merge_key_signature = None;
+            }
+            AccumulatingEvent::SiblingMerge(ref request) => {
+                if !self.is_new_sibling_merge(request) {
+                    return Ok(None);
+                }
+                self.state.sibling_merge = Some(request.clone());
+                self.members_changed = true;
+            }
+            AccumulatingEvent::SignMergeKey(ref key_info) => {
+                let sig = match self.check_and_combine_signatures(key_info, proofs) {
+                    Some(sig) => sig,
+                    None => return Ok(None),
+                };
+                self.merge_key_signature = Some((key_info.clone(), sig));
+            }
+            AccumulatingEvent::MergeKeySignature(ref key_info, ref sig) => {
+                if self.merge_key_signature.is_some()
+                    || !self.is_valid_merge_key_signature(key_info, sig)
+                {
+                    return Ok(None);
+                }
+                self.merge_key_signature = Some((key_info.clone(), sig.clone()));
+            }
+            AccumulatingEvent::LoadMetrics(ref metrics) => {
+                if *metrics == self.state.load_metrics {
+                    return Ok(None);
+                }
+                self.state.load_metrics = metrics.clone();
+                // The split policy may now decide differently.
+                self.members_changed = true;
+            }
             AccumulatingEvent::Online(_)
             | AccumulatingEvent::Offline(_)
+            | AccumulatingEvent::Misbehaviour(_)
             | AccumulatingEvent::StartDkg(_)
             | AccumulatingEvent::User(_)
             | AccumulatingEvent::ParsecPrune
@@ -346,7 +570,9 @@ impl Chain {
         event: AccumulatedEvent,
     ) -> Result<Option<AccumulatedEvent>, RoutingError> {
         let start_churn_event = match &event.content {
-            AccumulatingEvent::Online(_) | AccumulatingEvent::Offline(_) => true,
+            AccumulatingEvent::Online(_)
+            | AccumulatingEvent::Offline(_)
+            | AccumulatingEvent::Misbehaviour(_) => true,
             _ => false,
         };
 
@@ -366,29 +592,12 @@ impl Chain {
 
     // Increment the age counters of the members.
     pub fn increment_age_counters(&mut self, trigger_node: &PublicId) {
-        let our_section_size = self.state.our_joined_members().count();
-        let safe_section_size = self.safe_section_size();
-
-        if our_section_size >= safe_section_size
-            && self
-                .state
-                .get_persona(trigger_node)
-                .map(|persona| persona == MemberPersona::Infant)
-                .unwrap_or(true)
-        {
-            // FIXME: skipping infants churn for ageing breaks tests for node ageing, as once a
-            // section reaches a safe size, nodes stop ageing at all, because all churn in tests
-            // is Infant churn.
-            // Temporarily ignore until we either find a better way of preventing churn spam,
-            // or we change the tests to provide some Adult churn at all times.
-            trace!(
-                "{} FIXME: should do nothing for infants and unknown nodes {:?}",
-                self,
-                trigger_node
-            );
-        }
-
         let our_prefix = *self.state.our_prefix();
+        let trigger_persona = self.state.get_persona(trigger_node);
+        let section_size = self.state.our_joined_members().count();
+        let safe_section_size = self.safe_section_size();
+        let mut neighbour_sizes = self.neighbour_section_sizes();
+        let ageing = &self.strategies.ageing;
         let relocating_state = self.state.create_relocating_state();
         let mut details_to_add = Vec::new();
 
@@ -403,12 +612,25 @@ impl Chain {
                 continue;
             }
 
-            if !member_info.increment_age_counter() {
+            let churn = Churn {
+                prefix: &our_prefix,
+                trigger: trigger_node.name(),
+                trigger_persona,
+                section_size,
+                neighbour_sizes: &neighbour_sizes,
+                safe_section_size,
+            };
+            let old_age_counter = member_info.age_counter_value();
+            member_info.set_age_counter_value(ageing.increment_age_counter(
+                name,
+                old_age_counter,
+                &churn,
+            ));
+            if !ageing.should_relocate(old_age_counter, member_info.age_counter_value()) {
                 continue;
             }
 
-            let destination =
-                relocation::compute_destination(&our_prefix, name, trigger_node.name());
+            let destination = ageing.relocation_destination(name, &churn);
             if our_prefix.matches(&destination) {
                 // Relocation destination inside the current section - ignoring.
                 trace!(
@@ -418,6 +640,14 @@ impl Chain {
                 continue;
             }
 
+            if let Some(size) = neighbour_sizes
+                .iter_mut()
+                .find(|(prefix, _)| prefix.matches(&destination))
+                .map(|(_, size)| size)
+            {
+                *size += 1;
+            }
+
             member_info.state = relocating_state;
             details_to_add.push(PartialRelocateDetails {
                 pub_id: *member_info.p2p_node.public_id(),
@@ -444,6 +674,21 @@ impl Chain {
         }
     }
 
+    // Number of members of each neighbour section, as agreed by our section: the member count of
+    // the latest statistics the neighbour sent us, or its number of elders if we have none yet.
+    fn neighbour_section_sizes(&self) -> BTreeMap<Prefix<XorName>, usize> {
+        self.neighbour_infos()
+            .map(|info| {
+                let size = self
+                    .neighbour_stats
+                    .get(info.prefix())
+                    .map_or(0, |stats| stats.section.member_count() as usize)
+                    .max(info.len());
+                (*info.prefix(), size)
+            })
+            .collect()
+    }
+
     /// Returns the details of the next scheduled relocation to be voted for, if any.
     fn poll_relocation(&mut self) -> Option<RelocateDetails> {
         // Delay relocation until all backlogged churn events have been handled and no
@@ -494,7 +739,9 @@ impl Chain {
 
     /// Validate if can call add_member on this node.
     pub fn can_add_member(&self, pub_id: &PublicId) -> bool {
-        self.our_prefix().matches(pub_id.name()) && !self.is_peer_our_member(pub_id)
+        self.our_prefix().matches(pub_id.name())
+            && !self.is_peer_our_member(pub_id)
+            && !self.is_evicted(pub_id.name())
     }
 
     /// Validate if can call remove_member on this node.
@@ -503,18 +750,27 @@ impl Chain {
     }
 
     /// Adds a member to our section.
-    pub fn add_member(&mut self, p2p_node: P2pNode, age: u8) {
+    pub fn add_member(&mut self, p2p_node: P2pNode, age: u8, versions: VersionRange) {
         self.assert_no_prefix_change("add member");
         self.members_changed = true;
+        self.record_membership_change();
+
+        // A recently departed member proves it holds its old key by signing its join request, so
+        // it gets half its previous age back.
+        let age = match self.state.departed_members.remove(p2p_node.name()) {
+            Some(departed) if departed.pub_id == *p2p_node.public_id() => {
+                age.max(departed.rejoin_age())
+            }
+            _ => age,
+        };
 
         match self.state.our_members.entry(*p2p_node.name()) {
             Entry::Occupied(mut entry) => {
                 if entry.get().state == MemberState::Left {
                     // Node rejoining
-                    // TODO: To properly support rejoining, either keep the previous age or set the
-                    // new age to max(old_age, new_age)
                     entry.get_mut().state = MemberState::Joined;
                     entry.get_mut().set_age(age);
+                    entry.get_mut().versions = versions;
                 } else {
                     // Node already joined - this should not happen.
                     log_or_panic!(
@@ -527,7 +783,7 @@ impl Chain {
             }
             Entry::Vacant(entry) => {
                 // Node joining for the first time.
-                let _ = entry.insert(MemberInfo::new(age, p2p_node.clone()));
+                let _ = entry.insert(MemberInfo::new(age, p2p_node.clone(), versions));
             }
         }
     }
@@ -536,6 +792,8 @@ impl Chain {
     pub fn remove_member(&mut self, pub_id: &PublicId) -> MemberState {
         self.assert_no_prefix_change("remove member");
         self.members_changed = true;
+        self.record_membership_change();
+        let membership_changes = self.state.membership_changes;
 
         if let Some(info) = self
             .state
@@ -549,6 +807,17 @@ impl Chain {
             self.state
                 .relocate_queue
                 .retain(|details| &details.pub_id != pub_id);
+
+            // Relocated members have their age carried over to their new section already.
+            if member_state == MemberState::Joined {
+                let departed = DepartedMember {
+                    pub_id: *pub_id,
+                    age: info.age(),
+                    departed_at: membership_changes,
+                };
+                let _ = self.state.departed_members.insert(*pub_id.name(), departed);
+            }
+
             member_state
         } else {
             log_or_panic!(
@@ -562,6 +831,17 @@ impl Chain {
         }
     }
 
+    // Advance the membership change counter and forget the members that departed too long ago.
+    fn record_membership_change(&mut self) {
+        self.state.membership_changes += 1;
+
+        let now = self.state.membership_changes;
+        let rejoin_window = self.network_cfg.rejoin_window;
+        self.state
+            .departed_members
+            .retain(|_, departed| now - departed.departed_at < rejoin_window);
+    }
+
     /// Generate a new section info based on the current set of members.
     /// Returns a set of EldersInfos to vote for.
     fn promote_and_demote_elders(&mut self) -> Result<Option<Vec<EldersInfo>>, RoutingError> {
@@ -570,6 +850,13 @@ impl Chain {
             return Ok(None);
         }
 
+        if let Some(merged_info) = self.merged_elders_info()? {
+            self.state.split_in_progress = true;
+            self.members_changed = false;
+            self.churn_in_progress = true;
+            return Ok(Some(vec![merged_info]));
+        }
+
         if self.should_split()? {
             let (our_info, other_info) = self.split_self()?;
             self.state.split_in_progress = true;
@@ -594,7 +881,10 @@ impl Chain {
                 Some(self.state.our_info()),
             )?;
 
-            if self.state.our_info().len() < self.elder_size() && old_size >= self.elder_size() {
+            if self.state.our_info().len() < self.elder_size()
+                && old_size >= self.elder_size()
+                && self.network_cfg.merge_threshold == 0
+            {
                 panic!(
                     "Merging situation encountered! Not supported: {:?}: {:?}",
                     self.our_id(),
@@ -645,6 +935,11 @@ impl Chain {
         // TODO: Bring back using their_knowledge to clean_older section in our_infos
         self.check_and_clean_neighbour_infos(None);
         self.state.split_in_progress = false;
+        self.state.merge_proposed = false;
+        self.state.sibling_merge = None;
+        self.merge_key_signature = None;
+        // The load was measured for our previous prefix.
+        self.state.load_metrics = LoadMetrics::default();
 
         info!("{} - finalise_prefix_change: {:?}", self, self.our_prefix());
         trace!("{} - finalise_prefix_change state: {:?}", self, self.state);
@@ -672,6 +967,106 @@ impl Chain {
         self.state.split_in_progress
     }
 
+    /// Returns whether our section agreed to merge with its sibling.
+    pub fn merge_proposed(&self) -> bool {
+        self.state.merge_proposed
+    }
+
+    /// Returns whether our section should vote to merge with its sibling.
+    pub fn should_propose_merge(&self) -> bool {
+        let merge_threshold = self.network_cfg.merge_threshold;
+        merge_threshold > 0
+            && !self.state.merge_proposed
+            && !self.state.split_in_progress
+            && self.our_prefix().bit_count() > 0
+            && self.state.our_joined_members().count() < merge_threshold
+    }
+
+    /// Returns whether our section should vote to withdraw its proposal to merge, having grown
+    /// back to the merge threshold. Once our sibling agreed too, the merge goes ahead regardless.
+    pub fn should_withdraw_merge(&self) -> bool {
+        self.state.merge_proposed
+            && !self.state.split_in_progress
+            && !self.merge_pending()
+            && self.state.our_joined_members().count() >= self.network_cfg.merge_threshold
+    }
+
+    /// Returns whether both our section and our sibling agreed to merge. No node can join our
+    /// section or be relocated to it until the merge.
+    pub fn merge_pending(&self) -> bool {
+        self.state.merge_proposed
+            && self
+                .state
+                .sibling_merge
+                .as_ref()
+                .map_or(false, |request| !request.withdrawn)
+    }
+
+    /// Returns whether both siblings agreed to merge and our section is the one being absorbed.
+    pub fn is_being_absorbed(&self) -> bool {
+        self.merge_pending()
+            && self.state.sibling_merge.as_ref().map_or(false, |request| {
+                !is_absorber(self.our_info(), &request.elders_info)
+            })
+    }
+
+    /// Returns whether `sig` is the signature of the merged `key_info` by the last key of the
+    /// sibling we absorb.
+    pub fn is_valid_merge_key_signature(
+        &self,
+        key_info: &SectionKeyInfo,
+        sig: &bls::Signature,
+    ) -> bool {
+        match (
+            &self.state.sibling_merge,
+            key_info.serialise_for_signature(),
+        ) {
+            (Some(request), Ok(to_verify)) => request.key_info.key().verify(sig, to_verify),
+            _ => false,
+        }
+    }
+
+    /// Returns the key of the merged section and its signature by the section it absorbs, once
+    /// agreed.
+    pub fn merge_key_signature(&self) -> Option<&(SectionKeyInfo, bls::Signature)> {
+        self.merge_key_signature.as_ref()
+    }
+
+    /// Returns the request to send to our sibling for it to merge with us.
+    pub fn merge_request(&self) -> MergeRequest {
+        MergeRequest {
+            elders_info: self.our_info().clone(),
+            key_info: self.our_section_key_info().clone(),
+            members: self
+                .state
+                .our_joined_members()
+                .map(|(_, info)| MergeMember::new(info))
+                .collect(),
+            neighbour_infos: self.neighbour_infos().cloned().collect(),
+            membership_changes: self.state.membership_changes,
+            withdrawn: !self.state.merge_proposed,
+        }
+    }
+
+    /// Returns whether the merge request is from our sibling and newer than the one we have.
+    pub fn is_new_sibling_merge(&self, request: &MergeRequest) -> bool {
+        let our_prefix = self.our_prefix();
+        our_prefix.bit_count() > 0
+            && *request.elders_info.prefix() == our_prefix.sibling()
+            && self
+                .state
+                .sibling_merge
+                .as_ref()
+                .map_or(true, |old_request| request.is_newer_than(old_request))
+    }
+
+    /// Returns whether our section is going to be absorbed by the section of `prefix`, i.e. our
+    /// sibling once it absorbed us. Our sibling can only do so once we agreed to merge, even if we
+    /// withdrew our proposal since, too late for it to learn about it.
+    pub fn is_merging_into(&self, prefix: &Prefix<XorName>) -> bool {
+        self.our_prefix().bit_count() > 0 && self.our_prefix().popped() == *prefix
+    }
+
     /// Returns whether a churn (elders change) is in progress.
     pub fn churn_in_progress(&self) -> bool {
         self.churn_in_progress
@@ -797,6 +1192,16 @@ impl Chain {
             .map(|(_, info)| &info.p2p_node)
     }
 
+    /// Returns the number of infants and the number of adults (including elders) among our
+    /// joined members.
+    pub fn our_infant_and_adult_counts(&self) -> (usize, usize) {
+        let (infants, adults): (Vec<_>, Vec<_>) =
+            self.state.our_joined_members().partition(|(_, info)| {
+                self.state.get_persona(info.p2p_node.public_id()) == Some(MemberPersona::Infant)
+            });
+        (infants.len(), adults.len())
+    }
+
     fn elders_and_adults(&self) -> impl Iterator<Item = &PublicId> {
         self.state
             .our_joined_members()
@@ -807,14 +1212,7 @@ impl Chain {
     }
 
     fn our_expected_elders(&self) -> BTreeMap<XorName, P2pNode> {
-        let mut elders: BTreeMap<_, _> = self
-            .state
-            .our_joined_members()
-            .sorted_by(|&(_, info1), &(_, info2)| Ord::cmp(&info2.age_counter, &info1.age_counter))
-            .into_iter()
-            .map(|(name, info)| (*name, info.p2p_node.clone()))
-            .take(self.elder_size())
-            .collect();
+        let mut elders = self.select_elders(&Prefix::default());
 
         // Ensure that we can still handle one node lost when relocating.
         // Ensure that the node we eject are the one we want to relocate first.
@@ -834,18 +1232,65 @@ impl Chain {
         elders
     }
 
-    fn eldest_members_matching_prefix(
-        &self,
-        prefix: &Prefix<XorName>,
-    ) -> BTreeMap<XorName, P2pNode> {
-        self.state
+    /// Chooses the elders among our joined members matching `prefix` with the elder selection
+    /// strategy. If it chooses fewer than `elder_size` of them, the eldest of the others make up
+    /// for the missing ones.
+    fn select_elders(&self, prefix: &Prefix<XorName>) -> BTreeMap<XorName, P2pNode> {
+        let candidates: Vec<_> = self
+            .state
             .our_joined_members()
             .filter(|(name, _)| prefix.matches(name))
-            .sorted_by(|&(_, info1), &(_, info2)| Ord::cmp(&info2.age_counter, &info1.age_counter))
+            .map(|(name, info)| ElderCandidate {
+                p2p_node: &info.p2p_node,
+                age_counter: info.age_counter_value(),
+                misbehaviour_score: self
+                    .state
+                    .misbehaviour
+                    .get(name)
+                    .map_or(0, MisbehaviourRecord::score),
+                unresponsive_count: self
+                    .state
+                    .misbehaviour
+                    .get(name)
+                    .map_or(0, |record| record.count(Misbehaviour::Unresponsive)),
+                is_demoted: self.is_demoted(name),
+                elder_tenure: self.elder_tenure(name),
+            })
+            .collect();
+
+        let elder_size = self.elder_size();
+        let mut elders = BTreeMap::new();
+        let selected = self
+            .strategies
+            .elder_selection
+            .select_elders(&candidates, elder_size);
+        for name in selected
             .into_iter()
-            .map(|(name, info)| (*name, info.p2p_node.clone()))
-            .take(self.elder_size())
-            .collect()
+            .chain(EldestFirst.select_elders(&candidates, elder_size))
+        {
+            if elders.len() >= elder_size {
+                break;
+            }
+            if elders.contains_key(&name) {
+                continue;
+            }
+            if let Some(candidate) = candidates
+                .iter()
+                .find(|candidate| *candidate.name() == name)
+            {
+                let _ = elders.insert(name, candidate.p2p_node.clone());
+            }
+        }
+        elders
+    }
+
+    // Number of consecutive elder sets of our section, up to the current one, `name` is part of.
+    fn elder_tenure(&self, name: &XorName) -> usize {
+        self.state
+            .our_infos()
+            .rev()
+            .take_while(|elders_info| elders_info.member_map().contains_key(name))
+            .count()
     }
 
     /// Returns all neighbour elders.
@@ -935,7 +1380,16 @@ impl Chain {
     /// Check which nodes are unresponsive.
     pub fn check_vote_status(&mut self) -> BTreeSet<PublicId> {
         let members = self.our_info().member_ids();
-        self.chain_accumulator.check_vote_status(members)
+        self.chain_accumulator
+            .check_vote_status(members, self.network_cfg.unresponsive_threshold)
+    }
+
+    /// Check which nodes started lagging behind in voting, to report them as
+    /// `Misbehaviour::Unresponsive`.
+    pub fn check_lagging_members(&mut self) -> BTreeSet<PublicId> {
+        let members = self.state.our_info().member_ids();
+        self.chain_accumulator
+            .check_lagging(members, self.network_cfg.unresponsive_threshold)
     }
 
     /// Returns `true` if the given `NetworkEvent` is already accumulated and can be skipped.
@@ -994,8 +1448,11 @@ impl Chain {
                 }
 
                 // Do not process yet any version that is not the immediate follower of the one we have.
+                // A merged section follows both the merged ones, so it can skip versions of one.
                 let not_follow = |i: &EldersInfo| {
-                    info.prefix().is_compatible(i.prefix()) && info.version() != (i.version() + 1)
+                    info.prefix().is_compatible(i.prefix())
+                        && !i.prefix().is_extension_of(info.prefix())
+                        && info.version() != (i.version() + 1)
                 };
                 if self
                     .compatible_neighbour_info(info)
@@ -1015,7 +1472,17 @@ impl Chain {
             | AccumulatingEvent::AckMessage(_)
             | AccumulatingEvent::User(_)
             | AccumulatingEvent::Relocate(_)
-            | AccumulatingEvent::RelocatePrepare(_, _) => {
+            | AccumulatingEvent::RelocatePrepare(_, _)
+            | AccumulatingEvent::ProtocolVersion(_)
+            | AccumulatingEvent::Misbehaviour(_)
+            | AccumulatingEvent::StatsRound(_)
+            | AccumulatingEvent::NeighbourStats(_)
+            | AccumulatingEvent::ProposeMerge
+            | AccumulatingEvent::WithdrawMerge
+            | AccumulatingEvent::SiblingMerge(_)
+            | AccumulatingEvent::SignMergeKey(_)
+            | AccumulatingEvent::MergeKeySignature(_, _)
+            | AccumulatingEvent::LoadMetrics(_) => {
                 !self.state.split_in_progress && self.our_info().is_quorum(proofs)
             }
             AccumulatingEvent::StartDkg(_) => {
@@ -1093,7 +1560,7 @@ impl Chain {
         key_info: SectionKeyInfo,
         proofs: AccumulatingProof,
     ) -> Result<bool, RoutingError> {
-        // Split handling alone. wouldn't cater to merge
+        // Split handling alone: merges are handled below, once the merged info accumulated.
         if elders_info.prefix().is_extension_of(self.our_prefix()) {
             match self.state.split_cache.take() {
                 None => {
@@ -1119,6 +1586,10 @@ impl Chain {
                     Ok(true)
                 }
             }
+        } else if self.our_prefix().is_extension_of(elders_info.prefix()) {
+            self.do_add_elders_info(elders_info, key_info, proofs)?;
+            self.absorb_sibling()?;
+            Ok(true)
         } else {
             self.do_add_elders_info(elders_info, key_info, proofs)?;
             Ok(true)
@@ -1132,13 +1603,32 @@ impl Chain {
         proofs: AccumulatingProof,
     ) -> Result<(), RoutingError> {
         let is_new_elder = !self.is_elder && elders_info.is_member(&self.our_id);
-        let proof_block = self.combine_signatures_for_section_proof_block(key_info, proofs)?;
+        let mut proof_block = self.combine_signatures_for_section_proof_block(key_info, proofs)?;
+        if self.our_prefix().is_extension_of(elders_info.prefix()) {
+            match (&self.state.sibling_merge, self.merge_key_signature.take()) {
+                (Some(request), Some((signed_key_info, sig)))
+                    if signed_key_info == *proof_block.key_info()
+                        && self.is_valid_merge_key_signature(&signed_key_info, &sig) =>
+                {
+                    proof_block = proof_block.with_merged_key_info(request.key_info.clone(), sig);
+                }
+                _ => log_or_panic!(
+                    LogLevel::Error,
+                    "{} - Merged without the signature of our sibling.",
+                    self
+                ),
+            }
+        }
         let our_new_key = key_matching_first_elder_name(
             &elders_info,
             mem::replace(&mut self.new_section_bls_keys, Default::default()),
         )?;
 
-        self.state.push_our_new_info(elders_info, proof_block);
+        self.state.push_our_new_info(
+            elders_info,
+            proof_block,
+            self.network_cfg.max_their_recent_keys,
+        );
         self.our_section_bls_keys = SectionKeys::new(our_new_key, self.our_id(), self.our_info());
 
         if is_new_elder {
@@ -1150,6 +1640,33 @@ impl Chain {
         Ok(())
     }
 
+    // Takes over the members and neighbours of the sibling we just absorbed.
+    fn absorb_sibling(&mut self) -> Result<(), RoutingError> {
+        let request = match self.state.sibling_merge.take() {
+            Some(request) => request,
+            None => {
+                log_or_panic!(LogLevel::Error, "{} - Merged without a sibling.", self);
+                return Ok(());
+            }
+        };
+
+        for member in request.members {
+            if let Entry::Vacant(entry) = self.state.our_members.entry(*member.p2p_node.name()) {
+                let _ = entry.insert(member.into_member_info());
+            }
+        }
+
+        for elders_info in request.neighbour_infos {
+            if self.is_new_neighbour(&elders_info) {
+                self.add_neighbour_elders_info(elders_info)?;
+            }
+        }
+
+        // The elders of the merged section are still only ours: choose them among all members.
+        self.members_changed = true;
+        Ok(())
+    }
+
     fn add_neighbour_elders_info(&mut self, elders_info: EldersInfo) -> Result<(), RoutingError> {
         let pfx = *elders_info.prefix();
         let parent_pfx = elders_info.prefix().popped();
@@ -1252,7 +1769,8 @@ impl Chain {
             self.our_id(),
             key_info,
         );
-        self.state.update_their_keys(key_info);
+        self.state
+            .update_their_keys(key_info, self.network_cfg.max_their_recent_keys);
     }
 
     /// Returns whether we should split into two sections.
@@ -1274,9 +1792,45 @@ impl Chain {
                 }
             });
 
-        // If either of the two new sections will not contain enough entries, return `false`.
-        let safe_section_size = self.safe_section_size();
-        Ok(our_new_size >= safe_section_size && sibling_new_size >= safe_section_size)
+        // Never split into a section too small to have a full set of elders.
+        let elder_size = self.elder_size();
+        if our_new_size < elder_size || sibling_new_size < elder_size {
+            return Ok(false);
+        }
+
+        let candidate = SplitCandidate {
+            prefix: self.our_prefix(),
+            our_new_size,
+            sibling_new_size,
+            safe_section_size: self.safe_section_size(),
+            load: &self.state.load_metrics,
+        };
+        Ok(self.strategies.split.should_split(&candidate))
+    }
+
+    /// Returns the elders info of the parent of our prefix if both our section and our sibling
+    /// agreed to merge and we are the one absorbing the other. Our elders stay the elders of the
+    /// merged section until the members of our sibling joined it.
+    fn merged_elders_info(&self) -> Result<Option<EldersInfo>, RoutingError> {
+        if !self.state.merge_proposed || self.state.split_in_progress {
+            return Ok(None);
+        }
+
+        let sibling_info = match &self.state.sibling_merge {
+            Some(request)
+                if !request.withdrawn && is_absorber(self.our_info(), &request.elders_info) =>
+            {
+                &request.elders_info
+            }
+            _ => return Ok(None),
+        };
+
+        EldersInfo::new(
+            self.our_info().member_map().clone(),
+            self.our_prefix().popped(),
+            vec![self.our_info(), sibling_info],
+        )
+        .map(Some)
     }
 
     /// Splits our section and generates new elders infos for the child sections.
@@ -1286,8 +1840,8 @@ impl Chain {
         let our_prefix = self.our_prefix().pushed(next_bit);
         let other_prefix = self.our_prefix().pushed(!next_bit);
 
-        let our_new_section = self.eldest_members_matching_prefix(&our_prefix);
-        let other_section = self.eldest_members_matching_prefix(&other_prefix);
+        let our_new_section = self.select_elders(&our_prefix);
+        let other_section = self.select_elders(&other_prefix);
 
         let our_new_info =
             EldersInfo::new(our_new_section, our_prefix, Some(self.state.our_info()))?;
@@ -1313,12 +1867,11 @@ impl Chain {
                     return Some(*pfx);
                 }
 
-                // Remove older compatible neighbour prefixes.
-                // DO NOT SUPPORT MERGE: Not consider newer if the older one was extension (split).
+                // Remove older compatible neighbour prefixes. A shorter prefix is only newer than
+                // its extensions if they merged.
                 let is_newer = |(other_pfx, other_elders_info): (&Prefix<XorName>, &EldersInfo)| {
                     other_pfx.is_compatible(pfx)
                         && other_elders_info.version() > elders_info.version()
-                        && !pfx.is_extension_of(other_pfx)
                 };
 
                 if self.state.neighbour_infos.iter().any(is_newer) {
@@ -1396,6 +1949,128 @@ impl Chain {
         (best_pfx, best_info)
     }
 
+    /// Returns the elders of the section `name` belongs to, if we know it: our section, one of our
+    /// neighbours or a section from the section map.
+    pub fn section_for(&self, name: &XorName) -> Option<&EldersInfo> {
+        if self.our_prefix().matches(name) {
+            return Some(self.our_info());
+        }
+
+        self.neighbour_infos()
+            .find(|info| info.prefix().matches(name))
+            .or_else(|| self.section_map.get(name))
+    }
+
+    /// Returns a name whose section to query in order to complete or refresh the section map: a
+    /// name from a part of the network we know nothing about, or else from the section we learnt
+    /// about the longest time ago. Returns `None` if the section map is disabled or empty.
+    pub fn section_map_query_target(&self, rng: &mut MainRng) -> Option<XorName> {
+        if self.network_cfg.section_map_capacity == 0 {
+            return None;
+        }
+
+        let known: BTreeSet<_> = iter::once(*self.our_prefix())
+            .chain(self.state.neighbour_infos.keys().copied())
+            .chain(self.section_map.prefixes().copied())
+            .collect();
+        let prefix = section_map::find_gap(Prefix::default(), &known)
+            .or_else(|| self.section_map.oldest().copied())?;
+        Some(prefix.substituted_in(rng.gen()))
+    }
+
+    /// Handles the elders of another section sent in response to our query, once the message is
+    /// verified. `proof_key_info` is the last key of the proof of the message, if any. Returns
+    /// whether the section map changed.
+    pub fn handle_section_info_response(
+        &mut self,
+        elders_info: EldersInfo,
+        proof_key_info: Option<&SectionKeyInfo>,
+    ) -> bool {
+        let our_prefix = *self.our_prefix();
+        let neighbours = self.other_prefixes();
+        let is_tracked = |prefix: &Prefix<XorName>| {
+            prefix.is_compatible(&our_prefix)
+                || neighbours
+                    .iter()
+                    .any(|neighbour| neighbour.is_compatible(prefix))
+        };
+        if is_tracked(elders_info.prefix()) {
+            return false;
+        }
+
+        // The key the response was signed with, either proved by the message or already known.
+        let is_key_of = |key_info: &SectionKeyInfo| {
+            key_info.prefix() == elders_info.prefix() && key_info.version() == elders_info.version()
+        };
+        let key_info = if let Some(key_info) = proof_key_info
+            .filter(|key_info| is_key_of(key_info))
+            .or_else(|| {
+                self.get_their_key_infos()
+                    .map(|(_, key_info)| key_info)
+                    .find(|key_info| is_key_of(key_info))
+            }) {
+            key_info.clone()
+        } else {
+            trace!(
+                "{} - No key matching the section info {:?}",
+                self,
+                elders_info
+            );
+            return false;
+        };
+
+        self.section_map.retain(|prefix| !is_tracked(prefix));
+        self.section_map
+            .insert(elders_info, key_info, self.network_cfg.section_map_capacity)
+    }
+
+    // Computes the statistics of our section and the network from our current state.
+    fn compute_network_stats(&self, round: u64) -> NetworkStats {
+        let mut ages = BTreeMap::new();
+        for (_, member) in self.state.our_joined_members() {
+            *ages.entry(member.age()).or_insert(0) += 1;
+        }
+
+        let section = SectionStats {
+            prefix: *self.our_prefix(),
+            ages,
+            elders: self.our_info().member_names().copied().collect(),
+        };
+        let our_prefix = self.our_prefix();
+        let neighbours = self
+            .neighbour_stats
+            .values()
+            .filter(|stats| our_prefix.is_neighbour(&stats.section.prefix));
+
+        NetworkStats::new(round, section, neighbours)
+    }
+
+    /// Returns whether the statistics are from one of our neighbours and newer than the ones we
+    /// have from it.
+    pub fn is_new_neighbour_stats(&self, stats: &NetworkStats) -> bool {
+        self.our_prefix().is_neighbour(&stats.section.prefix)
+            && self
+                .neighbour_stats
+                .get(&stats.section.prefix)
+                .map_or(true, |old_stats| old_stats.round < stats.round)
+    }
+
+    // Records the statistics a neighbour agreed on, unless we already have newer ones from it.
+    // Returns whether they were recorded.
+    fn add_neighbour_stats(&mut self, stats: &NetworkStats) -> bool {
+        if !self.is_new_neighbour_stats(stats) {
+            return false;
+        }
+
+        let prefix = stats.section.prefix;
+        let our_prefix = *self.our_prefix();
+        self.neighbour_stats.retain(|old_prefix, _| {
+            !old_prefix.is_compatible(&prefix) && our_prefix.is_neighbour(old_prefix)
+        });
+        let _ = self.neighbour_stats.insert(prefix, stats.clone());
+        true
+    }
+
     /// Returns the known sections sorted by the distance from a given XorName.
     fn closest_sections_info(&self, name: XorName) -> Vec<(&Prefix<XorName>, &EldersInfo)> {
         let mut result: Vec<_> = iter::once((self.our_prefix(), self.our_info()))
@@ -1595,6 +2270,18 @@ impl Chain {
             }
         })
     }
+
+    /// Returns the number of nodes we know but have not yet processed.
+    pub fn online_backlog_len(&self) -> usize {
+        self.state
+            .churn_event_backlog
+            .iter()
+            .filter(|evt| match evt.content {
+                AccumulatingEvent::Online(_) => true,
+                _ => false,
+            })
+            .count()
+    }
 }
 
 impl Debug for Chain {
@@ -1703,13 +2390,6 @@ impl Chain {
     }
 }
 
-#[cfg(test)]
-impl Chain {
-    pub fn validate_our_history(&self) -> bool {
-        self.state.our_history.validate()
-    }
-}
-
 fn key_matching_first_elder_name(
     elders_info: &EldersInfo,
     mut name_to_key: BTreeMap<XorName, DkgResult>,
@@ -1743,11 +2423,12 @@ pub struct ParsecResetData {
 }
 
 /// The secret share of the section key.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SectionKeyShare {
     /// Index used to combine signature share and get PublicKeyShare from PublicKeySet.
     pub index: usize,
     /// Secret Key share
+    #[serde(with = "serde_secret_key_share")]
     pub key: bls::SecretKeyShare,
 }
 
@@ -1772,7 +2453,7 @@ impl SectionKeyShare {
 }
 
 /// All the key material needed to sign or combine signature for our section key.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SectionKeys {
     /// Public key set to verify threshold signatures and combine shares.
     pub public_key_set: bls::PublicKeySet,
@@ -1791,6 +2472,73 @@ impl SectionKeys {
     }
 }
 
+// Serde support for secret key shares, which `bls` only serialises via `SerdeSecret`.
+mod serde_secret_key_share {
+    use bls::serde_impl::SerdeSecret;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        key: &bls::SecretKeyShare,
+        serialiser: S,
+    ) -> Result<S::Ok, S::Error> {
+        SerdeSecret(key.clone()).serialize(serialiser)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserialiser: D,
+    ) -> Result<bls::SecretKeyShare, D::Error> {
+        let key: SerdeSecret<bls::SecretKeyShare> = Deserialize::deserialize(deserialiser)?;
+        Ok(key.0)
+    }
+}
+
+// Serde support for the pending DKG results. `DkgResultWrapper` only serialises the public part, so
+// the secret share is serialised separately here.
+mod serde_dkg_results {
+    use crate::{parsec::DkgResult, XorName};
+    use bls::serde_impl::SerdeSecret;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::BTreeMap;
+
+    type Repr = Vec<(
+        XorName,
+        bls::PublicKeySet,
+        Option<SerdeSecret<bls::SecretKeyShare>>,
+    )>;
+
+    pub fn serialize<S: Serializer>(
+        results: &BTreeMap<XorName, DkgResult>,
+        serialiser: S,
+    ) -> Result<S::Ok, S::Error> {
+        let repr: Repr = results
+            .iter()
+            .map(|(name, result)| {
+                (
+                    *name,
+                    result.public_key_set.clone(),
+                    result.secret_key_share.clone().map(SerdeSecret),
+                )
+            })
+            .collect();
+        repr.serialize(serialiser)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserialiser: D,
+    ) -> Result<BTreeMap<XorName, DkgResult>, D::Error> {
+        let repr: Repr = Deserialize::deserialize(deserialiser)?;
+        Ok(repr
+            .into_iter()
+            .map(|(name, public_key_set, secret_key_share)| {
+                (
+                    name,
+                    DkgResult::new(public_key_set, secret_key_share.map(|key| key.0)),
+                )
+            })
+            .collect())
+    }
+}
+
 struct EldersChangeBuilder {
     old_neighbour: BTreeSet<P2pNode>,
 }
@@ -1820,22 +2568,26 @@ impl EldersChangeBuilder {
 }
 
 #[cfg(test)]
-mod tests {
+pub(super) mod tests {
     use super::{
-        super::{EldersInfo, GenesisPfxInfo, MIN_AGE_COUNTER},
+        super::{EldersInfo, GenesisPfxInfo, MIN_AGE, MIN_AGE_COUNTER},
         *,
     };
     use crate::{
+        ageing::{AgeingStrategy, BalancedRelocation},
+        elder_selection::{ElderSelection, EldestFirst},
         id::{FullId, P2pNode, PublicId},
         parsec::generate_bls_threshold_secret_key,
         rng::{self, MainRng},
+        split::SplitPolicy,
         unwrap,
         xor_space::{Prefix, XorName},
         ConnectionInfo,
     };
     use rand::{seq::SliceRandom, Rng};
     use std::{
-        collections::{BTreeMap, HashMap},
+        collections::{BTreeMap, BTreeSet, HashMap},
+        rc::Rc,
         str::FromStr,
     };
 
@@ -1845,6 +2597,26 @@ mod tests {
         Remove(&'a EldersInfo),
     }
 
+    pub(crate) fn gen_p2p_node(rng: &mut MainRng, prefix: Prefix<XorName>) -> P2pNode {
+        let full_id = FullId::within_range(rng, &prefix.range_inclusive());
+        let connection_info = ConnectionInfo {
+            peer_addr: ([127, 0, 0, 1], 9999).into(),
+            peer_cert_der: vec![],
+        };
+        P2pNode::new(*full_id.public_id(), connection_info)
+    }
+
+    // Generates the `EldersInfo` of a section with a single elder.
+    pub(crate) fn gen_elders_info(prefix: &str, version: u64) -> EldersInfo {
+        let mut rng = rng::new();
+        let prefix: Prefix<XorName> = unwrap!(Prefix::from_str(prefix));
+        let p2p_node = gen_p2p_node(&mut rng, prefix);
+        let members = vec![(*p2p_node.public_id(), p2p_node)]
+            .into_iter()
+            .collect();
+        unwrap!(EldersInfo::new_for_test(members, prefix, version))
+    }
+
     fn gen_section_info(
         rng: &mut MainRng,
         gen: SecInfoGen,
@@ -2023,4 +2795,237 @@ mod tests {
             check_infos_for_duplication(&chain);
         }
     }
+
+    #[test]
+    fn departed_member_rejoins_with_half_age() {
+        let mut rng = rng::new();
+        let (mut chain, _, _) = gen_00_chain(&mut rng);
+        let prefix = unwrap!(Prefix::from_str("00"));
+        let node = gen_p2p_node(&mut rng, prefix);
+        let other_node = gen_p2p_node(&mut rng, prefix);
+
+        chain.add_member(node.clone(), 10, VersionRange::OURS);
+        let _ = chain.remove_member(node.public_id());
+        chain.add_member(node.clone(), MIN_AGE, VersionRange::OURS);
+        assert_eq!(chain.state.our_members[node.name()].age(), 5);
+
+        // Members that departed longer than the rejoin window ago start again from the minimum age.
+        chain.network_cfg.rejoin_window = 2;
+        let _ = chain.remove_member(node.public_id());
+        chain.add_member(other_node.clone(), MIN_AGE, VersionRange::OURS);
+        let _ = chain.remove_member(other_node.public_id());
+        chain.add_member(node.clone(), MIN_AGE, VersionRange::OURS);
+        assert_eq!(chain.state.our_members[node.name()].age(), MIN_AGE);
+    }
+
+    // Ages members twice as fast and relocates them all to section `1` on every churn.
+    struct FastAgeing;
+
+    impl AgeingStrategy for FastAgeing {
+        fn increment_age_counter(&self, _: &XorName, age_counter: u32, _: &Churn) -> u32 {
+            age_counter + 2
+        }
+
+        fn should_relocate(&self, _: u32, _: u32) -> bool {
+            true
+        }
+
+        fn relocation_destination(&self, _: &XorName, _: &Churn) -> XorName {
+            XorName([255; 32])
+        }
+    }
+
+    // Splits as soon as both halves can have a full set of elders, if the section stores too much.
+    struct StorageSplit;
+
+    impl SplitPolicy for StorageSplit {
+        fn should_split(&self, candidate: &SplitCandidate) -> bool {
+            candidate.load.get("stored_bytes") > 1000
+        }
+    }
+
+    #[test]
+    fn custom_split_policy() {
+        let mut rng = rng::new();
+        let (chain, _, _) = gen_00_chain(&mut rng);
+        let mut chain = chain.with_strategies(Strategies {
+            split: Rc::new(StorageSplit),
+            ..Strategies::default()
+        });
+        for prefix in &["000", "001"] {
+            let prefix: Prefix<XorName> = unwrap!(Prefix::from_str(prefix));
+            for _ in 0..chain.elder_size() {
+                let p2p_node = gen_p2p_node(&mut rng, prefix);
+                chain.add_member(p2p_node, MIN_AGE, VersionRange::OURS);
+            }
+        }
+        assert!(!unwrap!(chain.should_split()));
+
+        chain.state.load_metrics = LoadMetrics(
+            vec![("stored_bytes".to_string(), 5000)]
+                .into_iter()
+                .collect(),
+        );
+        assert!(unwrap!(chain.should_split()));
+
+        // The default policy still waits for both halves to reach the safe section size.
+        let chain = chain.with_strategies(Strategies::default());
+        assert!(!unwrap!(chain.should_split()));
+    }
+
+    // Chooses the eldest members which have not been elders for `max_tenure` elder sets yet.
+    struct TenureCap {
+        max_tenure: usize,
+    }
+
+    impl ElderSelection for TenureCap {
+        fn select_elders(&self, candidates: &[ElderCandidate], elder_size: usize) -> Vec<XorName> {
+            let eligible: Vec<_> = candidates
+                .iter()
+                .filter(|candidate| candidate.elder_tenure < self.max_tenure)
+                .cloned()
+                .collect();
+            EldestFirst.select_elders(&eligible, elder_size)
+        }
+    }
+
+    #[test]
+    fn custom_elder_selection() {
+        let mut rng = rng::new();
+        let (chain, _, _) = gen_00_chain(&mut rng);
+        let mut chain = chain.with_strategies(Strategies {
+            elder_selection: Rc::new(TenureCap { max_tenure: 1 }),
+            ..Strategies::default()
+        });
+        let old_elders: BTreeSet<_> = chain.our_info().member_names().cloned().collect();
+        for name in &old_elders {
+            if let Some(info) = chain.state.our_members.get_mut(name) {
+                info.set_age(MIN_AGE + 2);
+            }
+        }
+        let prefix: Prefix<XorName> = unwrap!(Prefix::from_str("00"));
+        let mut new_members = BTreeSet::new();
+        for _ in 0..chain.elder_size() {
+            let p2p_node = gen_p2p_node(&mut rng, prefix);
+            let _ = new_members.insert(*p2p_node.name());
+            chain.add_member(p2p_node, MIN_AGE, VersionRange::OURS);
+        }
+
+        let expected: BTreeSet<_> = chain.our_expected_elders().keys().cloned().collect();
+        assert_eq!(expected, new_members);
+
+        // The oldest members stay elders with the default strategy.
+        let chain = chain.with_strategies(Strategies::default());
+        let expected: BTreeSet<_> = chain.our_expected_elders().keys().cloned().collect();
+        assert_eq!(expected, old_elders);
+    }
+
+    // Chooses no elder at all.
+    struct NoElders;
+
+    impl ElderSelection for NoElders {
+        fn select_elders(&self, _: &[ElderCandidate], _: usize) -> Vec<XorName> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn short_elder_selection_is_topped_up() {
+        let mut rng = rng::new();
+        let (chain, _, _) = gen_00_chain(&mut rng);
+        let expected: BTreeSet<_> = chain.our_expected_elders().keys().cloned().collect();
+
+        let chain = chain.with_strategies(Strategies {
+            elder_selection: Rc::new(NoElders),
+            ..Strategies::default()
+        });
+        let actual: BTreeSet<_> = chain.our_expected_elders().keys().cloned().collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn custom_ageing_strategy() {
+        let mut rng = rng::new();
+        let (chain, _, _) = gen_00_chain(&mut rng);
+        let mut chain = chain.with_strategies(Strategies {
+            ageing: Rc::new(FastAgeing),
+            ..Strategies::default()
+        });
+        let trigger = *chain.our_id();
+        let others: Vec<_> = chain
+            .state
+            .our_joined_members()
+            .filter(|(_, info)| *info.p2p_node.public_id() != trigger)
+            .map(|(name, info)| (*name, info.age_counter_value()))
+            .collect();
+
+        chain.increment_age_counters(&trigger);
+
+        assert_eq!(chain.state.relocate_queue.len(), others.len());
+        assert!(chain
+            .state
+            .relocate_queue
+            .iter()
+            .all(|details| details.destination == XorName([255; 32])));
+        for (name, age_counter) in others {
+            assert_eq!(
+                chain.state.our_members[&name].age_counter_value(),
+                age_counter + 2
+            );
+        }
+    }
+
+    #[test]
+    fn balanced_relocation() {
+        let mut rng = rng::new();
+        let (chain, _, _) = gen_chain(
+            &mut rng,
+            vec![
+                (Prefix::from_str("00").unwrap(), 7),
+                (Prefix::from_str("01").unwrap(), 3),
+                (Prefix::from_str("10").unwrap(), 7),
+            ],
+        );
+        let mut chain = chain.with_strategies(Strategies {
+            ageing: Rc::new(BalancedRelocation::new(FastAgeing)),
+            ..Strategies::default()
+        });
+        chain.network_cfg.safe_section_size = 7;
+        let trigger = *chain.our_id();
+
+        // Section `01` told us it has two adults besides its three elders.
+        let prefix = unwrap!(Prefix::from_str("01"));
+        let section = SectionStats {
+            prefix,
+            ages: iter::once((5, 5)).collect(),
+            elders: BTreeSet::new(),
+        };
+        assert!(chain.add_neighbour_stats(&NetworkStats::new(1, section, iter::empty())));
+
+        chain.increment_age_counters(&trigger);
+
+        // Section `01` is the only one below the safe size and gets relocated members until it
+        // reaches it. The remaining ones go where the ageing strategy sends them.
+        let destinations: Vec<_> = chain
+            .state
+            .relocate_queue
+            .iter()
+            .map(|details| details.destination)
+            .collect();
+        assert_eq!(destinations.len(), 6);
+        assert_eq!(
+            destinations
+                .iter()
+                .filter(|name| prefix.matches(name))
+                .count(),
+            2
+        );
+        assert_eq!(
+            destinations
+                .iter()
+                .filter(|name| **name == XorName([255; 32]))
+                .count(),
+            4
+        );
+    }
 }
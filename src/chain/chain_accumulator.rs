@@ -15,19 +15,22 @@ use std::{
     rc::Rc,
 };
 
-/// An unresponsive node is detected by conunting how many (defined by UNRESPONSIVE_THRESHOLD)
-/// missed votes among the certain number (defined by UNRESPONSIVE_WINDOW) of recent consensused
+/// An unresponsive node is detected by conunting how many (defined by `unresponsive_threshold`)
+/// missed votes among the certain number (defined by `unresponsive_window`) of recent consensused
 /// observations.
 
-/// The threshold (number of unvoted votes) a node to be considered as unresponsive.
+/// The default threshold (number of unvoted votes) a node to be considered as unresponsive.
 pub const UNRESPONSIVE_THRESHOLD: usize = 48;
-/// The period (X consensued observations) during which node be considered as unresponsive.
+/// The default period (X consensued observations) during which node be considered as
+/// unresponsive.
 pub const UNRESPONSIVE_WINDOW: usize = 64;
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 struct VoteStatuses {
     tracked_events: VecDeque<Rc<AccumulatingEvent>>,
     unvoted: BTreeMap<PublicId, BTreeSet<Rc<AccumulatingEvent>>>,
+    /// Peers already reported as lagging, until they catch up again.
+    lagging: BTreeSet<PublicId>,
 }
 
 impl VoteStatuses {
@@ -36,6 +39,7 @@ impl VoteStatuses {
         event: AccumulatingEvent,
         non_voters: BTreeSet<PublicId>,
         all_members: &BTreeSet<PublicId>,
+        unresponsive_window: usize,
     ) {
         let event_rc = Rc::new(event);
         for id in non_voters {
@@ -45,7 +49,7 @@ impl VoteStatuses {
         self.tracked_events.push_back(event_rc);
 
         // Pruning old events
-        if self.tracked_events.len() > UNRESPONSIVE_WINDOW {
+        if self.tracked_events.len() > unresponsive_window {
             if let Some(removed_event) = self.tracked_events.pop_front() {
                 for events in self.unvoted.values_mut() {
                     let _ = events.remove(&removed_event);
@@ -68,16 +72,20 @@ impl VoteStatuses {
         }
     }
 
-    fn is_unresponsive(&self, peer: &PublicId) -> bool {
+    fn is_unresponsive(&self, peer: &PublicId, unresponsive_threshold: usize) -> bool {
         if let Some(events) = self.unvoted.get(peer) {
-            events.len() > UNRESPONSIVE_THRESHOLD
+            events.len() > unresponsive_threshold
         } else {
             false
         }
     }
+
+    fn is_lagging(&self, peer: &PublicId, unresponsive_threshold: usize) -> bool {
+        self.is_unresponsive(peer, unresponsive_threshold / 2)
+    }
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub(super) struct ChainAccumulator {
     /// A map containing network events that have not been handled yet, together with their proofs
     /// that have been collected so far. We are still waiting for more proofs, or to reach a state
@@ -138,6 +146,7 @@ impl ChainAccumulator {
         &mut self,
         event: AccumulatingEvent,
         all_voters: BTreeSet<PublicId>,
+        unresponsive_window: usize,
     ) -> Option<(AccumulatingEvent, AccumulatingProof)> {
         let proofs = self.chain_accumulator.remove(&event)?;
 
@@ -145,7 +154,7 @@ impl ChainAccumulator {
             log_or_panic!(LogLevel::Warn, "Duplicate insert in completed events.");
         }
 
-        self.add_expectation(event.clone(), &proofs, all_voters);
+        self.add_expectation(event.clone(), &proofs, all_voters, unresponsive_window);
 
         Some((event, proofs))
     }
@@ -178,24 +187,49 @@ impl ChainAccumulator {
         event: AccumulatingEvent,
         proofs: &AccumulatingProof,
         all_voters: BTreeSet<PublicId>,
+        unresponsive_window: usize,
     ) {
         let mut non_voted = all_voters.clone();
         for id in proofs.parsec_proof_set().ids() {
             let _ = non_voted.remove(id);
         }
         self.vote_statuses
-            .add_expectation(event, non_voted, &all_voters);
+            .add_expectation(event, non_voted, &all_voters, unresponsive_window);
     }
 
     pub fn check_vote_status<'a>(
         &self,
         members: impl Iterator<Item = &'a PublicId>,
+        unresponsive_threshold: usize,
     ) -> BTreeSet<PublicId> {
         members
-            .filter(|peer_id| self.vote_statuses.is_unresponsive(peer_id))
+            .filter(|peer_id| {
+                self.vote_statuses
+                    .is_unresponsive(peer_id, unresponsive_threshold)
+            })
             .cloned()
             .collect()
     }
+
+    /// Returns the members which started missing more than half the votes
+    /// `unresponsive_threshold` allows since the last call.
+    pub fn check_lagging<'a>(
+        &mut self,
+        members: impl Iterator<Item = &'a PublicId>,
+        unresponsive_threshold: usize,
+    ) -> BTreeSet<PublicId> {
+        let vote_statuses = &mut self.vote_statuses;
+        let lagging: BTreeSet<_> = members
+            .filter(|peer_id| vote_statuses.is_lagging(peer_id, unresponsive_threshold))
+            .cloned()
+            .collect();
+        let newly_lagging = lagging
+            .difference(&vote_statuses.lagging)
+            .cloned()
+            .collect();
+        vote_statuses.lagging = lagging;
+        newly_lagging
+    }
 }
 
 #[derive(Default, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Serialize, Deserialize)]
@@ -415,7 +449,7 @@ mod test {
         let _ = acc.insert_with_proof_set(data.event.clone(), data.proofs.clone());
 
         let event_to_poll = unwrap!(acc.incomplete_events().next()).0.clone();
-        let result = acc.poll_event(event_to_poll, Default::default());
+        let result = acc.poll_event(event_to_poll, Default::default(), UNRESPONSIVE_WINDOW);
 
         assert_eq!(result, Some((data.event, data.acc_proofs)));
         assert_eq!(incomplete_events(&acc), vec![]);
@@ -455,7 +489,7 @@ mod test {
     fn re_insert_with_proof_set_after_poll(data: TestData, data2: TestData) {
         let mut acc = ChainAccumulator::default();
         let _ = acc.insert_with_proof_set(data.event.clone(), data.proofs.clone());
-        let _ = acc.poll_event(data.event.clone(), Default::default());
+        let _ = acc.poll_event(data.event.clone(), Default::default(), UNRESPONSIVE_WINDOW);
 
         let result = acc.insert_with_proof_set(data.event, data2.proofs);
 
@@ -528,7 +562,7 @@ mod test {
     fn re_add_proof_after_poll(data: TestData) {
         let mut acc = ChainAccumulator::default();
         let _ = acc.add_proof(data.event.clone(), data.first_proof, data.signature.clone());
-        let _ = acc.poll_event(data.event.clone(), Default::default());
+        let _ = acc.poll_event(data.event.clone(), Default::default(), UNRESPONSIVE_WINDOW);
 
         let result = acc.add_proof(data.event, data.first_proof, data.signature);
 
@@ -551,7 +585,7 @@ mod test {
     fn reset_all_completed(data: TestData) {
         let mut acc = ChainAccumulator::default();
         let _ = acc.add_proof(data.event.clone(), data.first_proof, data.signature.clone());
-        let _ = acc.poll_event(data.event.clone(), Default::default());
+        let _ = acc.poll_event(data.event.clone(), Default::default(), UNRESPONSIVE_WINDOW);
 
         let result = acc.reset_accumulator(&data.our_id);
 
@@ -641,7 +675,7 @@ mod test {
             let event = AccumulatingEvent::User([i as u8].to_vec());
             for (index, (id, proof)) in ids_and_proofs.iter().enumerate() {
                 if index == polling_point {
-                    let _ = acc.poll_event(event.clone(), members.clone());
+                    let _ = acc.poll_event(event.clone(), members.clone(), UNRESPONSIVE_WINDOW);
                 }
                 if i >= (UNRESPONSIVE_WINDOW - UNRESPONSIVE_THRESHOLD - 1)
                     && id == &unresponsive_node
@@ -653,7 +687,7 @@ mod test {
         }
 
         let expected: BTreeSet<_> = iter::once(unresponsive_node).collect();
-        let detected = acc.check_vote_status(members.iter());
+        let detected = acc.check_vote_status(members.iter(), UNRESPONSIVE_THRESHOLD);
         assert_eq!(detected, expected);
     }
 }
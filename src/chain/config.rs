@@ -6,15 +6,178 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{ELDER_SIZE, SAFE_SECTION_SIZE};
+use super::{
+    chain_accumulator::{UNRESPONSIVE_THRESHOLD, UNRESPONSIVE_WINDOW},
+    member_info::REJOIN_WINDOW,
+    misbehaviour::{
+        MISBEHAVIOUR_AGE_PENALTY_SCORE, MISBEHAVIOUR_DEMOTE_SCORE, MISBEHAVIOUR_EVICT_SCORE,
+    },
+    section_map::{SECTION_MAP_CAPACITY, SECTION_MAP_QUERY_INTERVAL},
+    shared_state::MAX_THEIR_RECENT_KEYS,
+};
+use crate::{
+    crypto::{self, Digest256},
+    error::RoutingError,
+    join_admission::{
+        JOIN_RETRY_DELAY, JOIN_THROTTLE_INTERVAL, MAX_CONCURRENT_JOINS, MAX_INFANTS_PER_ADULT,
+    },
+    network_service::MAX_RESENDS,
+    parsec::PARSEC_SIZE_LIMIT,
+    pending_deliveries::{DELIVERY_RECEIPT_TIMEOUT, MAX_DELIVERY_ATTEMPTS},
+    rate_limiter::{SPAM_INTERVAL, SPAM_MESSAGE_LIMIT},
+    routing_message_filter::{INCOMING_EXPIRY_DURATION, OUTGOING_EXPIRY_DURATION},
+    signature_accumulator::ACCUMULATION_TIMEOUT,
+    snapshot::SNAPSHOT_INTERVAL,
+    states::{
+        BOOTSTRAP_TIMEOUT, GOSSIP_TIMEOUT, JOIN_TIMEOUT, RESOURCE_PROOF_DIFFICULTY,
+        RESOURCE_PROOF_TARGET_SIZE, RESOURCE_PROOF_TIMEOUT,
+    },
+    time::Duration,
+    ELDER_SIZE, SAFE_SECTION_SIZE,
+};
 
-/// Network parameters: number of elders, safe section size
-#[derive(Clone, Copy, Debug)]
+/// Network parameters.
+///
+/// The values marked as consensus-critical must be the same on all the nodes of the network. A
+/// section ignores join requests from nodes that don't agree with its own values.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct NetworkParams {
-    /// The number of elders per section
+    /// The number of elders per section. Consensus-critical.
     pub elder_size: usize,
-    /// Minimum number of nodes we consider safe in a section
+    /// Minimum number of nodes we consider safe in a section. Consensus-critical.
     pub safe_section_size: usize,
+    /// Number of missed votes among the last `unresponsive_window` consensused observations
+    /// above which an elder is considered unresponsive. Consensus-critical.
+    pub unresponsive_threshold: usize,
+    /// Number of recent consensused observations considered when detecting unresponsive elders.
+    /// Consensus-critical.
+    pub unresponsive_window: usize,
+    /// Number of previous keys of other sections to keep, so messages signed with them can still
+    /// be verified. Consensus-critical.
+    pub max_their_recent_keys: usize,
+    /// Estimated size (in bytes) of the parsec graph above which the section votes to prune it.
+    /// Consensus-critical.
+    pub parsec_size_limit: u64,
+    /// Misbehaviour score from which each further misbehaviour costs a member one age.
+    /// Consensus-critical.
+    pub misbehaviour_age_penalty_score: u32,
+    /// Misbehaviour score from which a member is no longer considered for elder.
+    /// Consensus-critical.
+    pub misbehaviour_demote_score: u32,
+    /// Misbehaviour score from which a member is evicted from its section. Consensus-critical.
+    pub misbehaviour_evict_score: u32,
+    /// Number of membership changes of a section during which a member that left it can rejoin
+    /// with half its age instead of starting again from the minimum age. Consensus-critical.
+    pub rejoin_window: u64,
+    /// Time between two rounds of statistics of a section and the network agreed on by the
+    /// section, or `None` for sections not to agree on statistics. Consensus-critical.
+    pub network_stats_interval: Option<Duration>,
+    /// Number of joined members below which a section and its sibling merge, once both agree
+    /// they are below it. Zero disables merging. Consensus-critical.
+    pub merge_threshold: usize,
+    /// Time within which a message and a quorum of its signatures need to arrive to accumulate.
+    pub accumulation_timeout: Duration,
+    /// Time to wait for a response to a bootstrap request before trying another node.
+    pub bootstrap_timeout: Duration,
+    /// Time to wait for the approval of a join request before restarting.
+    pub join_timeout: Duration,
+    /// Time between two parsec gossips of an elder.
+    pub gossip_timeout: Duration,
+    /// Number of times sending a message to a peer is retried before trying another one.
+    pub max_resends: u8,
+    /// Time during which an incoming message is remembered, so it is not handled again.
+    pub incoming_filter_expiry: Duration,
+    /// Time during which an outgoing message is remembered, so it is not sent to the same peer
+    /// again.
+    pub outgoing_filter_expiry: Duration,
+    /// Difficulty of the resource proof challenge joining peers have to solve.
+    pub resource_proof_difficulty: u8,
+    /// Minimum size (in bytes) of the data the resource proof of joining peers is computed over.
+    pub resource_proof_target_size: usize,
+    /// Time within which a joining peer has to solve its resource proof challenge.
+    pub resource_proof_timeout: Duration,
+    /// Maximum number of peers joining the section at the same time.
+    pub max_concurrent_joins: usize,
+    /// Maximum number of infants per adult (or elder) in the section. New peers are refused
+    /// while the section has more infants.
+    pub max_infants_per_adult: usize,
+    /// Minimum time between two joins from the same IP address.
+    pub join_throttle_interval: Duration,
+    /// Time a peer refused because the section is busy should wait before retrying.
+    pub join_retry_delay: Duration,
+    /// Maximum number of sections, besides ours and our neighbours, an elder remembers the elders
+    /// and keys of. If zero, elders don't query other sections.
+    pub section_map_capacity: usize,
+    /// Time between two queries of an elder for the elders of another section.
+    pub section_map_query_interval: Duration,
+    /// Time between two snapshots of the node state, if the node takes snapshots.
+    pub snapshot_interval: Duration,
+    /// Time to wait for the delivery receipt of a message before re-sending it.
+    pub delivery_receipt_timeout: Duration,
+    /// Number of times a message is sent before giving up waiting for its delivery receipt.
+    pub max_delivery_attempts: u8,
+    /// Time over which the messages from each member are counted to detect spam.
+    pub spam_interval: Duration,
+    /// Maximum number of messages a member can send within `spam_interval` before it is voted
+    /// for spam.
+    pub spam_message_limit: usize,
+}
+
+impl NetworkParams {
+    /// Checks the parameters are consistent with each other.
+    pub fn validate(&self) -> Result<(), RoutingError> {
+        let is_valid = self.elder_size > 0
+            && self.safe_section_size >= self.elder_size
+            && self.unresponsive_threshold < self.unresponsive_window
+            && self.parsec_size_limit > 0
+            && self.misbehaviour_age_penalty_score > 0
+            && self.misbehaviour_age_penalty_score <= self.misbehaviour_demote_score
+            && self.misbehaviour_demote_score <= self.misbehaviour_evict_score
+            && self.accumulation_timeout > Duration::from_secs(0)
+            && self.bootstrap_timeout > Duration::from_secs(0)
+            && self.join_timeout > Duration::from_secs(0)
+            && self.gossip_timeout > Duration::from_secs(0)
+            && self.resource_proof_timeout > Duration::from_secs(0)
+            && self.max_concurrent_joins > 0
+            && self.max_infants_per_adult > 0
+            && self.section_map_query_interval > Duration::from_secs(0)
+            && self.snapshot_interval > Duration::from_secs(0)
+            && self.delivery_receipt_timeout > Duration::from_secs(0)
+            && self.max_delivery_attempts > 0
+            && self.spam_interval > Duration::from_secs(0)
+            && self.spam_message_limit > 0
+            && self
+                .network_stats_interval
+                .map_or(true, |interval| interval > Duration::from_secs(0))
+            && self.merge_threshold <= self.safe_section_size;
+
+        if is_valid {
+            Ok(())
+        } else {
+            Err(RoutingError::InvalidNetworkParams)
+        }
+    }
+
+    /// Hash of the consensus-critical values, to check other nodes agree with them.
+    pub(crate) fn consensus_digest(&self) -> Digest256 {
+        let values = (
+            self.elder_size as u64,
+            self.safe_section_size as u64,
+            self.unresponsive_threshold as u64,
+            self.unresponsive_window as u64,
+            self.max_their_recent_keys as u64,
+            self.parsec_size_limit,
+            self.misbehaviour_age_penalty_score,
+            self.misbehaviour_demote_score,
+            self.misbehaviour_evict_score,
+            self.rejoin_window,
+            self.network_stats_interval,
+            self.merge_threshold as u64,
+        );
+        // Serialising integers can't fail.
+        let bytes = bincode::serialize(&values).unwrap_or_default();
+        crypto::sha3_256(&bytes)
+    }
 }
 
 impl Default for NetworkParams {
@@ -22,6 +185,105 @@ impl Default for NetworkParams {
         Self {
             elder_size: ELDER_SIZE,
             safe_section_size: SAFE_SECTION_SIZE,
+            unresponsive_threshold: UNRESPONSIVE_THRESHOLD,
+            unresponsive_window: UNRESPONSIVE_WINDOW,
+            max_their_recent_keys: MAX_THEIR_RECENT_KEYS,
+            parsec_size_limit: PARSEC_SIZE_LIMIT,
+            misbehaviour_age_penalty_score: MISBEHAVIOUR_AGE_PENALTY_SCORE,
+            misbehaviour_demote_score: MISBEHAVIOUR_DEMOTE_SCORE,
+            misbehaviour_evict_score: MISBEHAVIOUR_EVICT_SCORE,
+            rejoin_window: REJOIN_WINDOW,
+            network_stats_interval: None,
+            merge_threshold: 0,
+            accumulation_timeout: ACCUMULATION_TIMEOUT,
+            bootstrap_timeout: BOOTSTRAP_TIMEOUT,
+            join_timeout: JOIN_TIMEOUT,
+            gossip_timeout: GOSSIP_TIMEOUT,
+            max_resends: MAX_RESENDS,
+            incoming_filter_expiry: INCOMING_EXPIRY_DURATION,
+            outgoing_filter_expiry: OUTGOING_EXPIRY_DURATION,
+            resource_proof_difficulty: RESOURCE_PROOF_DIFFICULTY,
+            resource_proof_target_size: RESOURCE_PROOF_TARGET_SIZE,
+            resource_proof_timeout: RESOURCE_PROOF_TIMEOUT,
+            max_concurrent_joins: MAX_CONCURRENT_JOINS,
+            max_infants_per_adult: MAX_INFANTS_PER_ADULT,
+            join_throttle_interval: JOIN_THROTTLE_INTERVAL,
+            join_retry_delay: JOIN_RETRY_DELAY,
+            section_map_capacity: SECTION_MAP_CAPACITY,
+            section_map_query_interval: SECTION_MAP_QUERY_INTERVAL,
+            snapshot_interval: SNAPSHOT_INTERVAL,
+            delivery_receipt_timeout: DELIVERY_RECEIPT_TIMEOUT,
+            max_delivery_attempts: MAX_DELIVERY_ATTEMPTS,
+            spam_interval: SPAM_INTERVAL,
+            spam_message_limit: SPAM_MESSAGE_LIMIT,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate() {
+        assert!(NetworkParams::default().validate().is_ok());
+
+        let params = NetworkParams {
+            safe_section_size: ELDER_SIZE - 1,
+            ..Default::default()
+        };
+        assert!(params.validate().is_err());
+
+        let params = NetworkParams {
+            unresponsive_threshold: UNRESPONSIVE_WINDOW,
+            ..Default::default()
+        };
+        assert!(params.validate().is_err());
+
+        let params = NetworkParams {
+            misbehaviour_evict_score: MISBEHAVIOUR_DEMOTE_SCORE - 1,
+            ..Default::default()
+        };
+        assert!(params.validate().is_err());
+
+        let params = NetworkParams {
+            network_stats_interval: Some(Duration::from_secs(0)),
+            ..Default::default()
+        };
+        assert!(params.validate().is_err());
+
+        let params = NetworkParams {
+            merge_threshold: SAFE_SECTION_SIZE + 1,
+            ..Default::default()
+        };
+        assert!(params.validate().is_err());
+
+        let params = NetworkParams {
+            max_delivery_attempts: 0,
+            ..Default::default()
+        };
+        assert!(params.validate().is_err());
+    }
+
+    #[test]
+    fn consensus_digest() {
+        let params = NetworkParams::default();
+        let other_params = NetworkParams {
+            gossip_timeout: params.gossip_timeout * 2,
+            ..params
+        };
+        assert_eq!(params.consensus_digest(), other_params.consensus_digest());
+
+        let other_params = NetworkParams {
+            max_their_recent_keys: params.max_their_recent_keys + 1,
+            ..params
+        };
+        assert_ne!(params.consensus_digest(), other_params.consensus_digest());
+
+        let other_params = NetworkParams {
+            network_stats_interval: Some(Duration::from_secs(60)),
+            ..params
+        };
+        assert_ne!(params.consensus_digest(), other_params.consensus_digest());
+    }
+}
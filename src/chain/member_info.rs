@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::P2pNode;
+use crate::{id::PublicId, messages::VersionRange, P2pNode};
 
 /// The type for counting the churn events experienced by a node
 #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
@@ -24,6 +24,7 @@ impl AgeCounter {
     }
 
     /// Increment the counter and return whether the age increased.
+    #[cfg(test)]
     pub fn increment(&mut self) -> bool {
         if let Some(new_value) = self.0.checked_add(1) {
             self.0 = new_value;
@@ -48,21 +49,28 @@ pub const MIN_AGE: u8 = 4;
 
 const MAX_INFANT_AGE: u32 = MIN_AGE as u32;
 
+/// Default number of membership changes of a section during which a departed member can rejoin
+/// with half its age.
+pub const REJOIN_WINDOW: u64 = 100;
+
 /// Information about a member of our section.
 #[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub struct MemberInfo {
     pub age_counter: AgeCounter,
     pub state: MemberState,
     pub p2p_node: P2pNode,
+    /// Protocol versions the member speaks.
+    pub versions: VersionRange,
 }
 
 impl MemberInfo {
     /// Create new `MemberInfo` in the `Joined` state.
-    pub fn new(age: u8, p2p_node: P2pNode) -> Self {
+    pub fn new(age: u8, p2p_node: P2pNode, versions: VersionRange) -> Self {
         Self {
             age_counter: AgeCounter::from_age(age),
             state: MemberState::Joined,
             p2p_node,
+            versions,
         }
     }
 
@@ -74,25 +82,28 @@ impl MemberInfo {
         self.age_counter = AgeCounter::from_age(age);
     }
 
-    // Increment the age counter and return whether the age increased.
-    pub fn increment_age_counter(&mut self) -> bool {
-        self.age_counter.increment()
+    pub fn age_counter_value(&self) -> u32 {
+        self.age_counter.0
     }
 
-    pub fn is_mature(&self) -> bool {
-        self.age_counter >= AgeCounter(2_u32.pow(MAX_INFANT_AGE + 1))
+    /// Sets the age counter. Values below `MIN_AGE_COUNTER` are raised to it.
+    pub fn set_age_counter_value(&mut self, value: u32) {
+        self.age_counter = AgeCounter(value.max(MIN_AGE_COUNTER.0));
     }
 
-    #[cfg(feature = "mock_base")]
-    pub fn age_counter_value(&self) -> u32 {
-        self.age_counter.0
+    pub fn is_mature(&self) -> bool {
+        self.age_counter >= AgeCounter(2_u32.pow(MAX_INFANT_AGE + 1))
     }
 }
 
+/// Role of a member in its section.
 #[derive(Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub enum MemberPersona {
+    /// A member not older than `MIN_AGE`.
     Infant,
+    /// An older member which is not an elder.
     Adult,
+    /// A member of the elders of the section.
     Elder,
 }
 
@@ -103,20 +114,50 @@ pub enum MemberState {
         // Node knowledge of us
         node_knowledge: u64,
     },
-    // TODO: we should track how long the node has been away. If longer than some limit, remove it
-    // from the list. Otherwise we allow it to return.
+    // The node can rejoin with half its age for a while, see `DepartedMember`.
     Left,
 }
 
+/// A member that left our section, remembered for a while so it can rejoin without losing all its
+/// age.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
+pub struct DepartedMember {
+    pub pub_id: PublicId,
+    pub age: u8,
+    /// Value of the membership change counter of the section when the member left.
+    pub departed_at: u64,
+}
+
+impl DepartedMember {
+    /// Age the member gets if it rejoins.
+    pub fn rejoin_age(&self) -> u8 {
+        (self.age / 2).max(MIN_AGE)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{id::FullId, rng};
 
     #[test]
     fn min_age_counter_agrees_with_min_age() {
         assert_eq!(MIN_AGE_COUNTER.age(), MIN_AGE);
     }
 
+    #[test]
+    fn rejoin_age_is_halved() {
+        let mut departed = DepartedMember {
+            pub_id: *FullId::gen(&mut rng::new()).public_id(),
+            age: 12,
+            departed_at: 0,
+        };
+        assert_eq!(departed.rejoin_age(), 6);
+
+        departed.age = MIN_AGE + 1;
+        assert_eq!(departed.rejoin_age(), MIN_AGE);
+    }
+
     #[test]
     fn age_counter_to_age() {
         let mut age_counter = AgeCounter::default();
@@ -0,0 +1,105 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{AgeCounter, EldersInfo, MemberInfo, MemberState, SectionKeyInfo};
+use crate::{id::P2pNode, messages::VersionRange};
+
+/// A joined member of a section that wants to merge, as it is handed over to its sibling.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct MergeMember {
+    pub p2p_node: P2pNode,
+    pub age_counter: AgeCounter,
+    pub versions: VersionRange,
+}
+
+impl MergeMember {
+    pub fn new(info: &MemberInfo) -> Self {
+        Self {
+            p2p_node: info.p2p_node.clone(),
+            age_counter: info.age_counter,
+            versions: info.versions,
+        }
+    }
+
+    pub fn into_member_info(self) -> MemberInfo {
+        MemberInfo {
+            age_counter: self.age_counter,
+            state: MemberState::Joined,
+            p2p_node: self.p2p_node,
+            versions: self.versions,
+        }
+    }
+}
+
+/// Sent by a section to its sibling once it agreed it is below `NetworkParams::merge_threshold`,
+/// and again whenever its members change until the merge, or it withdraws its proposal.
+///
+/// Once both siblings agreed, the one with the higher version of its `EldersInfo` (or the one
+/// whose prefix ends with `0`, if equal) absorbs the other: it takes its members over and runs DKG
+/// for the `EldersInfo` of the parent prefix. The members of the absorbed section then adopt the
+/// genesis of the merged section.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct MergeRequest {
+    /// Latest `EldersInfo` of the section.
+    pub elders_info: EldersInfo,
+    /// Latest key of the section.
+    pub key_info: SectionKeyInfo,
+    /// Joined members of the section, by name.
+    pub members: Vec<MergeMember>,
+    /// Neighbours of the section, so the merged section knows all of its own.
+    pub neighbour_infos: Vec<EldersInfo>,
+    /// Number of membership changes of the section, to order requests with the same elders.
+    pub membership_changes: u64,
+    /// Whether the section withdrew its proposal to merge, having grown back to the threshold.
+    pub withdrawn: bool,
+}
+
+impl MergeRequest {
+    /// Returns whether this request supersedes `other`, sent by the same section.
+    pub fn is_newer_than(&self, other: &Self) -> bool {
+        (
+            self.elders_info.version(),
+            self.membership_changes,
+            self.withdrawn,
+        ) > (
+            other.elders_info.version(),
+            other.membership_changes,
+            other.withdrawn,
+        )
+    }
+}
+
+/// Returns whether the section of `ours` absorbs the section of its sibling `theirs` when they
+/// merge.
+pub fn is_absorber(ours: &EldersInfo, theirs: &EldersInfo) -> bool {
+    let last_bit = |info: &EldersInfo| {
+        let prefix = info.prefix();
+        prefix.bit_count() > 0 && prefix.name().bit(prefix.bit_count() - 1)
+    };
+
+    // The absorber's `EldersInfo` is the newest predecessor of the merged one, so the versions of
+    // its history stay consecutive.
+    (ours.version(), !last_bit(ours)) > (theirs.version(), !last_bit(theirs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::chain::tests::gen_elders_info, *};
+
+    #[test]
+    fn absorber() {
+        let info_10 = gen_elders_info("10", 3);
+        let info_11 = gen_elders_info("11", 3);
+        assert!(is_absorber(&info_10, &info_11));
+        assert!(!is_absorber(&info_11, &info_10));
+
+        let info_11 = gen_elders_info("11", 4);
+        assert!(!is_absorber(&info_10, &info_11));
+        assert!(is_absorber(&info_11, &info_10));
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::NetworkParams;
+use crate::id::PublicId;
+use std::collections::BTreeMap;
+
+/// Default misbehaviour score from which each further misbehaviour costs a member one age.
+pub const MISBEHAVIOUR_AGE_PENALTY_SCORE: u32 = 4;
+/// Default misbehaviour score from which a member is no longer considered for elder.
+pub const MISBEHAVIOUR_DEMOTE_SCORE: u32 = 8;
+/// Default misbehaviour score from which a member is evicted from its section.
+pub const MISBEHAVIOUR_EVICT_SCORE: u32 = 16;
+
+/// Kind of misbehaviour of a section member.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum Misbehaviour {
+    /// The member sent a message with an invalid signature.
+    InvalidSignature,
+    /// The member cast an invalid vote, as detected by parsec.
+    InvalidVote,
+    /// The member flooded the section with more messages than `NetworkParams::spam_message_limit`
+    /// allows.
+    Spam,
+    /// The member forged the payload of a relocation.
+    ForgedRelocation,
+    /// The member missed more than half the votes `NetworkParams::unresponsive_threshold`
+    /// allows. Not punished, as members missing more are voted offline anyway, but recorded so
+    /// the elder selection strategy can take it into account.
+    Unresponsive,
+}
+
+impl Misbehaviour {
+    /// How much a single occurrence adds to the score of the offender.
+    pub fn weight(self) -> u32 {
+        match self {
+            Self::Unresponsive => 0,
+            Self::Spam => 1,
+            Self::InvalidSignature => 4,
+            Self::InvalidVote | Self::ForgedRelocation => 8,
+        }
+    }
+}
+
+/// Punishment of a misbehaving member, each one implying the previous ones.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Punishment {
+    /// The member is not punished.
+    None,
+    /// The member loses one age.
+    AgePenalty,
+    /// The member is no longer considered for elder.
+    Demote,
+    /// The member is removed from the section.
+    Evict,
+}
+
+impl Punishment {
+    /// Punishment deserved by a member with the given misbehaviour score.
+    pub fn for_score(score: u32, network_cfg: &NetworkParams) -> Self {
+        if score >= network_cfg.misbehaviour_evict_score {
+            Self::Evict
+        } else if score >= network_cfg.misbehaviour_demote_score {
+            Self::Demote
+        } else if score >= network_cfg.misbehaviour_age_penalty_score {
+            Self::AgePenalty
+        } else {
+            Self::None
+        }
+    }
+}
+
+/// The misbehaviours of a section member agreed on by the section.
+#[derive(Clone, Default, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct MisbehaviourRecord(BTreeMap<Misbehaviour, u32>);
+
+impl MisbehaviourRecord {
+    /// Number of times the member misbehaved in the given way.
+    pub fn count(&self, kind: Misbehaviour) -> u32 {
+        self.0.get(&kind).cloned().unwrap_or(0)
+    }
+
+    /// Weighted sum of all the misbehaviours of the member.
+    pub fn score(&self) -> u32 {
+        self.0
+            .iter()
+            .map(|(kind, count)| kind.weight().saturating_mul(*count))
+            .fold(0, u32::saturating_add)
+    }
+
+    /// Records one more occurrence of the given misbehaviour.
+    pub fn record(&mut self, kind: Misbehaviour) {
+        let count = self.0.entry(kind).or_insert(0);
+        *count = count.saturating_add(1);
+    }
+}
+
+/// Report of a misbehaviour voted for by the elders.
+///
+/// `count` is the number of times the offender was already recorded for the same kind of
+/// misbehaviour, so each new occurrence gets its own vote and reports made against an outdated
+/// ledger are ignored.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct MisbehaviourReport {
+    pub offender: PublicId,
+    pub kind: Misbehaviour,
+    pub count: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn punishment_grows_with_score() {
+        let network_cfg = NetworkParams::default();
+        let mut record = MisbehaviourRecord::default();
+        assert_eq!(
+            Punishment::for_score(record.score(), &network_cfg),
+            Punishment::None
+        );
+
+        record.record(Misbehaviour::InvalidSignature);
+        assert_eq!(record.count(Misbehaviour::InvalidSignature), 1);
+        assert_eq!(
+            Punishment::for_score(record.score(), &network_cfg),
+            Punishment::AgePenalty
+        );
+
+        record.record(Misbehaviour::InvalidVote);
+        assert_eq!(
+            Punishment::for_score(record.score(), &network_cfg),
+            Punishment::Demote
+        );
+
+        record.record(Misbehaviour::InvalidSignature);
+        record.record(Misbehaviour::Spam);
+        assert_eq!(record.score(), 17);
+        assert_eq!(
+            Punishment::for_score(record.score(), &network_cfg),
+            Punishment::Evict
+        );
+    }
+}
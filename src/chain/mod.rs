@@ -14,22 +14,34 @@ mod chain_accumulator;
 mod config;
 mod elders_info;
 mod member_info;
+mod merge;
+mod misbehaviour;
 mod network_event;
 mod proof;
+mod section_map;
 mod shared_state;
+mod stats;
+mod strategies;
 
 pub use self::{
     chain::{delivery_group_size, Chain, ParsecResetData, PollAccumulated, SectionKeyShare},
     chain_accumulator::AccumulatingProof,
     config::NetworkParams,
     elders_info::{quorum_count, EldersInfo},
-    member_info::{AgeCounter, MemberInfo, MemberPersona, MemberState, MIN_AGE, MIN_AGE_COUNTER},
+    member_info::{
+        AgeCounter, DepartedMember, MemberInfo, MemberPersona, MemberState, MIN_AGE,
+        MIN_AGE_COUNTER,
+    },
+    merge::{MergeMember, MergeRequest},
+    misbehaviour::{Misbehaviour, MisbehaviourRecord, MisbehaviourReport, Punishment},
     network_event::{
         AccumulatedEvent, AccumulatingEvent, AckMessagePayload, EldersChange, EventSigPayload,
         IntoAccumulatingEvent, NetworkEvent, OnlinePayload, SendAckMessagePayload,
     },
     proof::{Proof, ProofSet},
-    shared_state::{SectionKeyInfo, SectionProofSlice, TrustStatus},
+    shared_state::{SectionKeyInfo, SectionProofChain, SectionProofSlice, TrustStatus},
+    stats::{NetworkStats, SectionStats, StatsSummary},
+    strategies::Strategies,
 };
 use crate::PublicId;
 use std::{
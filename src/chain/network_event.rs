@@ -6,12 +6,14 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{EldersInfo, SectionKeyInfo};
+use super::{EldersInfo, MergeRequest, MisbehaviourReport, NetworkStats, SectionKeyInfo};
 use crate::{
     error::RoutingError,
     id::{P2pNode, PublicId},
+    messages::VersionRange,
     parsec,
     relocation::RelocateDetails,
+    split::LoadMetrics,
     Prefix, XorName,
 };
 use hex_fmt::HexFmt;
@@ -72,6 +74,8 @@ pub struct OnlinePayload {
     pub age: u8,
     // The version of the destination section that the joining node knows, if any.
     pub their_knowledge: Option<u64>,
+    // Protocol versions the joining node speaks.
+    pub versions: VersionRange,
 }
 
 /// Routing Network events
@@ -112,6 +116,36 @@ pub enum AccumulatingEvent {
 
     // Opaque user-defined event.
     User(Vec<u8>),
+
+    // Voted to switch to a newer protocol version, once all our members speak it.
+    ProtocolVersion(u16),
+
+    // Voted for a member that misbehaved.
+    Misbehaviour(MisbehaviourReport),
+
+    // Voted to agree on the statistics of our section and the network, for the given round.
+    StatsRound(u64),
+
+    // Voted for received statistics of a neighbour section.
+    NeighbourStats(NetworkStats),
+
+    // Voted to merge with our sibling, once our section is below the merge threshold.
+    ProposeMerge,
+
+    // Voted to withdraw our proposal to merge, once our section grew back to the merge threshold.
+    WithdrawMerge,
+
+    // Voted for received merge request of our sibling.
+    SiblingMerge(MergeRequest),
+
+    // Voted to sign the key of the section absorbing us, to link our history to its.
+    SignMergeKey(SectionKeyInfo),
+
+    // Voted for received signature of our new key by the sibling we absorb.
+    MergeKeySignature(SectionKeyInfo, bls::Signature),
+
+    // Voted for application-provided load metrics of our section, consulted to decide on splits.
+    LoadMetrics(LoadMetrics),
 }
 
 impl AccumulatingEvent {
@@ -151,6 +185,27 @@ impl Debug for AccumulatingEvent {
                 write!(formatter, "RelocatePrepare({:?}, {})", payload, count_down)
             }
             Self::User(payload) => write!(formatter, "User({:<8})", HexFmt(payload)),
+            Self::ProtocolVersion(version) => write!(formatter, "ProtocolVersion({})", version),
+            Self::Misbehaviour(report) => write!(formatter, "Misbehaviour({:?})", report),
+            Self::StatsRound(round) => write!(formatter, "StatsRound({})", round),
+            Self::NeighbourStats(stats) => write!(
+                formatter,
+                "NeighbourStats({:?}, round: {})",
+                stats.section.prefix, stats.round
+            ),
+            Self::ProposeMerge => write!(formatter, "ProposeMerge"),
+            Self::WithdrawMerge => write!(formatter, "WithdrawMerge"),
+            Self::SiblingMerge(request) => write!(
+                formatter,
+                "SiblingMerge({:?}, members: {})",
+                request.elders_info,
+                request.members.len()
+            ),
+            Self::SignMergeKey(key_info) => write!(formatter, "SignMergeKey({:?})", key_info),
+            Self::MergeKeySignature(key_info, _) => {
+                write!(formatter, "MergeKeySignature({:?})", key_info)
+            }
+            Self::LoadMetrics(metrics) => write!(formatter, "LoadMetrics({:?})", metrics),
         }
     }
 }
@@ -0,0 +1,176 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{EldersInfo, SectionKeyInfo};
+use crate::{
+    time::Duration,
+    xor_space::{Prefix, XorName},
+};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// Default maximum number of sections kept in the section map. Zero disables querying other
+/// sections.
+pub const SECTION_MAP_CAPACITY: usize = 0;
+/// Default time between two queries for the elders of another section.
+pub const SECTION_MAP_QUERY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Bounded cache of the elders and keys of sections other than ours and our neighbours, learnt by
+/// querying them. When full, the least recently inserted section is dropped first.
+#[derive(Default, Serialize, Deserialize)]
+pub struct SectionMap {
+    entries: BTreeMap<Prefix<XorName>, (EldersInfo, SectionKeyInfo)>,
+    // Prefixes of the entries, least recently inserted first.
+    order: VecDeque<Prefix<XorName>>,
+}
+
+impl SectionMap {
+    /// Returns the elders of the known section matching `name`.
+    pub fn get(&self, name: &XorName) -> Option<&EldersInfo> {
+        self.entries
+            .iter()
+            .find(|(prefix, _)| prefix.matches(name))
+            .map(|(_, (elders_info, _))| elders_info)
+    }
+
+    /// Inserts the elders and key of a section, replacing the older entries of sections with
+    /// compatible prefixes. Returns whether the entry is new. `key_info` must belong to
+    /// `elders_info`.
+    pub fn insert(
+        &mut self,
+        elders_info: EldersInfo,
+        key_info: SectionKeyInfo,
+        capacity: usize,
+    ) -> bool {
+        let prefix = *key_info.prefix();
+        let version = key_info.version();
+
+        if let Some((_, (_, old_key_info))) =
+            self.entries.iter().find(|(old_prefix, (_, old_key_info))| {
+                old_prefix.is_compatible(&prefix) && old_key_info.version() >= version
+            })
+        {
+            if *old_key_info == key_info {
+                // Still up to date - refresh it so it's not dropped or queried again too soon.
+                self.order.retain(|old_prefix| *old_prefix != prefix);
+                self.order.push_back(prefix);
+            }
+            return false;
+        }
+
+        self.retain(|old_prefix| !old_prefix.is_compatible(&prefix));
+        let _ = self.entries.insert(prefix, (elders_info, key_info));
+        self.order.push_back(prefix);
+
+        while self.order.len() > capacity {
+            if let Some(old_prefix) = self.order.pop_front() {
+                let _ = self.entries.remove(&old_prefix);
+            }
+        }
+
+        true
+    }
+
+    /// Keeps only the sections whose prefix satisfies the predicate.
+    pub fn retain<F: Fn(&Prefix<XorName>) -> bool>(&mut self, keep: F) {
+        self.entries.retain(|prefix, _| keep(prefix));
+        self.order.retain(|prefix| keep(prefix));
+    }
+
+    /// Returns the prefix of the section inserted the longest time ago, if any.
+    pub fn oldest(&self) -> Option<&Prefix<XorName>> {
+        self.order.front()
+    }
+
+    /// Returns the prefixes of the known sections.
+    pub fn prefixes(&self) -> impl Iterator<Item = &Prefix<XorName>> {
+        self.entries.keys()
+    }
+}
+
+/// Returns a prefix of a part of the address space not covered by any of the `known` prefixes,
+/// if any.
+pub fn find_gap(
+    prefix: Prefix<XorName>,
+    known: &BTreeSet<Prefix<XorName>>,
+) -> Option<Prefix<XorName>> {
+    if known
+        .iter()
+        .any(|known| prefix.is_extension_of(known) || prefix == *known)
+    {
+        return None;
+    }
+
+    if !known.iter().any(|known| known.is_compatible(&prefix)) {
+        return Some(prefix);
+    }
+
+    find_gap(prefix.pushed(false), known).or_else(|| find_gap(prefix.pushed(true), known))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::chain::tests::gen_elders_info, *};
+    use crate::{parsec::generate_bls_threshold_secret_key, rng, unwrap};
+    use std::str::FromStr;
+
+    fn gen_section(prefix: &str, version: u64) -> (EldersInfo, SectionKeyInfo) {
+        let elders_info = gen_elders_info(prefix, version);
+        let key = generate_bls_threshold_secret_key(&mut rng::new(), 1)
+            .public_keys()
+            .public_key();
+        let key_info = SectionKeyInfo::from_elders_info(&elders_info, key);
+        (elders_info, key_info)
+    }
+
+    #[test]
+    fn insert_replaces_older_compatible_sections() {
+        let mut map = SectionMap::default();
+        let (info_1, key_1) = gen_section("1", 1);
+        let (info_10, key_10) = gen_section("10", 2);
+
+        assert!(map.insert(info_1.clone(), key_1.clone(), 2));
+        assert!(map.insert(info_10, key_10, 2));
+        assert_eq!(map.prefixes().count(), 1);
+        assert!(!map.insert(info_1, key_1, 2));
+
+        let name = unwrap!(Prefix::<XorName>::from_str("10")).lower_bound();
+        assert_eq!(map.get(&name).map(|info| info.version()), Some(2));
+    }
+
+    #[test]
+    fn insert_drops_oldest_when_full() {
+        let mut map = SectionMap::default();
+        let (info_00, key_00) = gen_section("00", 1);
+        let (info_01, key_01) = gen_section("01", 1);
+        let (info_1, key_1) = gen_section("1", 1);
+
+        assert!(map.insert(info_00, key_00, 2));
+        assert!(map.insert(info_01, key_01, 2));
+        assert!(map.insert(info_1, key_1, 2));
+        assert_eq!(map.oldest(), Some(&unwrap!(Prefix::from_str("01"))));
+        assert_eq!(map.prefixes().count(), 2);
+    }
+
+    #[test]
+    fn gap() {
+        let known: BTreeSet<_> = vec!["00", "10"]
+            .into_iter()
+            .map(|prefix| unwrap!(Prefix::from_str(prefix)))
+            .collect();
+        assert_eq!(
+            find_gap(Prefix::default(), &known),
+            Some(unwrap!(Prefix::from_str("01")))
+        );
+
+        let known: BTreeSet<_> = vec!["0", "1"]
+            .into_iter()
+            .map(|prefix| unwrap!(Prefix::from_str(prefix)))
+            .collect();
+        assert_eq!(find_gap(Prefix::default(), &known), None);
+    }
+}
@@ -7,12 +7,18 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
-    AccumulatedEvent, AccumulatingProof, AgeCounter, EldersInfo, MemberInfo, MemberPersona,
-    MemberState, MIN_AGE_COUNTER,
+    AccumulatedEvent, AccumulatingProof, AgeCounter, DepartedMember, EldersInfo, MemberInfo,
+    MemberPersona, MemberState, MergeRequest, MisbehaviourRecord, MIN_AGE_COUNTER,
 };
 use crate::{
-    error::RoutingError, id::PublicId, location::DstLocation, relocation::RelocateDetails,
-    utils::LogIdent, Prefix, XorName,
+    error::RoutingError,
+    id::PublicId,
+    location::DstLocation,
+    messages::{VersionRange, PROTOCOL_VERSION},
+    relocation::RelocateDetails,
+    split::LoadMetrics,
+    utils::LogIdent,
+    Prefix, XorName,
 };
 use bincode::{deserialize, serialize};
 use itertools::Itertools;
@@ -26,13 +32,13 @@ use std::{
 #[cfg(feature = "mock_base")]
 use crate::crypto::Digest256;
 
-// Number of recent keys we keep: i.e how many other section churns we can handle before a
+// Default number of recent keys we keep: i.e how many other section churns we can handle before a
 // message send with a previous version of a section is no longer trusted.
 // With low churn rate, a ad hoc 20 should be big enough to avoid losing messages.
-const MAX_THEIR_RECENT_KEYS: usize = 20;
+pub const MAX_THEIR_RECENT_KEYS: usize = 20;
 
 /// Section state that is shared among all elders of a section via Parsec consensus.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SharedState {
     /// Indicate whether nodes are shared state because genesis event was seen
     pub handled_genesis_event: bool,
@@ -63,6 +69,20 @@ pub struct SharedState {
     pub churn_event_backlog: VecDeque<AccumulatedEvent>,
     /// Queue of pending relocations.
     pub relocate_queue: VecDeque<RelocateDetails>,
+    /// Protocol version the section agreed on.
+    pub protocol_version: u16,
+    /// Misbehaviours of our members the section agreed on.
+    pub misbehaviour: BTreeMap<XorName, MisbehaviourRecord>,
+    /// Number of members added to or removed from our section so far.
+    pub membership_changes: u64,
+    /// Members that left our section recently and can rejoin with half their age.
+    pub departed_members: BTreeMap<XorName, DepartedMember>,
+    /// Whether our section agreed to merge with its sibling.
+    pub merge_proposed: bool,
+    /// Latest merge request of our sibling, if it agreed to merge with us.
+    pub sibling_merge: Option<MergeRequest>,
+    /// Load metrics of our section it agreed on last, since its prefix last changed.
+    pub load_metrics: LoadMetrics,
 }
 
 impl SharedState {
@@ -83,6 +103,8 @@ impl SharedState {
                     age_counter: *ages.get(p2p_node.public_id()).unwrap_or(&MIN_AGE_COUNTER),
                     state: MemberState::Joined,
                     p2p_node: p2p_node.clone(),
+                    // The genesis members are assumed to speak the same versions as us.
+                    versions: VersionRange::OURS,
                 };
                 (*p2p_node.name(), info)
             })
@@ -102,6 +124,13 @@ impl SharedState {
             their_recent_keys: Default::default(),
             churn_event_backlog: Default::default(),
             relocate_queue: VecDeque::new(),
+            protocol_version: PROTOCOL_VERSION,
+            misbehaviour: Default::default(),
+            membership_changes: 0,
+            departed_members: Default::default(),
+            merge_proposed: false,
+            sibling_merge: None,
+            load_metrics: Default::default(),
         }
     }
 
@@ -132,6 +161,13 @@ impl SharedState {
             their_recent_keys,
             churn_event_backlog,
             relocate_queue,
+            protocol_version,
+            misbehaviour,
+            membership_changes,
+            departed_members,
+            merge_proposed,
+            sibling_merge,
+            load_metrics,
         ) = deserialize(related_info)?;
         if self.our_infos.len() != 1 {
             // Check nodes with a history before genesis match the genesis block:
@@ -189,6 +225,48 @@ impl SharedState {
                 &self.relocate_queue,
                 &relocate_queue,
             );
+            update_with_genesis_related_info_check_same(
+                log_ident,
+                "protocol_version",
+                &self.protocol_version,
+                &protocol_version,
+            );
+            update_with_genesis_related_info_check_same(
+                log_ident,
+                "misbehaviour",
+                &self.misbehaviour,
+                &misbehaviour,
+            );
+            update_with_genesis_related_info_check_same(
+                log_ident,
+                "membership_changes",
+                &self.membership_changes,
+                &membership_changes,
+            );
+            update_with_genesis_related_info_check_same(
+                log_ident,
+                "departed_members",
+                &self.departed_members,
+                &departed_members,
+            );
+            update_with_genesis_related_info_check_same(
+                log_ident,
+                "merge_proposed",
+                &self.merge_proposed,
+                &merge_proposed,
+            );
+            update_with_genesis_related_info_check_same(
+                log_ident,
+                "sibling_merge",
+                &self.sibling_merge,
+                &sibling_merge,
+            );
+            update_with_genesis_related_info_check_same(
+                log_ident,
+                "load_metrics",
+                &self.load_metrics,
+                &load_metrics,
+            );
         }
         self.our_infos = our_infos;
         self.our_history = our_history;
@@ -199,6 +277,13 @@ impl SharedState {
         self.their_recent_keys = their_recent_keys;
         self.churn_event_backlog = churn_event_backlog;
         self.relocate_queue = relocate_queue;
+        self.protocol_version = protocol_version;
+        self.misbehaviour = misbehaviour;
+        self.membership_changes = membership_changes;
+        self.departed_members = departed_members;
+        self.merge_proposed = merge_proposed;
+        self.sibling_merge = sibling_merge;
+        self.load_metrics = load_metrics;
 
         Ok(())
     }
@@ -214,6 +299,13 @@ impl SharedState {
             &self.their_recent_keys,
             &self.churn_event_backlog,
             &self.relocate_queue,
+            &self.protocol_version,
+            &self.misbehaviour,
+            &self.membership_changes,
+            &self.departed_members,
+            &self.merge_proposed,
+            &self.sibling_merge,
+            &self.load_metrics,
         ))?)
     }
 
@@ -285,21 +377,57 @@ impl SharedState {
                 .partition(|(name, _)| self.our_prefix().matches(name));
         self.our_members = our_members;
         self.post_split_sibling_members = post_split_sibling_members;
+
+        let our_prefix = *self.our_prefix();
+        self.misbehaviour.retain(|name, _| our_prefix.matches(name));
+        self.departed_members
+            .retain(|name, _| our_prefix.matches(name));
     }
 
-    pub fn push_our_new_info(&mut self, elders_info: EldersInfo, proof_block: SectionProofBlock) {
+    pub fn push_our_new_info(
+        &mut self,
+        elders_info: EldersInfo,
+        proof_block: SectionProofBlock,
+        max_their_recent_keys: usize,
+    ) {
         self.our_history.push(proof_block);
         self.our_infos.push(elders_info);
 
         let key_info = self.our_history.last_key_info().clone();
-        self.update_their_keys(&key_info);
+        self.update_their_keys(&key_info, max_their_recent_keys);
     }
 
     /// Updates the entry in `their_keys` for `prefix` to the latest known key; if a split
     /// occurred in the meantime, the keys for sections covering the rest of the address space are
-    /// initialised to the old key that was stored for their common ancestor
-    /// NOTE: the function as it is currently is not merge-safe.
-    pub fn update_their_keys(&mut self, key_info: &SectionKeyInfo) {
+    /// initialised to the old key that was stored for their common ancestor. If a merge occurred,
+    /// the key replaces the ones of the merged sections.
+    pub fn update_their_keys(&mut self, key_info: &SectionKeyInfo, max_their_recent_keys: usize) {
+        let merged: Vec<_> = self
+            .their_keys
+            .iter()
+            .filter(|(pfx, _)| pfx.is_extension_of(key_info.prefix()))
+            .map(|(pfx, info)| (*pfx, info.version()))
+            .collect();
+        if !merged.is_empty() {
+            // Sections are only newer than their extensions if they merged. Otherwise this is an
+            // old key of the section from before it split.
+            if merged
+                .iter()
+                .any(|(_, version)| *version >= key_info.version())
+            {
+                return;
+            }
+
+            for (pfx, _) in merged {
+                if let Some(old_key_info) = self.their_keys.remove(&pfx) {
+                    trace!("    from {:?} to {:?}", old_key_info, key_info);
+                    self.push_their_recent_key(pfx, old_key_info, max_their_recent_keys);
+                }
+            }
+            let _ = self.their_keys.insert(*key_info.prefix(), key_info.clone());
+            return;
+        }
+
         if let Some((&old_pfx, old_version)) = self
             .their_keys
             .iter()
@@ -316,11 +444,7 @@ impl SharedState {
                 .remove(&old_pfx)
                 .expect("Bug in BTreeMap for update_their_keys");
 
-            self.their_recent_keys
-                .push_front((old_pfx, old_key_info.clone()));
-            if self.their_recent_keys.len() > MAX_THEIR_RECENT_KEYS {
-                let _ = self.their_recent_keys.pop_back();
-            }
+            self.push_their_recent_key(old_pfx, old_key_info.clone(), max_their_recent_keys);
 
             trace!("    from {:?} to {:?}", old_key_info, key_info);
 
@@ -334,6 +458,18 @@ impl SharedState {
         let _ = self.their_keys.insert(*key_info.prefix(), key_info.clone());
     }
 
+    fn push_their_recent_key(
+        &mut self,
+        pfx: Prefix<XorName>,
+        key_info: SectionKeyInfo,
+        max_their_recent_keys: usize,
+    ) {
+        self.their_recent_keys.push_front((pfx, key_info));
+        if self.their_recent_keys.len() > max_their_recent_keys {
+            let _ = self.their_recent_keys.pop_back();
+        }
+    }
+
     /// Updates the entry in `their_knowledge` for `prefix` to the `version`; if a split
     /// occurred in the meantime, the versions for sections covering the rest of the address space
     /// are initialised to the old version that was stored for their common ancestor
@@ -439,7 +575,7 @@ fn update_with_genesis_related_info_check_same<T>(
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SplitCache {
     pub elders_info: EldersInfo,
     pub key_info: SectionKeyInfo,
@@ -491,17 +627,53 @@ where
 pub struct SectionProofBlock {
     key_info: SectionKeyInfo,
     sig: bls::Signature,
+    /// For the block of a merge, the last key of the sibling section we absorbed and its signature
+    /// of `key_info`. It links the history of the sibling to ours, so whoever trusts that key can
+    /// trust ours from there.
+    merged: Option<(SectionKeyInfo, bls::Signature)>,
 }
 
 impl SectionProofBlock {
     pub fn new(key_info: SectionKeyInfo, sig: bls::Signature) -> Self {
-        Self { key_info, sig }
+        Self {
+            key_info,
+            sig,
+            merged: None,
+        }
+    }
+
+    /// Records the last key of the sibling section absorbed by the merge this block is for, and
+    /// its signature of our new key.
+    pub fn with_merged_key_info(
+        self,
+        merged_key_info: SectionKeyInfo,
+        merged_sig: bls::Signature,
+    ) -> Self {
+        Self {
+            merged: Some((merged_key_info, merged_sig)),
+            ..self
+        }
     }
 
     pub fn key_info(&self) -> &SectionKeyInfo {
         &self.key_info
     }
 
+    pub fn merged_key_info(&self) -> Option<&SectionKeyInfo> {
+        self.merged.as_ref().map(|(key_info, _)| key_info)
+    }
+
+    /// Returns whether this is the block of a merge whose new key was signed by the absorbed
+    /// sibling.
+    fn verify_merged(&self) -> bool {
+        match (&self.merged, self.key_info.serialise_for_signature()) {
+            (Some((merged_key_info, merged_sig)), Ok(to_verify)) => {
+                merged_key_info.key().verify(merged_sig, to_verify)
+            }
+            _ => false,
+        }
+    }
+
     pub fn verify_with_pk(&self, pk: bls::PublicKey) -> bool {
         if let Ok(to_verify) = self.key_info.serialise_for_signature() {
             pk.verify(&self.sig, to_verify)
@@ -563,22 +735,32 @@ impl SectionProofSlice {
         &'a self,
         last_trusted: &'a SectionKeyInfo,
     ) -> Option<&'a SectionKeyInfo> {
-        let block_offset = last_trusted.version().saturating_sub(self.version) as usize;
-
-        if block_offset == 0 {
-            if last_trusted.version() != self.version || last_trusted.prefix() != &self.prefix {
-                return None;
-            }
-        } else if let Some(block) = self.blocks.get(block_offset - 1) {
-            if block.key_info() != last_trusted {
+        let (mut current, block_offset) =
+            if last_trusted.version() == self.version && last_trusted.prefix() == &self.prefix {
+                (last_trusted, 0)
+            } else if let Some(index) = self
+                .blocks
+                .iter()
+                .position(|block| block.key_info() == last_trusted)
+            {
+                (last_trusted, index + 1)
+            } else if let Some(index) = self
+                .blocks
+                .iter()
+                .position(|block| block.merged_key_info() == Some(last_trusted))
+            {
+                // The last key of a section we absorbed links its history to the merge block, as
+                // long as it signed the key of the merged section.
+                let block = &self.blocks[index];
+                if !block.verify_merged() {
+                    return None;
+                }
+                (block.key_info(), index + 1)
+            } else {
+                // Root of trust not found
                 return None;
-            }
-        } else {
-            // Root of trust not found
-            return None;
-        }
+            };
 
-        let mut current = last_trusted;
         for block in &self.blocks[block_offset..] {
             if !validate_next_block(current, block) {
                 return None;
@@ -590,6 +772,12 @@ impl SectionProofSlice {
         Some(current)
     }
 
+    fn is_merged_key_info(&self, key_info: &SectionKeyInfo) -> bool {
+        self.blocks
+            .iter()
+            .any(|block| block.merged_key_info() == Some(key_info))
+    }
+
     // Verify this proof chain against the given key infos.
     pub fn check_trust<'a, I>(&'a self, their_key_infos: I) -> TrustStatus<'a>
     where
@@ -610,7 +798,9 @@ impl SectionProofSlice {
             max_known_version = std::cmp::max(max_known_version, proof_key_info.version());
             found_prefix_keys = true;
 
-            if inclusive_range.contains(&proof_key_info.version()) {
+            if inclusive_range.contains(&proof_key_info.version())
+                || self.is_merged_key_info(proof_key_info)
+            {
                 // We can validate trust with that key: we are done.
                 if let Some(trusted_info) = self.last_trusted_key_info(proof_key_info) {
                     return TrustStatus::Trusted(trusted_info.key());
@@ -656,7 +846,6 @@ impl SectionProofChain {
         self.blocks.push(block)
     }
 
-    #[cfg(test)]
     pub fn validate(&self) -> bool {
         let mut current = &self.genesis_key_info;
         for block in &self.blocks {
@@ -718,6 +907,17 @@ fn validate_next_block(last: &SectionKeyInfo, next: &SectionProofBlock) -> bool
         return false;
     }
 
+    if let Some(merged) = next.merged_key_info() {
+        // Only the parent of two siblings can absorb one of them, and only a newer one.
+        if next.prefix().bit_count() + 1 != last.prefix().bit_count()
+            || *merged.prefix() != last.prefix().sibling()
+            || merged.version() >= next.version()
+            || !next.verify_merged()
+        {
+            return false;
+        }
+    }
+
     if !next.verify_with_pk(*last.key()) {
         return false;
     }
@@ -857,7 +1057,7 @@ mod test {
         // Act
         //
         for (key_info, _, _) in keys_to_update.iter().skip(1) {
-            state.update_their_keys(key_info);
+            state.update_their_keys(key_info, MAX_THEIR_RECENT_KEYS);
         }
 
         // Assert
@@ -915,6 +1115,93 @@ mod test {
         );
     }
 
+    #[test]
+    fn simple_merge() {
+        update_keys_and_check_with_version(
+            &mut rng::new(),
+            vec![(0, "0"), (5, "10"), (5, "11"), (6, "1")],
+            vec![("0", 0), ("1", 3), ("11", 2), ("10", 1)],
+        );
+    }
+
+    #[test]
+    fn simple_merge_out_of_order() {
+        // Key from before the split ignored
+        update_keys_and_check_with_version(
+            &mut rng::new(),
+            vec![(0, "0"), (5, "10"), (5, "11"), (4, "1")],
+            vec![("0", 0), ("10", 1), ("11", 2)],
+        );
+    }
+
+    fn gen_key_info(
+        rng: &mut MainRng,
+        pfx_str: &str,
+        version: u64,
+    ) -> (SectionKeyInfo, bls::SecretKey) {
+        let secret_key = generate_bls_threshold_secret_key(rng, 1).secret_key();
+        let pfx = unwrap!(Prefix::<XorName>::from_str(pfx_str));
+        let key_info = SectionKeyInfo::new(version, pfx, secret_key.public_key());
+        (key_info, secret_key)
+    }
+
+    #[test]
+    fn merge_block_links_sibling_history() {
+        let mut rng = rng::new();
+        let (our_key_info, our_secret_key) = gen_key_info(&mut rng, "10", 0);
+        let (sibling_key_info, sibling_secret_key) = gen_key_info(&mut rng, "11", 0);
+        let (other_key_info, _) = gen_key_info(&mut rng, "11", 0);
+        let (merged_key_info, _) = gen_key_info(&mut rng, "1", 1);
+
+        let to_sign = unwrap!(merged_key_info.serialise_for_signature());
+        let sig = our_secret_key.sign(&to_sign);
+        let merged_sig = sibling_secret_key.sign(&to_sign);
+        let block = SectionProofBlock::new(merged_key_info.clone(), sig)
+            .with_merged_key_info(sibling_key_info.clone(), merged_sig);
+        let mut history = SectionProofChain::from_genesis(our_key_info);
+        history.push(block);
+        assert!(history.validate());
+
+        let proof = history.slice_from(0);
+        let their_keys = vec![(*sibling_key_info.prefix(), sibling_key_info)];
+        match proof.check_trust(their_keys.iter().map(|(pfx, info)| (pfx, info))) {
+            TrustStatus::Trusted(key) => assert_eq!(key, merged_key_info.key()),
+            status => panic!("Unexpected trust status: {:?}", status),
+        }
+
+        let their_keys = vec![(*other_key_info.prefix(), other_key_info)];
+        match proof.check_trust(their_keys.iter().map(|(pfx, info)| (pfx, info))) {
+            TrustStatus::ProofInvalid => (),
+            status => panic!("Unexpected trust status: {:?}", status),
+        }
+    }
+
+    #[test]
+    fn forged_merge_block_not_trusted() {
+        let mut rng = rng::new();
+        let (forged_key_info, forged_secret_key) = gen_key_info(&mut rng, "10", 0);
+        let (sibling_key_info, _) = gen_key_info(&mut rng, "11", 0);
+        let (merged_key_info, _) = gen_key_info(&mut rng, "1", 1);
+
+        // The forger can sign the merge block with its own key, but not with the sibling's one.
+        let to_sign = unwrap!(merged_key_info.serialise_for_signature());
+        let sig = forged_secret_key.sign(&to_sign);
+        let block = SectionProofBlock::new(merged_key_info, sig.clone())
+            .with_merged_key_info(sibling_key_info.clone(), sig);
+        assert!(!validate_next_block(&forged_key_info, &block));
+
+        let proof = SectionProofSlice {
+            version: forged_key_info.version(),
+            prefix: *forged_key_info.prefix(),
+            blocks: vec![block],
+        };
+        let their_keys = vec![(*sibling_key_info.prefix(), sibling_key_info)];
+        match proof.check_trust(their_keys.iter().map(|(pfx, info)| (pfx, info))) {
+            TrustStatus::ProofInvalid => (),
+            status => panic!("Unexpected trust status: {:?}", status),
+        }
+    }
+
     #[test]
     fn our_section_not_sibling_of_ancestor() {
         // 01 Not the sibling of the single bit parent prefix of 111
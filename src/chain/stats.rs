@@ -0,0 +1,217 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::xor_space::{Prefix, XorName};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Add,
+};
+
+/// Statistics of a single section, agreed on by its elders.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct SectionStats {
+    /// Prefix of the section.
+    pub prefix: Prefix<XorName>,
+    /// Number of members of the section of each age.
+    pub ages: BTreeMap<u8, u64>,
+    /// Names of the elders of the section.
+    pub elders: BTreeSet<XorName>,
+}
+
+impl SectionStats {
+    /// Number of members of the section.
+    pub fn member_count(&self) -> u64 {
+        self.ages.values().sum()
+    }
+
+    /// Totals over the section alone.
+    pub fn summary(&self) -> StatsSummary {
+        StatsSummary {
+            section_count: 1,
+            node_count: self.member_count(),
+            age_sum: self
+                .ages
+                .iter()
+                .map(|(age, count)| u64::from(*age) * count)
+                .sum(),
+            complete: true,
+        }
+    }
+}
+
+/// Totals over the sections of a part of the network.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct StatsSummary {
+    /// Number of sections.
+    pub section_count: u64,
+    /// Number of nodes.
+    pub node_count: u64,
+    /// Sum of the ages of the nodes.
+    pub age_sum: u64,
+    /// Whether every section of that part of the network is accounted for.
+    pub complete: bool,
+}
+
+impl StatsSummary {
+    /// Average age of the nodes, or zero if there are none.
+    pub fn average_age(&self) -> f64 {
+        if self.node_count == 0 {
+            0.0
+        } else {
+            self.age_sum as f64 / self.node_count as f64
+        }
+    }
+}
+
+impl Add for StatsSummary {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            section_count: self.section_count.saturating_add(other.section_count),
+            node_count: self.node_count.saturating_add(other.node_count),
+            age_sum: self.age_sum.saturating_add(other.age_sum),
+            complete: self.complete && other.complete,
+        }
+    }
+}
+
+/// Statistics of a section and of the rest of the network as known to it, agreed on by the
+/// elders of the section every `NetworkParams::network_stats_interval`.
+///
+/// The rest of the network is summarised per sibling of each ancestor of the section prefix,
+/// from the statistics our neighbours in that part of the network last sent us. The summaries
+/// of a section are thus aggregated up the prefix tree as they travel from neighbour to
+/// neighbour. Parts we haven't heard of yet are missing, so the totals are lower bounds until
+/// the statistics are complete.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct NetworkStats {
+    /// Number of times the section agreed on its statistics.
+    pub round: u64,
+    /// Statistics of the section itself.
+    pub section: SectionStats,
+    /// Totals over the other parts of the network, by prefix.
+    pub subtrees: BTreeMap<Prefix<XorName>, StatsSummary>,
+}
+
+impl NetworkStats {
+    /// Computes the statistics of a section, given the latest statistics of its neighbours.
+    pub(crate) fn new<'a, I>(round: u64, section: SectionStats, neighbours: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Self>,
+    {
+        let prefix = section.prefix;
+        let neighbours: Vec<_> = neighbours.into_iter().collect();
+        let subtrees = (0..prefix.bit_count())
+            .map(|bit| Prefix::new(bit + 1, prefix.with_flipped_bit(bit).name()))
+            .filter_map(|subtree| {
+                // Prefer a neighbour which knows the whole subtree, if there are several.
+                let summaries: Vec<_> = neighbours
+                    .iter()
+                    .filter(|neighbour| is_within(&neighbour.section.prefix, &subtree))
+                    .map(|neighbour| neighbour.summary_within(&subtree))
+                    .collect();
+                summaries
+                    .iter()
+                    .find(|summary| summary.complete)
+                    .or_else(|| summaries.first())
+                    .map(|summary| (subtree, *summary))
+            })
+            .collect();
+
+        Self {
+            round,
+            section,
+            subtrees,
+        }
+    }
+
+    /// Totals over the whole network as known to the section.
+    pub fn network_summary(&self) -> StatsSummary {
+        self.summary_within(&Prefix::default())
+    }
+
+    /// Returns whether every part of the network is accounted for.
+    pub fn is_complete(&self) -> bool {
+        self.network_summary().complete
+    }
+
+    // Totals over the part of the network covered by `subtree`, which our section is part of.
+    fn summary_within(&self, subtree: &Prefix<XorName>) -> StatsSummary {
+        let summaries: Vec<_> = self
+            .subtrees
+            .iter()
+            .filter(|(prefix, _)| is_within(prefix, subtree))
+            .map(|(_, summary)| *summary)
+            .collect();
+        let expected_count = self
+            .section
+            .prefix
+            .bit_count()
+            .saturating_sub(subtree.bit_count());
+        let total = summaries
+            .iter()
+            .fold(self.section.summary(), |total, summary| total + *summary);
+
+        StatsSummary {
+            complete: total.complete && summaries.len() == expected_count,
+            ..total
+        }
+    }
+}
+
+fn is_within(prefix: &Prefix<XorName>, subtree: &Prefix<XorName>) -> bool {
+    prefix == subtree || prefix.is_extension_of(subtree)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unwrap;
+    use std::str::FromStr;
+
+    fn section_stats(prefix: &str, ages: &[(u8, u64)]) -> SectionStats {
+        SectionStats {
+            prefix: unwrap!(Prefix::from_str(prefix)),
+            ages: ages.iter().cloned().collect(),
+            elders: BTreeSet::new(),
+        }
+    }
+
+    #[test]
+    fn aggregate_up_the_prefix_tree() {
+        // Sections 00, 01 and 1, each knowing nothing of the others yet.
+        let stats_00 = NetworkStats::new(1, section_stats("00", &[(4, 2)]), None);
+        let stats_01 = NetworkStats::new(1, section_stats("01", &[(5, 4)]), None);
+        let stats_1 = NetworkStats::new(1, section_stats("1", &[(6, 1), (8, 1)]), None);
+        assert!(!stats_00.is_complete());
+        assert_eq!(stats_00.network_summary().node_count, 2);
+
+        // Section 01 hears from both its neighbours, then 00 hears from its own.
+        let stats_01 = NetworkStats::new(2, stats_01.section, vec![&stats_00, &stats_1]);
+        assert!(stats_01.is_complete());
+        let stats_00 = NetworkStats::new(2, stats_00.section, vec![&stats_01, &stats_1]);
+        assert!(stats_00.is_complete());
+
+        let summary = stats_00.network_summary();
+        assert_eq!(summary.section_count, 3);
+        assert_eq!(summary.node_count, 8);
+        assert_eq!(summary.age_sum, 42);
+        assert!((summary.average_age() - 5.25).abs() < f64::EPSILON);
+
+        // Section 1 learns about the whole of 0 from 01, once 01 heard from 00.
+        let stale_stats_01 = NetworkStats::new(1, section_stats("01", &[(5, 4)]), None);
+        let stale_stats_1 = NetworkStats::new(2, stats_1.section.clone(), vec![&stale_stats_01]);
+        assert!(!stale_stats_1.is_complete());
+        assert_eq!(stale_stats_1.network_summary().node_count, 6);
+
+        let stats_1 = NetworkStats::new(3, stats_1.section, vec![&stats_01]);
+        assert!(stats_1.is_complete());
+        assert_eq!(stats_1.network_summary(), summary);
+    }
+}
@@ -0,0 +1,33 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    ageing::{AgeingStrategy, DefaultAgeing},
+    elder_selection::{ElderSelection, EldestFirst},
+    split::{DefaultSplit, SplitPolicy},
+};
+use std::rc::Rc;
+
+/// Pluggable rules applied by our section. They are not part of the chain state, so they have to
+/// be set again whenever a chain is created or restored.
+#[derive(Clone)]
+pub struct Strategies {
+    pub ageing: Rc<dyn AgeingStrategy>,
+    pub split: Rc<dyn SplitPolicy>,
+    pub elder_selection: Rc<dyn ElderSelection>,
+}
+
+impl Default for Strategies {
+    fn default() -> Self {
+        Self {
+            ageing: Rc::new(DefaultAgeing),
+            split: Rc::new(DefaultSplit),
+            elder_selection: Rc::new(EldestFirst),
+        }
+    }
+}
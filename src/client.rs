@@ -0,0 +1,459 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    chain::{EldersInfo, SectionKeyInfo, SectionProofChain},
+    error::RoutingError,
+    event::{self, Connected, Event},
+    id::{FullId, P2pNode, PublicId},
+    location::DstLocation,
+    messages::{ClientMessage, Message, Variant, VerifyStatus, PROTOCOL_VERSION},
+    quic_p2p::{Builder as QuicP2pBuilder, OurType, Peer, QuicP2p, Token},
+    rng::{self, MainRng},
+    xor_space::{Prefix, XorName},
+    ConnectionInfo, NetworkConfig, NetworkEvent,
+};
+use bytes::Bytes;
+use crossbeam_channel as mpmc;
+use rand::RngCore;
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    net::SocketAddr,
+};
+
+/// A builder to configure and create a new `Client`.
+pub struct ClientBuilder {
+    rng: Option<MainRng>,
+    network_config: Option<NetworkConfig>,
+    full_id: Option<FullId>,
+    genesis_key: Option<bls::PublicKey>,
+}
+
+impl ClientBuilder {
+    /// The client will use the given network config rather than default.
+    pub fn network_config(self, config: NetworkConfig) -> Self {
+        Self {
+            network_config: Some(config),
+            ..self
+        }
+    }
+
+    /// The client will use the given full id rather than default, randomly generated one.
+    pub fn full_id(self, full_id: FullId) -> Self {
+        Self {
+            full_id: Some(full_id),
+            ..self
+        }
+    }
+
+    /// The client will only trust sections which can prove their key back to the given genesis key
+    /// of the network, as returned by `Node::genesis_key`: the proxy must prove the key of its
+    /// section from it, and so must the sections sending messages to the client. If not set, the
+    /// client trusts the genesis key its proxy proves the key of its section from.
+    pub fn genesis_key(self, key: bls::PublicKey) -> Self {
+        Self {
+            genesis_key: Some(key),
+            ..self
+        }
+    }
+
+    /// Use the supplied random number generator. If this is not called, a default `OsRng` is used.
+    pub fn rng<R: RngCore>(self, rng: &mut R) -> Self {
+        Self {
+            rng: Some(rng::new_from(rng)),
+            ..self
+        }
+    }
+
+    /// Creates new `Client` and starts bootstrapping it to the network.
+    pub fn create(self) -> Result<(Client, mpmc::Receiver<Event>), RoutingError> {
+        let mut rng = self.rng.unwrap_or_else(rng::new);
+        let full_id = self.full_id.unwrap_or_else(|| FullId::gen(&mut rng));
+
+        let mut network_config = self.network_config.unwrap_or_default();
+        network_config.our_type = OurType::Client;
+
+        let (network_tx, network_rx) = mpmc::unbounded();
+        let mut quic_p2p = QuicP2pBuilder::new(network_tx)
+            .with_config(network_config)
+            .build()?;
+        quic_p2p.bootstrap();
+
+        let (user_event_tx, user_event_rx) = mpmc::unbounded();
+
+        let client = Client {
+            full_id,
+            quic_p2p,
+            network_rx,
+            network_rx_idx: 0,
+            user_event_tx,
+            is_running: true,
+            proxy: None,
+            proxy_id: None,
+            elders_info: None,
+            genesis_key_info: self
+                .genesis_key
+                .map(|key| SectionKeyInfo::new(0, Prefix::default(), key)),
+            their_keys: BTreeMap::new(),
+            next_msg_token: 0,
+        };
+
+        Ok((client, user_event_rx))
+    }
+}
+
+/// Interface for sending and receiving messages to and from the network, in the role of a client.
+///
+/// Unlike a [`Node`](struct.Node.html), a client is not a member of any section. It bootstraps to
+/// a single node (its proxy) which routes the client's messages to their destinations and
+/// delivers the messages addressed to the client. Messages received from the network are verified
+/// against the key of the proxy's section, which the proxy proves back to the genesis key of the
+/// network when the client connects. The client is connected once the proxy's section sent it its
+/// elders, signed with that key. If it loses its proxy, it bootstraps again and raises `Connected`
+/// once its new proxy's section did the same.
+pub struct Client {
+    full_id: FullId,
+    quic_p2p: QuicP2p,
+    network_rx: mpmc::Receiver<NetworkEvent>,
+    network_rx_idx: usize,
+    user_event_tx: mpmc::Sender<Event>,
+    is_running: bool,
+    proxy: Option<ConnectionInfo>,
+    proxy_id: Option<PublicId>,
+    elders_info: Option<EldersInfo>,
+    genesis_key_info: Option<SectionKeyInfo>,
+    their_keys: BTreeMap<Prefix<XorName>, SectionKeyInfo>,
+    next_msg_token: Token,
+}
+
+impl Client {
+    /// Creates a new builder to configure and create a `Client`.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder {
+            rng: None,
+            network_config: None,
+            full_id: None,
+            genesis_key: None,
+        }
+    }
+
+    /// Returns the `PublicId` of this client.
+    pub fn id(&self) -> &PublicId {
+        self.full_id.public_id()
+    }
+
+    /// Returns the `PublicId` of our proxy, once connected. Nodes need its name to send messages
+    /// to this client.
+    pub fn proxy_id(&self) -> Option<&PublicId> {
+        self.proxy_id.as_ref()
+    }
+
+    /// Returns the prefix of the section of our proxy, once connected.
+    pub fn our_prefix(&self) -> Option<&Prefix<XorName>> {
+        self.elders_info.as_ref().map(EldersInfo::prefix)
+    }
+
+    /// Returns the connection information of the elders of the section of our proxy, once
+    /// connected.
+    pub fn our_elders_info(&self) -> Option<impl Iterator<Item = &P2pNode>> {
+        self.elders_info.as_ref().map(EldersInfo::member_nodes)
+    }
+
+    /// Send a message to the given destination. The message is signed by this client and routed
+    /// to its destination by our proxy.
+    pub fn send_message(&mut self, dst: DstLocation, content: Vec<u8>) -> Result<(), RoutingError> {
+        if self.elders_info.is_none() {
+            warn!("{} - Cannot send message - not connected.", self);
+            return Err(RoutingError::InvalidState);
+        }
+
+        let msg = Message::single_src(
+            &self.full_id,
+            PROTOCOL_VERSION,
+            dst,
+            Variant::UserMessage(content),
+        )?;
+        self.send_to_proxy(&ClientMessage::Message(msg))
+    }
+
+    /// Register the client event channels with the provided
+    /// [selector](https://docs.rs/crossbeam-channel/0.3/crossbeam_channel/struct.Select.html).
+    pub fn register<'a>(&'a mut self, select: &mut mpmc::Select<'a>) {
+        self.network_rx_idx = select.recv(&self.network_rx);
+    }
+
+    /// Processes events received externally from one of the channels.
+    /// For this function to work properly, the client event channels need to be registered by
+    /// calling [`Client::register`]. [`Select::ready`] needs to be called to get `op_index`,
+    /// the event channel index.
+    ///
+    /// This function is non-blocking.
+    ///
+    /// Errors are permanent failures due to either: client termination, the permanent closing of
+    /// one of the event channels, or an invalid (unknown) channel index.
+    ///
+    /// [`Client::register`]: #method.register
+    /// [`Select::ready`]: https://docs.rs/crossbeam-channel/0.3/crossbeam_channel/struct.Select.html#method.ready
+    pub fn handle_selected_operation(&mut self, op_index: usize) -> Result<bool, mpmc::RecvError> {
+        if !self.is_running || op_index != self.network_rx_idx {
+            return Err(mpmc::RecvError);
+        }
+
+        let event = self.network_rx.recv()?;
+        self.handle_network_event(event);
+        Ok(true)
+    }
+
+    fn handle_network_event(&mut self, event: NetworkEvent) {
+        use crate::NetworkEvent::*;
+
+        match event {
+            BootstrappedTo { node } => self.handle_bootstrapped_to(node),
+            BootstrapFailure => {
+                info!("{} Failed to bootstrap. Terminating.", self);
+                self.terminate();
+            }
+            ConnectionFailure { peer_addr, .. } => {
+                if self.is_proxy(&peer_addr) {
+                    info!(
+                        "{} Lost connection to proxy {}. Reconnecting.",
+                        self, peer_addr
+                    );
+                    self.proxy = None;
+                    self.proxy_id = None;
+                    self.elders_info = None;
+                    self.quic_p2p.bootstrap();
+                }
+            }
+            NewMessage { peer_addr, msg } => {
+                if self.is_proxy(&peer_addr) {
+                    self.handle_proxy_message(peer_addr, msg)
+                } else {
+                    debug!("{} - Message from unknown peer {}.", self, peer_addr);
+                }
+            }
+            UnsentUserMessage {
+                peer_addr, token, ..
+            } => debug!(
+                "{} - Failed to send message with ID {} to {}.",
+                self, token, peer_addr
+            ),
+            ConnectedTo { .. } | SentUserMessage { .. } => (),
+            Finish => self.terminate(),
+        }
+    }
+
+    fn handle_bootstrapped_to(&mut self, conn_info: ConnectionInfo) {
+        if self.proxy.is_some() {
+            debug!("{} - Ignoring BootstrappedTo {:?}.", self, conn_info);
+            return;
+        }
+
+        debug!("{} - Bootstrapped to {:?}.", self, conn_info);
+        self.proxy = Some(conn_info);
+
+        if let Err(error) = self.send_to_proxy(&ClientMessage::GetSectionInfo) {
+            error!("{} - Failed to request section info: {:?}", self, error);
+        }
+    }
+
+    fn handle_proxy_message(&mut self, peer_addr: SocketAddr, bytes: Bytes) {
+        match ClientMessage::from_bytes(&bytes) {
+            Ok(ClientMessage::SectionInfo { proxy_id, proof }) => {
+                self.handle_section_info(proxy_id, proof)
+            }
+            Ok(ClientMessage::Message(msg)) => self.handle_message(msg),
+            Ok(msg) => debug!("{} - Unexpected message from proxy: {:?}", self, msg),
+            Err(_) => {
+                // Not a routing message - pass it to the user library as is.
+                self.send_event(
+                    event::Client::NewMessage {
+                        peer_addr,
+                        msg: bytes,
+                    }
+                    .into(),
+                );
+            }
+        }
+    }
+
+    fn handle_section_info(&mut self, proxy_id: PublicId, proof: SectionProofChain) {
+        if self.proxy_id.is_some() {
+            // Once connected, section updates are only accepted through signed proofs.
+            debug!("{} - Ignoring unsolicited section info.", self);
+            return;
+        }
+
+        let key_info = proof.last_key_info();
+        if !key_info.prefix().matches(proxy_id.name()) {
+            debug!("{} - Section info of another section: {:?}", self, key_info);
+            return;
+        }
+
+        if !self.is_trusted_history(&proof) {
+            debug!("{} - Untrusted section info: {:?}", self, key_info);
+            return;
+        }
+
+        // The genesis key is our root of trust: every section-signed message we accept must prove
+        // its key back to it, possibly through the key of the proxy's section.
+        if self.genesis_key_info.is_none() {
+            self.genesis_key_info = Some(proof.first_key_info().clone());
+        }
+        self.update_their_keys(key_info);
+        // We are connected once the section of our proxy sends us its elders, signed.
+        self.proxy_id = Some(proxy_id);
+    }
+
+    // Accepts the elders of the section of our proxy, if signed with the key of that section.
+    fn handle_elders_info(&mut self, elders_info: EldersInfo, key_info: Option<SectionKeyInfo>) {
+        let is_consistent = self.proxy_id.map_or(false, |proxy_id| {
+            elders_info.prefix().matches(proxy_id.name())
+        }) && key_info.map_or(false, |key_info| {
+            key_info.prefix() == elders_info.prefix() && key_info.version() == elders_info.version()
+        });
+        if !is_consistent {
+            debug!("{} - Inconsistent section info: {:?}", self, elders_info);
+            return;
+        }
+
+        match &self.elders_info {
+            Some(old_info) if old_info.version() >= elders_info.version() => (),
+            Some(_) => self.elders_info = Some(elders_info),
+            None => {
+                self.elders_info = Some(elders_info);
+                self.send_event(Event::Connected(Connected::First));
+            }
+        }
+    }
+
+    fn handle_message(&mut self, msg: Message) {
+        // All the keys we trust are proven back to the genesis key, so section-signed messages,
+        // including the ones delivered by our proxy, must be too.
+        let their_key_infos = self.their_keys.iter().chain(
+            self.genesis_key_info
+                .iter()
+                .map(|key_info| (key_info.prefix(), key_info)),
+        );
+        match msg.verify(their_key_infos) {
+            Ok(VerifyStatus::Full) => (),
+            Ok(VerifyStatus::ProofTooNew) => {
+                debug!("{} - Untrusted message: {:?}", self, msg);
+                return;
+            }
+            Err(error) => {
+                debug!("{} - Verification failed: {:?} - {:?}", self, msg, error);
+                return;
+            }
+        }
+
+        let src_key_info = msg.source_section_key_info().cloned();
+        if let Some(key_info) = &src_key_info {
+            self.update_their_keys(key_info);
+        }
+
+        let our_name = *self.id().name();
+        let via_proxy = match (&msg.dst, &self.proxy_id) {
+            (DstLocation::Node(name), Some(proxy_id)) => name == proxy_id.name(),
+            _ => false,
+        };
+
+        match msg.variant {
+            Variant::UserMessageToClient { client, content } if via_proxy && client == our_name => {
+                self.send_event(Event::MessageReceived {
+                    content,
+                    src: msg.src.location(),
+                    dst: DstLocation::Node(our_name),
+                })
+            }
+            Variant::UserMessage(content) if msg.dst == DstLocation::Node(our_name) => self
+                .send_event(Event::MessageReceived {
+                    content,
+                    src: msg.src.location(),
+                    dst: msg.dst,
+                }),
+            Variant::SectionInfoResponse(elders_info) if via_proxy => {
+                self.handle_elders_info(elders_info, src_key_info)
+            }
+            variant => debug!("{} - Message not for us: {:?}", self, variant),
+        }
+    }
+
+    // Returns whether `proof` is a valid chain of signatures starting at the genesis key of the
+    // network, which must be the one we were configured with, if any.
+    fn is_trusted_history(&self, proof: &SectionProofChain) -> bool {
+        let first_key_info = proof.first_key_info();
+        if first_key_info.version() != 0 || *first_key_info.prefix() != Prefix::default() {
+            return false;
+        }
+
+        if let Some(genesis_key_info) = &self.genesis_key_info {
+            if genesis_key_info != first_key_info {
+                return false;
+            }
+        }
+
+        proof.validate()
+    }
+
+    // Replaces the keys of the sections compatible with the one in `key_info`, if it is newer.
+    fn update_their_keys(&mut self, key_info: &SectionKeyInfo) {
+        let outdated: Vec<_> = self
+            .their_keys
+            .iter()
+            .filter(|(prefix, _)| prefix.is_compatible(key_info.prefix()))
+            .map(|(prefix, info)| (*prefix, info.version()))
+            .collect();
+
+        if outdated
+            .iter()
+            .any(|(_, version)| *version >= key_info.version())
+        {
+            return;
+        }
+
+        for (prefix, _) in outdated {
+            let _ = self.their_keys.remove(&prefix);
+        }
+
+        let _ = self.their_keys.insert(*key_info.prefix(), key_info.clone());
+    }
+
+    fn send_to_proxy(&mut self, msg: &ClientMessage) -> Result<(), RoutingError> {
+        let node_info = self.proxy.clone().ok_or(RoutingError::InvalidState)?;
+        let bytes = msg.to_bytes()?;
+
+        self.next_msg_token = self.next_msg_token.wrapping_add(1);
+        self.quic_p2p
+            .send(Peer::Node { node_info }, bytes, self.next_msg_token);
+
+        Ok(())
+    }
+
+    fn is_proxy(&self, peer_addr: &SocketAddr) -> bool {
+        self.proxy
+            .as_ref()
+            .map_or(false, |proxy| proxy.peer_addr == *peer_addr)
+    }
+
+    fn terminate(&mut self) {
+        self.is_running = false;
+        self.send_event(Event::Terminated);
+    }
+
+    fn send_event(&self, event: Event) {
+        let _ = self.user_event_tx.send(event);
+    }
+}
+
+impl Display for Client {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "Client({})", self.id().name())
+    }
+}
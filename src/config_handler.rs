@@ -0,0 +1,171 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{chain::NetworkParams, error::RoutingError, id::FullId, rng::MainRng, NetworkConfig};
+use config_file_handler::FileHandler;
+use std::{
+    env,
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+/// Environment variable holding the passphrase the identity file of `NodeConfig::full_id_file` is
+/// encrypted with. If it is not set, the file is not encrypted.
+pub const FULL_ID_PASSPHRASE_VAR: &str = "ROUTING_FULL_ID_PASSPHRASE";
+
+/// Configuration of a node, as read from a JSON config file.
+///
+/// All the fields are optional in the file: missing ones take their default value.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NodeConfig {
+    /// Config of the network layer: hard-coded contacts, port, etc. If not set, the network layer
+    /// uses its own config file.
+    pub network_config: Option<NetworkConfig>,
+    /// Network parameters.
+    pub network_params: NetworkParams,
+    /// File holding the identity of the node. If the file doesn't exist yet, a new identity is
+    /// generated and written to it, so the node keeps the same identity across restarts. If not
+    /// set, a new identity is generated on every start. The file is encrypted with the passphrase
+    /// in the `ROUTING_FULL_ID_PASSPHRASE` environment variable, if set.
+    pub full_id_file: Option<PathBuf>,
+    /// Directory the node writes snapshots of its state to.
+    pub snapshot_dir: Option<PathBuf>,
+}
+
+/// Reads the config from the JSON file at `path`.
+pub fn read_config_file(path: &Path) -> Result<NodeConfig, RoutingError> {
+    let file = File::open(path)?;
+    let config = serde_json::from_reader(BufReader::new(file)).map_err(io::Error::from)?;
+    Ok(config)
+}
+
+/// Reads the config from the file named `<executable name>.routing.config`, searched for in the
+/// directory of the executable, the user's app directory and the system cache directory.
+pub fn read_default_config_file() -> Result<NodeConfig, RoutingError> {
+    // if the config file is not present, a default one will be generated
+    let file_handler = FileHandler::new(&default_file_name()?, false)?;
+    let config = file_handler.read_file()?;
+    Ok(config)
+}
+
+/// Returns the passphrase of the identity file from the `ROUTING_FULL_ID_PASSPHRASE` environment
+/// variable, if set.
+pub fn full_id_passphrase_from_env() -> Option<String> {
+    env::var(FULL_ID_PASSPHRASE_VAR).ok()
+}
+
+/// Reads the identity stored in the file at `path`, or generates a new one and writes it to the
+/// file if it doesn't exist yet. The identity is encrypted with `passphrase`, if given. New files
+/// are only readable and writable by their owner.
+pub fn load_or_create_full_id(
+    path: &Path,
+    passphrase: Option<&str>,
+    rng: &mut MainRng,
+) -> Result<FullId, RoutingError> {
+    match fs::read(path) {
+        Ok(bytes) => FullId::import(&bytes, passphrase),
+        Err(ref error) if error.kind() == ErrorKind::NotFound => {
+            let full_id = FullId::gen(rng);
+            let mut file = secret_file_options().open(path)?;
+            file.write_all(&full_id.export(passphrase)?)?;
+            file.sync_all()?;
+            Ok(full_id)
+        }
+        Err(error) => Err(error.into()),
+    }
+}
+
+fn secret_file_options() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    let _ = options.write(true).create_new(true);
+    #[cfg(unix)]
+    let _ = options.mode(0o600);
+    options
+}
+
+fn default_file_name() -> Result<OsString, RoutingError> {
+    let mut name = config_file_handler::exe_file_stem()?;
+    name.push(".routing.config");
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rng, unwrap};
+    use std::env;
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("routing-{}-{:016x}", name, rand::random::<u64>()))
+    }
+
+    #[test]
+    fn read_partial_config() {
+        let path = temp_path("config");
+        unwrap!(fs::write(
+            &path,
+            r#"{ "full_id_file": "/var/lib/routing/id", "snapshot_dir": null }"#
+        ));
+
+        let config = unwrap!(read_config_file(&path));
+        let _ = fs::remove_file(path);
+
+        assert!(config.network_config.is_none());
+        assert_eq!(config.network_params, NetworkParams::default());
+        assert_eq!(
+            config.full_id_file,
+            Some(PathBuf::from("/var/lib/routing/id"))
+        );
+        assert!(config.snapshot_dir.is_none());
+    }
+
+    #[test]
+    fn config_round_trip() {
+        let config = NodeConfig {
+            network_params: NetworkParams {
+                elder_size: 5,
+                safe_section_size: 10,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let path = temp_path("config");
+        unwrap!(fs::write(&path, unwrap!(serde_json::to_vec(&config))));
+        let read_config = unwrap!(read_config_file(&path));
+        let _ = fs::remove_file(path);
+
+        assert_eq!(read_config.network_params, config.network_params);
+    }
+
+    #[test]
+    fn full_id_is_created_once() {
+        let mut rng = rng::new();
+        let path = temp_path("full-id");
+
+        let full_id = unwrap!(load_or_create_full_id(&path, Some("passphrase"), &mut rng));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = unwrap!(fs::metadata(&path)).permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let loaded_full_id = unwrap!(load_or_create_full_id(&path, Some("passphrase"), &mut rng));
+        let wrong_passphrase_result = load_or_create_full_id(&path, None, &mut rng);
+        let _ = fs::remove_file(path);
+
+        assert_eq!(full_id.public_id(), loaded_full_id.public_id());
+        assert!(wrong_passphrase_result.is_err());
+    }
+}
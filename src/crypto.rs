@@ -28,4 +28,22 @@ pub mod signing {
 /// Encryption and decryption
 pub mod encryption {
     pub use bls::{Ciphertext, PublicKey, SecretKey};
+
+    /// Serde support for `SecretKey`, for use with `#[serde(with = "...")]`.
+    pub mod serde_secret_key {
+        use super::SecretKey;
+        use bls::serde_impl::SerdeSecret;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        pub fn serialize<S: Serializer>(key: &SecretKey, serialiser: S) -> Result<S::Ok, S::Error> {
+            SerdeSecret(key.clone()).serialize(serialiser)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserialiser: D,
+        ) -> Result<SecretKey, D::Error> {
+            let key: SerdeSecret<SecretKey> = Deserialize::deserialize(deserialiser)?;
+            Ok(key.0)
+        }
+    }
 }
@@ -0,0 +1,69 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{id::P2pNode, xor_space::XorName};
+use std::cmp::Reverse;
+
+/// A joined member of our section that can be chosen as one of its elders, as agreed on by the
+/// section.
+#[derive(Clone, Copy, Debug)]
+pub struct ElderCandidate<'a> {
+    /// The member, including the address it is reachable at.
+    pub p2p_node: &'a P2pNode,
+    /// Age counter of the member. Its age is the base-2 logarithm of it.
+    pub age_counter: u32,
+    /// Weighted sum of the misbehaviours of the member our section agreed on.
+    pub misbehaviour_score: u32,
+    /// Number of times our section agreed the member lagged behind in voting, which is its
+    /// agreed responsiveness record.
+    pub unresponsive_count: u32,
+    /// Whether the misbehaviour score of the member reached
+    /// `NetworkParams::misbehaviour_demote_score`.
+    pub is_demoted: bool,
+    /// Number of consecutive elder sets of our section, up to and including the current one, the
+    /// member is part of. Zero if it is not an elder now.
+    pub elder_tenure: usize,
+}
+
+impl ElderCandidate<'_> {
+    /// Name of the member.
+    pub fn name(&self) -> &XorName {
+        self.p2p_node.name()
+    }
+}
+
+/// Rule choosing the elders of our section among its members.
+///
+/// The strategy is applied by all the elders of a section whenever its members change or it
+/// splits, so it must be deterministic and the same on all of them, otherwise they would not agree
+/// on the new elders. It can only rely on what the section agreed on, which is what the
+/// candidates describe: local observations such as how responsive a member was to us are only
+/// available once agreed on, like `ElderCandidate::unresponsive_count`.
+pub trait ElderSelection {
+    /// Returns the names of the elders to choose among the given candidates, which are sorted by
+    /// name. Names which are not candidates are ignored, and only the first `elder_size` are
+    /// kept. If fewer are returned, the eldest of the other candidates are chosen too.
+    fn select_elders(&self, candidates: &[ElderCandidate], elder_size: usize) -> Vec<XorName>;
+}
+
+/// The default strategy: the oldest members are chosen, demoted ones only if there is no one
+/// else. Also used to top up the choice of other strategies.
+pub(crate) struct EldestFirst;
+
+impl ElderSelection for EldestFirst {
+    fn select_elders(&self, candidates: &[ElderCandidate], elder_size: usize) -> Vec<XorName> {
+        let mut candidates = candidates.to_vec();
+        // Stable sort, so the order by name breaks the ties.
+        candidates.sort_by_key(|candidate| (candidate.is_demoted, Reverse(candidate.age_counter)));
+        candidates
+            .iter()
+            .take(elder_size)
+            .map(|candidate| *candidate.name())
+            .collect()
+    }
+}
@@ -9,7 +9,7 @@
 use crate::{quic_p2p, xor_space::XorName};
 use bincode::ErrorKind;
 use err_derive::Error;
-use std::sync::mpsc;
+use std::{io, sync::mpsc};
 
 /// The type returned by the routing message handling methods.
 pub type Result<T, E = RoutingError> = std::result::Result<T, E>;
@@ -46,6 +46,16 @@ pub enum RoutingError {
     InvalidRelocation,
     #[error(display = "An Elder DKG result is invalid.")]
     InvalidElderDkgResult,
+    #[error(display = "The network parameters are inconsistent.")]
+    InvalidNetworkParams,
+    #[error(display = "The passphrase is wrong or the identity is corrupted.")]
+    InvalidPassphrase,
+    #[error(display = "The application payload is too large.")]
+    AppPayloadTooLarge,
     #[error(display = "Error while trying to receive a message from a mpsc channel.")]
     MpscRecvError(mpsc::RecvError),
+    #[error(display = "I/O error.")]
+    Io(io::Error),
+    #[error(display = "Config file handler error.")]
+    ConfigFileHandler(config_file_handler::Error),
 }
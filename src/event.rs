@@ -7,7 +7,10 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
+    chain::NetworkStats,
+    id::PublicId,
     location::{DstLocation, SrcLocation},
+    messages::MessageId,
     xor_space::{Prefix, XorName},
 };
 use bytes::Bytes;
@@ -91,14 +94,76 @@ pub enum Event {
         /// The destination location that receives the message.
         dst: DstLocation,
     },
+    /// Received a request. The response should be sent with `Node::send_response`, using the same
+    /// `id`.
+    RequestReceived {
+        /// The id of the request.
+        id: MessageId,
+        /// The content of the request.
+        content: Vec<u8>,
+        /// The source location that sent the request.
+        src: SrcLocation,
+        /// The destination location that receives the request.
+        dst: DstLocation,
+    },
+    /// Received the response to a request we sent.
+    ResponseReceived {
+        /// The id of the request.
+        id: MessageId,
+        /// The content of the response.
+        content: Vec<u8>,
+        /// The source location that sent the response.
+        src: SrcLocation,
+        /// The destination location that receives the response.
+        dst: DstLocation,
+    },
+    /// No response to a request we sent arrived in time, or we stopped being an elder before it
+    /// did.
+    RequestTimedOut {
+        /// The id of the request.
+        id: MessageId,
+        /// The destination the request was sent to.
+        dst: DstLocation,
+    },
+    /// The destination of a message we sent with a delivery receipt confirmed its delivery.
+    MessageDelivered {
+        /// The id of the message.
+        id: MessageId,
+        /// The destination the message was sent to.
+        dst: DstLocation,
+    },
+    /// No delivery receipt arrived for a message we sent, even after re-sending it.
+    MessageDeliveryFailed {
+        /// The id of the message.
+        id: MessageId,
+        /// The destination the message was sent to.
+        dst: DstLocation,
+    },
     /// Our own section has been split, resulting in the included `Prefix` for our new section.
     SectionSplit(Prefix<XorName>),
+    /// Our own section has merged with its sibling, resulting in the included `Prefix` for our
+    /// new section.
+    SectionMerged(Prefix<XorName>),
     /// The client has successfully connected to a proxy node on the network.
     Connected(Connected),
     /// Disconnected or failed to connect - restart required.
     RestartRequired,
     /// Startup failed - terminate.
     Terminated,
+    /// We left the network after `Node::leave` - terminate.
+    Left,
+    /// The section we tried to join refused us, for the given reason - terminate.
+    JoinRejected(String),
+    /// The section we joined handed over the given application payload. Raised right before
+    /// `Connected`, only if the payload is not empty.
+    ApprovalPayload(Vec<u8>),
+    /// Our section agreed to switch to the given protocol version, which all its members speak.
+    ProtocolVersionChanged(u16),
+    /// Our section evicted the given member for misbehaving.
+    MemberEvicted(PublicId),
+    /// Our section agreed on new statistics of itself and the network, every
+    /// `NetworkParams::network_stats_interval`.
+    NetworkStats(NetworkStats),
     /// Consensus on a custom event.
     Consensus(Vec<u8>),
 }
@@ -126,14 +191,76 @@ impl Debug for Event {
                 src,
                 dst
             ),
+            Self::RequestReceived {
+                ref id,
+                ref content,
+                ref src,
+                ref dst,
+            } => write!(
+                formatter,
+                "Event::RequestReceived {{ id: {:?}, content: \"{:<8}\", src: {:?}, dst: {:?} }}",
+                id,
+                HexFmt(content),
+                src,
+                dst
+            ),
+            Self::ResponseReceived {
+                ref id,
+                ref content,
+                ref src,
+                ref dst,
+            } => write!(
+                formatter,
+                "Event::ResponseReceived {{ id: {:?}, content: \"{:<8}\", src: {:?}, dst: {:?} }}",
+                id,
+                HexFmt(content),
+                src,
+                dst
+            ),
+            Self::RequestTimedOut { ref id, ref dst } => write!(
+                formatter,
+                "Event::RequestTimedOut {{ id: {:?}, dst: {:?} }}",
+                id, dst
+            ),
+            Self::MessageDelivered { ref id, ref dst } => write!(
+                formatter,
+                "Event::MessageDelivered {{ id: {:?}, dst: {:?} }}",
+                id, dst
+            ),
+            Self::MessageDeliveryFailed { ref id, ref dst } => write!(
+                formatter,
+                "Event::MessageDeliveryFailed {{ id: {:?}, dst: {:?} }}",
+                id, dst
+            ),
             Self::SectionSplit(ref prefix) => {
                 write!(formatter, "Event::SectionSplit({:?})", prefix)
             }
+            Self::SectionMerged(ref prefix) => {
+                write!(formatter, "Event::SectionMerged({:?})", prefix)
+            }
             Self::Connected(ref connect_type) => {
                 write!(formatter, "Event::Connected({:?})", connect_type)
             }
             Self::RestartRequired => write!(formatter, "Event::RestartRequired"),
             Self::Terminated => write!(formatter, "Event::Terminated"),
+            Self::Left => write!(formatter, "Event::Left"),
+            Self::ApprovalPayload(ref payload) => {
+                write!(formatter, "Event::ApprovalPayload({:<8})", HexFmt(payload))
+            }
+            Self::JoinRejected(ref reason) => {
+                write!(formatter, "Event::JoinRejected({:?})", reason)
+            }
+            Self::ProtocolVersionChanged(version) => {
+                write!(formatter, "Event::ProtocolVersionChanged({})", version)
+            }
+            Self::MemberEvicted(ref pub_id) => {
+                write!(formatter, "Event::MemberEvicted({})", pub_id)
+            }
+            Self::NetworkStats(ref stats) => write!(
+                formatter,
+                "Event::NetworkStats({:?}, round: {})",
+                stats.section.prefix, stats.round
+            ),
             Self::Consensus(ref payload) => {
                 write!(formatter, "Event::Consensus({:<8})", HexFmt(payload))
             }
@@ -8,12 +8,18 @@
 
 use crate::{
     crypto::{encryption, signing},
+    error::RoutingError,
     parsec,
     rng::{self, MainRng, RngCompat},
     xor_space::XorName,
     ConnectionInfo,
 };
 use bincode::{deserialize, serialize};
+use chacha20poly1305::{
+    aead::{Aead, NewAead},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use rand::Rng as _;
 use rand_crypto::Rng as _;
 use serde::{de::Deserialize, Deserializer, Serialize, Serializer};
 use std::{
@@ -25,6 +31,11 @@ use std::{
     rc::Rc,
 };
 
+/// Memory (in KiB) and passes of the Argon2id derivation of the encryption key of an exported
+/// identity from its passphrase. They make guessing the passphrase expensive.
+const PASSPHRASE_KDF_MEM_COST: u32 = 19 * 1024;
+const PASSPHRASE_KDF_TIME_COST: u32 = 2;
+
 /// Network identity component containing name, and public and private keys.
 #[derive(Clone)]
 pub struct FullId {
@@ -98,6 +109,51 @@ impl FullId {
             &self.secret_keys.signing,
         )
     }
+
+    /// Serialises the identity, including its secret keys, so it can be restored with `import`.
+    /// If a `passphrase` is given, the secret keys are encrypted with a key derived from it.
+    pub fn export(&self, passphrase: Option<&str>) -> Result<Vec<u8>, RoutingError> {
+        let exported = match passphrase {
+            None => ExportedFullId::Plain(self.clone()),
+            Some(passphrase) => {
+                let mut rng = rng::new();
+                let salt: [u8; 16] = rng.gen();
+                let nonce: [u8; 24] = rng.gen();
+                let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+                let ciphertext = cipher
+                    .encrypt(XNonce::from_slice(&nonce), &serialize(self)?[..])
+                    .map_err(|_| RoutingError::InvalidPassphrase)?;
+
+                ExportedFullId::Encrypted {
+                    salt,
+                    nonce,
+                    ciphertext,
+                }
+            }
+        };
+
+        Ok(serialize(&exported)?)
+    }
+
+    /// Restores an identity exported with `export`. Fails with `RoutingError::InvalidPassphrase`
+    /// if the identity is encrypted and the passphrase is missing or wrong.
+    pub fn import(bytes: &[u8], passphrase: Option<&str>) -> Result<Self, RoutingError> {
+        match deserialize(bytes)? {
+            ExportedFullId::Plain(full_id) => Ok(full_id),
+            ExportedFullId::Encrypted {
+                salt,
+                nonce,
+                ciphertext,
+            } => {
+                let passphrase = passphrase.ok_or(RoutingError::InvalidPassphrase)?;
+                let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+                let plaintext = cipher
+                    .decrypt(XNonce::from_slice(&nonce), &ciphertext[..])
+                    .map_err(|_| RoutingError::InvalidPassphrase)?;
+                Ok(deserialize(&plaintext)?)
+            }
+        }
+    }
 }
 
 impl parsec::SecretId for FullId {
@@ -125,8 +181,56 @@ impl parsec::SecretId for FullId {
     }
 }
 
+impl Serialize for FullId {
+    fn serialize<S: Serializer>(&self, serialiser: S) -> Result<S::Ok, S::Error> {
+        self.secret_keys.serialize(serialiser)
+    }
+}
+
+impl<'de> Deserialize<'de> for FullId {
+    fn deserialize<D: Deserializer<'de>>(deserialiser: D) -> Result<Self, D::Error> {
+        let secret_keys: SecretKeys = Deserialize::deserialize(deserialiser)?;
+        let public_id = PublicId::new(
+            signing::PublicKey::from(&secret_keys.signing),
+            secret_keys.encryption.public_key(),
+        );
+
+        Ok(Self {
+            public_id,
+            secret_keys: Rc::new(secret_keys),
+        })
+    }
+}
+
+// Identity as written by `FullId::export`. Encrypted identities are sealed with
+// XChaCha20-Poly1305 under a key derived from the passphrase with Argon2id.
+#[derive(Serialize, Deserialize)]
+enum ExportedFullId {
+    Plain(FullId),
+    Encrypted {
+        salt: [u8; 16],
+        nonce: [u8; 24],
+        ciphertext: Vec<u8>,
+    },
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key, RoutingError> {
+    let config = argon2::Config {
+        variant: argon2::Variant::Argon2id,
+        mem_cost: PASSPHRASE_KDF_MEM_COST,
+        time_cost: PASSPHRASE_KDF_TIME_COST,
+        hash_length: 32,
+        ..argon2::Config::default()
+    };
+    let key = argon2::hash_raw(passphrase.as_bytes(), salt, &config)
+        .map_err(|_| RoutingError::InvalidPassphrase)?;
+    Ok(Key::clone_from_slice(&key))
+}
+
+#[derive(Serialize, Deserialize)]
 struct SecretKeys {
     signing: signing::SecretKey,
+    #[serde(with = "encryption::serde_secret_key")]
     encryption: encryption::SecretKey,
 }
 
@@ -338,4 +442,45 @@ mod tests {
         let parsed = unwrap!(deserialize(&serialised));
         assert_eq!(*full_id.public_id(), parsed);
     }
+
+    #[test]
+    fn full_id_serialisation() {
+        let full_id = FullId::gen(&mut rng::new());
+        let serialised = unwrap!(serialize(&full_id));
+        let parsed: FullId = unwrap!(deserialize(&serialised));
+        assert_eq!(full_id.public_id(), parsed.public_id());
+
+        let message = b"message";
+        assert!(parsed.public_id().verify(message, &full_id.sign(message)));
+    }
+
+    #[test]
+    fn export_and_import() {
+        let full_id = FullId::gen(&mut rng::new());
+
+        let exported = unwrap!(full_id.export(None));
+        let imported = unwrap!(FullId::import(&exported, None));
+        assert_eq!(full_id.public_id(), imported.public_id());
+    }
+
+    #[test]
+    fn export_and_import_with_passphrase() {
+        let full_id = FullId::gen(&mut rng::new());
+
+        let exported = unwrap!(full_id.export(Some("passphrase")));
+        let imported = unwrap!(FullId::import(&exported, Some("passphrase")));
+        assert_eq!(full_id.public_id(), imported.public_id());
+
+        let message = b"message";
+        assert!(full_id.public_id().verify(message, &imported.sign(message)));
+
+        match FullId::import(&exported, Some("wrong passphrase")) {
+            Err(RoutingError::InvalidPassphrase) => (),
+            result => panic!("Unexpected result: {:?}", result.map(|id| *id.public_id())),
+        }
+        match FullId::import(&exported, None) {
+            Err(RoutingError::InvalidPassphrase) => (),
+            result => panic!("Unexpected result: {:?}", result.map(|id| *id.public_id())),
+        }
+    }
 }
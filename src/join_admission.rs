@@ -0,0 +1,146 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    chain::NetworkParams,
+    time::{Duration, Instant},
+};
+use std::{collections::HashMap, net::IpAddr};
+
+/// Default maximum number of peers joining our section at the same time.
+pub const MAX_CONCURRENT_JOINS: usize = 10;
+/// Default maximum number of infants per adult in our section.
+pub const MAX_INFANTS_PER_ADULT: usize = 10;
+/// Default time a peer refused because our section is busy should wait before retrying.
+pub const JOIN_RETRY_DELAY: Duration = Duration::from_secs(60);
+// Default minimum time between two joins from the same IP address in production
+#[cfg(not(feature = "mock_base"))]
+pub const JOIN_THROTTLE_INTERVAL: Duration = Duration::from_secs(30);
+// Disabled in tests, where all the nodes might share an IP address
+#[cfg(feature = "mock_base")]
+pub const JOIN_THROTTLE_INTERVAL: Duration = Duration::from_secs(0);
+
+/// Current load of our section, as far as admitting new nodes is concerned.
+#[derive(Clone, Copy, Debug)]
+pub struct SectionLoad {
+    /// Number of peers currently joining.
+    pub joining: usize,
+    /// Number of infants among our members.
+    pub infants: usize,
+    /// Number of adults (including elders) among our members.
+    pub adults: usize,
+}
+
+/// Admission policy for new nodes joining our section.
+#[derive(Default)]
+pub struct JoinAdmission {
+    // Time of the last admitted join from each IP address.
+    last_joins: HashMap<IpAddr, Instant>,
+}
+
+impl JoinAdmission {
+    /// Decides whether a new node from `ip` can start joining our section. If it can, the join is
+    /// recorded for throttling. Otherwise, returns how long the node should wait before retrying.
+    pub fn admit(
+        &mut self,
+        ip: IpAddr,
+        load: SectionLoad,
+        network_cfg: &NetworkParams,
+    ) -> Result<(), Duration> {
+        let now = Instant::now();
+        let throttle_interval = network_cfg.join_throttle_interval;
+        self.last_joins
+            .retain(|_, time| now.duration_since(*time) < throttle_interval);
+
+        if let Some(time) = self.last_joins.get(&ip) {
+            return Err(throttle_interval - now.duration_since(*time));
+        }
+
+        if load.joining >= network_cfg.max_concurrent_joins {
+            return Err(network_cfg.join_retry_delay);
+        }
+
+        if load.infants >= load.adults.max(1) * network_cfg.max_infants_per_adult {
+            return Err(network_cfg.join_retry_delay);
+        }
+
+        let _ = self.last_joins.insert(ip, now);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "mock_base"))]
+mod tests {
+    use super::*;
+    use fake_clock::FakeClock;
+
+    const IDLE: SectionLoad = SectionLoad {
+        joining: 0,
+        infants: 0,
+        adults: 5,
+    };
+
+    fn network_cfg() -> NetworkParams {
+        NetworkParams {
+            max_concurrent_joins: 2,
+            max_infants_per_adult: 2,
+            join_throttle_interval: Duration::from_secs(10),
+            join_retry_delay: Duration::from_secs(30),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn throttle_per_ip() {
+        let network_cfg = network_cfg();
+        let mut admission = JoinAdmission::default();
+        let ip = IpAddr::from([192, 0, 2, 1]);
+        let other_ip = IpAddr::from([192, 0, 2, 2]);
+
+        assert_eq!(admission.admit(ip, IDLE, &network_cfg), Ok(()));
+        assert_eq!(admission.admit(other_ip, IDLE, &network_cfg), Ok(()));
+
+        FakeClock::advance_time(4_000);
+        assert_eq!(
+            admission.admit(ip, IDLE, &network_cfg),
+            Err(Duration::from_secs(6))
+        );
+
+        FakeClock::advance_time(6_000);
+        assert_eq!(admission.admit(ip, IDLE, &network_cfg), Ok(()));
+    }
+
+    #[test]
+    fn limit_section_load() {
+        let network_cfg = network_cfg();
+        let mut admission = JoinAdmission::default();
+        let ip = IpAddr::from([192, 0, 2, 1]);
+
+        let busy = SectionLoad { joining: 2, ..IDLE };
+        assert_eq!(
+            admission.admit(ip, busy, &network_cfg),
+            Err(network_cfg.join_retry_delay)
+        );
+
+        let too_many_infants = SectionLoad {
+            infants: 10,
+            ..IDLE
+        };
+        assert_eq!(
+            admission.admit(ip, too_many_infants, &network_cfg),
+            Err(network_cfg.join_retry_delay)
+        );
+
+        let infants_only = SectionLoad {
+            infants: 1,
+            adults: 0,
+            ..IDLE
+        };
+        assert_eq!(admission.admit(ip, infants_only, &network_cfg), Ok(()));
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{error::RoutingError, id::P2pNode};
+
+/// Maximum size (in bytes) of the application payloads exchanged when joining a section.
+pub const MAX_APP_PAYLOAD_SIZE: usize = 64 * 1024;
+
+/// Decision of a `JoinPolicy` about a node joining our section.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum JoinDecision {
+    /// The node can join.
+    Accept,
+    /// The node can't join. The reason is sent back to it.
+    Reject(String),
+    /// The node can't join at the moment. It is asked to retry later.
+    Defer,
+}
+
+/// Hook letting the upper layer decide which nodes can join our section.
+///
+/// The policy is consulted by the elders when they receive a join request, before the joining
+/// node is challenged and voted online. All the elders of a section should apply the same policy,
+/// otherwise some of them might vote for nodes the others refuse.
+pub trait JoinPolicy {
+    /// Decides whether `p2p_node` can join our section. `is_relocating` tells whether the node is
+    /// being relocated to us from another section, and `payload` is the application payload it
+    /// attached to its join request.
+    fn decide(&self, p2p_node: &P2pNode, is_relocating: bool, payload: &[u8]) -> JoinDecision;
+}
+
+/// The default policy: every node can join.
+struct AcceptAll;
+
+impl JoinPolicy for AcceptAll {
+    fn decide(&self, _: &P2pNode, _: bool, _: &[u8]) -> JoinDecision {
+        JoinDecision::Accept
+    }
+}
+
+/// Settings for joining sections: the payload we attach to our own join requests, and the policy
+/// and approval payload applied to the join requests of others once we are an elder.
+pub struct JoinConfig {
+    pub policy: Box<dyn JoinPolicy>,
+    pub join_payload: Vec<u8>,
+    pub approval_payload: Vec<u8>,
+}
+
+impl Default for JoinConfig {
+    fn default() -> Self {
+        Self {
+            policy: Box::new(AcceptAll),
+            join_payload: Vec::new(),
+            approval_payload: Vec::new(),
+        }
+    }
+}
+
+/// Checks the application payload is not larger than `MAX_APP_PAYLOAD_SIZE`.
+pub fn check_app_payload(payload: &[u8]) -> Result<(), RoutingError> {
+    if payload.len() > MAX_APP_PAYLOAD_SIZE {
+        Err(RoutingError::AppPayloadTooLarge)
+    } else {
+        Ok(())
+    }
+}
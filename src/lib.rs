@@ -78,17 +78,28 @@ mod macros;
 // Public API
 // ############################################################################
 pub use self::{
+    ageing::{AgeingStrategy, BalancedRelocation, Churn, DefaultAgeing},
+    chain::{MemberPersona, Misbehaviour, NetworkParams, NetworkStats, SectionStats, StatsSummary},
+    client::{Client, ClientBuilder},
+    config_handler::NodeConfig,
+    elder_selection::{ElderCandidate, ElderSelection},
     error::RoutingError,
     id::{FullId, P2pNode, PublicId},
+    join_policy::{JoinDecision, JoinPolicy, MAX_APP_PAYLOAD_SIZE},
     location::{DstLocation, SrcLocation},
+    messages::{MessageId, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION},
     node::{Builder, Node},
     pause::PausedState,
     quic_p2p::{Config as NetworkConfig, NodeInfo as ConnectionInfo},
+    split::{LoadMetrics, SplitCandidate, SplitPolicy},
     xor_space::{Prefix, XorName, XOR_NAME_LEN},
 };
 /// Routing events.
 pub mod event;
 
+#[cfg(feature = "async")]
+pub use self::async_node::{AsyncNode, EventStream, NodeDriver};
+
 // ############################################################################
 // Mock and test API
 // ############################################################################
@@ -105,7 +116,7 @@ pub mod rng;
 pub use self::{
     chain::{
         delivery_group_size, elders_info_for_test, quorum_count, section_proof_slice_for_test,
-        NetworkParams, SectionKeyShare, MIN_AGE,
+        SectionKeyShare, MIN_AGE,
     },
     messages::{AccumulatingMessage, Message, PlainMessage, Variant},
     parsec::generate_bls_threshold_secret_key,
@@ -118,7 +129,7 @@ pub use self::{
 pub mod test_consts {
     pub use crate::{
         chain::{UNRESPONSIVE_THRESHOLD, UNRESPONSIVE_WINDOW},
-        states::{BOOTSTRAP_TIMEOUT, JOIN_TIMEOUT},
+        states::{BOOTSTRAP_TIMEOUT, JOIN_TIMEOUT, LEAVE_TIMEOUT, MAX_LEAVE_ATTEMPTS},
     };
 }
 
@@ -130,9 +141,17 @@ pub use self::mock::parsec::init_mock;
 // ############################################################################
 
 mod action;
+mod ageing;
+#[cfg(feature = "async")]
+mod async_node;
 mod chain;
+mod client;
+mod config_handler;
+mod elder_selection;
 mod error;
 mod id;
+mod join_admission;
+mod join_policy;
 mod location;
 mod message_filter;
 mod messages;
@@ -142,11 +161,16 @@ mod outbox;
 mod parsec;
 mod pause;
 mod peer_map;
+mod pending_deliveries;
+mod pending_requests;
+mod rate_limiter;
 mod relocation;
 #[cfg(not(feature = "mock_base"))]
 mod rng;
 mod routing_message_filter;
 mod signature_accumulator;
+mod snapshot;
+mod split;
 mod state_machine;
 mod states;
 mod time;
@@ -106,6 +106,7 @@ impl AccumulatingMessage {
         };
 
         Some(Message {
+            version: self.content.version,
             src: SrcAuthority::Section {
                 prefix: self.content.src,
                 signature,
@@ -149,6 +150,8 @@ impl AccumulatingMessage {
 /// Section-source message without signature and proof.
 #[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub struct PlainMessage {
+    /// Protocol version agreed by the source section.
+    pub version: u16,
     /// Prefix of the source section.
     pub src: Prefix<XorName>,
     /// Destination location.
@@ -168,7 +171,7 @@ mod tests {
     use super::*;
     use crate::{
         chain::SectionKeyInfo,
-        messages::VerifyStatus,
+        messages::{VerifyStatus, PROTOCOL_VERSION},
         parsec::generate_bls_threshold_secret_key,
         rng::{self, MainRng},
         unwrap, Prefix,
@@ -284,6 +287,7 @@ mod tests {
         use rand::distributions::Standard;
 
         PlainMessage {
+            version: PROTOCOL_VERSION,
             src: gen_prefix(rng),
             dst: DstLocation::Section(rng.gen()),
             variant: Variant::UserMessage(rng.sample_iter(Standard).take(6).collect()),
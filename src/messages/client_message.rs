@@ -0,0 +1,72 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::Message;
+use crate::{
+    chain::SectionProofChain,
+    error::{Result, RoutingError},
+    id::PublicId,
+};
+use bytes::Bytes;
+use std::fmt::{self, Debug, Formatter};
+
+/// Prepended to every serialised `ClientMessage`, to tell it apart from the application payloads
+/// a client and its proxy exchange over the same connection.
+const CLIENT_MESSAGE_TAG: &[u8] = b"routing-client-message";
+
+/// Message exchanged between a client and the node it is bootstrapped to (its proxy).
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
+pub enum ClientMessage {
+    /// Sent by a client to ask the proxy for the info of its section.
+    GetSectionInfo,
+    /// Response to `GetSectionInfo`. The proxy then passes on the elders of its section, as a
+    /// `SectionInfoResponse` signed by the section.
+    SectionInfo {
+        /// The id of the proxy.
+        proxy_id: PublicId,
+        /// The history of the keys of the proxy's section, from the genesis key of the network to
+        /// its current key.
+        proof: SectionProofChain,
+    },
+    /// A routing message, either sent by a client to be routed to its destination or delivered
+    /// to a client by its proxy.
+    Message(Message),
+}
+
+impl ClientMessage {
+    /// Deserialize the message. Fails if `bytes` do not start with the tag of client messages.
+    pub(crate) fn from_bytes(bytes: &Bytes) -> Result<Self> {
+        if !bytes.starts_with(CLIENT_MESSAGE_TAG) {
+            return Err(RoutingError::InvalidMessage);
+        }
+        Ok(bincode::deserialize(&bytes[CLIENT_MESSAGE_TAG.len()..])?)
+    }
+
+    /// Serialize the message, prefixed with the tag of client messages.
+    pub(crate) fn to_bytes(&self) -> Result<Bytes> {
+        let mut bytes = CLIENT_MESSAGE_TAG.to_vec();
+        bincode::serialize_into(&mut bytes, self)?;
+        Ok(bytes.into())
+    }
+}
+
+impl Debug for ClientMessage {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::GetSectionInfo => write!(formatter, "GetSectionInfo"),
+            Self::SectionInfo { proxy_id, proof } => write!(
+                formatter,
+                "SectionInfo {{ proxy_id: {}, key_info: {:?} }}",
+                proxy_id,
+                proof.last_key_info()
+            ),
+            Self::Message(msg) => write!(formatter, "Message({:?})", msg),
+        }
+    }
+}
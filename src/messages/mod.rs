@@ -7,14 +7,18 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod accumulating_message;
+mod client_message;
 mod src_authority;
 mod variant;
+mod version;
 mod with_bytes;
 
 pub use self::{
     accumulating_message::{AccumulatingMessage, PlainMessage},
+    client_message::ClientMessage,
     src_authority::SrcAuthority,
     variant::{BootstrapResponse, JoinRequest, MemberKnowledge, Variant},
+    version::{newest_common_version, VersionRange, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION},
     with_bytes::MessageWithBytes,
 };
 use crate::{
@@ -22,15 +26,20 @@ use crate::{
     error::{Result, RoutingError},
     id::{FullId, PublicId},
     location::DstLocation,
+    rng::MainRng,
     xor_space::{Prefix, XorName},
     ConnectionInfo,
 };
 use bytes::Bytes;
+use rand::Rng;
 use std::fmt::{self, Debug, Formatter};
 
 /// Message sent over the network.
 #[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Message {
+    /// Version of the protocol the message is encoded with. Messages from section members carry
+    /// the version their section agreed on.
+    pub version: u16,
     /// Destination location.
     pub dst: DstLocation,
     /// Source authority.
@@ -39,9 +48,22 @@ pub struct Message {
     pub variant: Variant,
 }
 
+/// Identifier of a request, used to match it with its response.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub struct MessageId(pub u64);
+
+impl MessageId {
+    /// Generates a new random id.
+    pub fn gen(rng: &mut MainRng) -> Self {
+        Self(rng.gen())
+    }
+}
+
 /// Partially deserialized message.
 #[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub struct PartialMessage {
+    /// Version of the protocol the message is encoded with.
+    pub version: u16,
     /// Destination location.
     pub dst: DstLocation,
 }
@@ -65,11 +87,17 @@ impl Message {
     }
 
     /// Creates a message from single node.
-    pub(crate) fn single_src(src: &FullId, dst: DstLocation, variant: Variant) -> Result<Self> {
+    pub(crate) fn single_src(
+        src: &FullId,
+        version: u16,
+        dst: DstLocation,
+        variant: Variant,
+    ) -> Result<Self> {
         let serialized = serialize_for_node_signing(src.public_id(), &dst, &variant)?;
         let signature = src.sign(&serialized);
 
         Ok(Self {
+            version,
             dst,
             src: SrcAuthority::Node {
                 public_id: *src.public_id(),
@@ -103,7 +131,10 @@ impl Message {
     }
 
     pub(crate) fn to_partial(&self) -> PartialMessage {
-        PartialMessage { dst: self.dst }
+        PartialMessage {
+            version: self.version,
+            dst: self.dst,
+        }
     }
 }
 
@@ -111,6 +142,7 @@ impl Debug for Message {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         formatter
             .debug_struct("Message")
+            .field("version", &self.version)
             .field("src", &self.src.location())
             .field("dst", &self.dst)
             .field("variant", &self.variant)
@@ -136,6 +168,7 @@ impl VerifyStatus {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct QueuedMessage {
     pub message: Message,
     pub sender: Option<ConnectionInfo>,
@@ -6,9 +6,10 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::AccumulatingMessage;
+use super::{AccumulatingMessage, MessageId, VersionRange};
 use crate::{
-    chain::{EldersInfo, GenesisPfxInfo},
+    chain::{EldersInfo, GenesisPfxInfo, MergeRequest, NetworkStats, SectionKeyInfo},
+    crypto::Digest256,
     parsec,
     relocation::{RelocateDetails, RelocatePayload},
     xor_space::{Prefix, XorName},
@@ -16,7 +17,10 @@ use crate::{
 };
 use hex_fmt::HexFmt;
 use serde::Serialize;
-use std::fmt::{self, Debug, Formatter};
+use std::{
+    fmt::{self, Debug, Formatter},
+    time::Duration,
+};
 
 #[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 /// Message variant
@@ -25,9 +29,50 @@ pub enum Variant {
     NeighbourInfo(EldersInfo),
     /// User-facing message
     UserMessage(Vec<u8>),
+    /// User-facing request. The destination is expected to reply with `UserResponse` with the same
+    /// id.
+    UserRequest {
+        /// The id of the request.
+        id: MessageId,
+        /// The content of the request.
+        content: Vec<u8>,
+    },
+    /// User-facing response to the `UserRequest` with the same id.
+    UserResponse {
+        /// The id of the request this responds to.
+        id: MessageId,
+        /// The content of the response.
+        content: Vec<u8>,
+    },
+    /// User-facing message whose destination is expected to confirm the delivery with
+    /// `DeliveryReceipt` with the same id.
+    UserMessageWithReceipt {
+        /// The id of the message.
+        id: MessageId,
+        /// Number of times the message was re-sent, so every attempt is a distinct message.
+        attempt: u8,
+        /// The content of the message.
+        content: Vec<u8>,
+    },
+    /// Confirms the delivery of the `UserMessageWithReceipt` with the given id.
+    /// Destination of the message -> Source of the message
+    DeliveryReceipt(MessageId),
+    /// User-facing message to a client, delivered by the client's proxy node.
+    /// Section X -> Proxy node of the client
+    UserMessageToClient {
+        /// Name of the client.
+        client: XorName,
+        /// The content of the message.
+        content: Vec<u8>,
+    },
     /// Approves the joining node as a routing node.
     /// Section X -> Node joining X
-    NodeApproval(Box<GenesisPfxInfo>),
+    NodeApproval {
+        /// Genesis info of the section the node joined.
+        gen_pfx_info: Box<GenesisPfxInfo>,
+        /// Application payload handed over by the section.
+        app_payload: Vec<u8>,
+    },
     /// Acknowledgement that the src section knows that the dst section is at the specified
     /// version.
     /// Section X -> Section Y
@@ -46,19 +91,69 @@ pub enum Variant {
     MessageSignature(Box<AccumulatingMessage>),
     /// Sent from a newly connected peer to the bootstrap node to request connection infos of
     /// members of the section matching the given name.
-    BootstrapRequest(XorName),
+    BootstrapRequest {
+        /// Name the section should match.
+        name: XorName,
+        /// Protocol versions the peer speaks.
+        versions: VersionRange,
+    },
     /// Sent from the bootstrap node to a peer in response to `BootstrapRequest`. It can either
     /// accept the peer into the section, or redirect it to another set of bootstrap peers
     BootstrapResponse(BootstrapResponse),
     /// Sent from a bootstrapping peer to the section that responded with a
     /// `BootstrapResponse::Join` to its `BootstrapRequest`.
     JoinRequest(Box<JoinRequest>),
+    /// Sent from the elders of a section to a peer which requested to join it. The peer is only
+    /// accepted once it solved the challenge.
+    /// Elder -> Joining node
+    ResourceChallenge {
+        /// Minimum size of the data the proof is computed over.
+        target_size: usize,
+        /// Difficulty of the proof of work.
+        difficulty: u8,
+        /// Nonce the proof must be computed with.
+        nonce: [u8; 32],
+    },
+    /// Solution of a `ResourceChallenge`.
+    /// Joining node -> Elder which sent the challenge
+    ResourceProofResponse {
+        /// Nonce of the challenge.
+        nonce: [u8; 32],
+        /// The proof.
+        proof: u64,
+    },
     /// Sent from members of a section to a joining node in response to `ConnectionRequest`
     /// (which is a routing message)
     ConnectionResponse,
     /// Sent from Adults and Infants to Elders. Updates Elders about the sender's knowledge of its
     /// own section.
     MemberKnowledge(MemberKnowledge),
+    /// Sent from a member leaving the network to the elders of its section, which then vote it
+    /// offline.
+    /// Leaving member -> Elders of its section
+    Leave,
+    /// Asks the section of the destination name for its elders.
+    /// Node X -> Section Y
+    SectionInfoRequest,
+    /// The elders of the source section, in response to `SectionInfoRequest`.
+    /// Section Y -> Node X
+    SectionInfoResponse(EldersInfo),
+    /// Statistics of the source section and the network our section agreed on.
+    /// Section X -> Neighbour Y
+    NetworkStats(NetworkStats),
+    /// Sent by a section below the merge threshold to its sibling, to merge with it.
+    /// Section X -> Sibling Y
+    Merge(Box<MergeRequest>),
+    /// Sent by a section absorbing its sibling, for the sibling to sign its new key.
+    /// Section X -> Sibling Y
+    MergeKey(SectionKeyInfo),
+    /// The signature of the key in `MergeKey`, by the sibling being absorbed.
+    /// Section Y -> Sibling X
+    MergeKeySignature(SectionKeyInfo, bls::Signature),
+    /// Sent from an elder which lost its parsec graph (e.g. when resumed from bytes) to the other
+    /// elders of its section, which then start a new parsec instance it can join.
+    /// Elder -> Other elders of its section
+    ParsecRejoin,
     /// Parsec request message
     ParsecRequest(u64, parsec::Request),
     /// Parsec response message
@@ -70,7 +165,38 @@ impl Debug for Variant {
         match self {
             Self::NeighbourInfo(payload) => write!(f, "NeighbourInfo({:?})", payload),
             Self::UserMessage(payload) => write!(f, "UserMessage({})", HexFmt(payload)),
-            Self::NodeApproval(payload) => write!(f, "NodeApproval({:?})", payload),
+            Self::UserRequest { id, content } => {
+                write!(f, "UserRequest({:?}, {})", id, HexFmt(content))
+            }
+            Self::UserResponse { id, content } => {
+                write!(f, "UserResponse({:?}, {})", id, HexFmt(content))
+            }
+            Self::UserMessageWithReceipt {
+                id,
+                attempt,
+                content,
+            } => write!(
+                f,
+                "UserMessageWithReceipt({:?}, {}, {})",
+                id,
+                attempt,
+                HexFmt(content)
+            ),
+            Self::DeliveryReceipt(id) => write!(f, "DeliveryReceipt({:?})", id),
+            Self::UserMessageToClient { client, content } => write!(
+                f,
+                "UserMessageToClient {{ client: {}, content: {} }}",
+                client,
+                HexFmt(content)
+            ),
+            Self::NodeApproval {
+                gen_pfx_info,
+                app_payload,
+            } => f
+                .debug_struct("NodeApproval")
+                .field("gen_pfx_info", gen_pfx_info)
+                .field("app_payload", &format_args!("{:<8}", HexFmt(app_payload)))
+                .finish(),
             Self::AckMessage {
                 src_prefix,
                 ack_version,
@@ -82,11 +208,47 @@ impl Debug for Variant {
             Self::GenesisUpdate(payload) => write!(f, "GenesisUpdate({:?})", payload),
             Self::Relocate(payload) => write!(f, "Relocate({:?})", payload),
             Self::MessageSignature(payload) => write!(f, "MessageSignature({:?})", payload.content),
-            Self::BootstrapRequest(payload) => write!(f, "BootstrapRequest({})", payload),
+            Self::BootstrapRequest { name, versions } => f
+                .debug_struct("BootstrapRequest")
+                .field("name", name)
+                .field("versions", versions)
+                .finish(),
             Self::BootstrapResponse(payload) => write!(f, "BootstrapResponse({:?})", payload),
             Self::JoinRequest(payload) => write!(f, "JoinRequest({:?})", payload),
+            Self::ResourceChallenge {
+                target_size,
+                difficulty,
+                nonce,
+            } => f
+                .debug_struct("ResourceChallenge")
+                .field("target_size", target_size)
+                .field("difficulty", difficulty)
+                .field("nonce", &HexFmt(nonce))
+                .finish(),
+            Self::ResourceProofResponse { nonce, proof } => f
+                .debug_struct("ResourceProofResponse")
+                .field("nonce", &HexFmt(nonce))
+                .field("proof", proof)
+                .finish(),
             Self::ConnectionResponse => write!(f, "ConnectionResponse"),
             Self::MemberKnowledge(payload) => write!(f, "MemberKnowledge({:?})", payload),
+            Self::Leave => write!(f, "Leave"),
+            Self::SectionInfoRequest => write!(f, "SectionInfoRequest"),
+            Self::SectionInfoResponse(payload) => write!(f, "SectionInfoResponse({:?})", payload),
+            Self::NetworkStats(payload) => write!(
+                f,
+                "NetworkStats({:?}, round: {})",
+                payload.section.prefix, payload.round
+            ),
+            Self::Merge(payload) => write!(
+                f,
+                "Merge({:?}, members: {})",
+                payload.elders_info,
+                payload.members.len()
+            ),
+            Self::MergeKey(payload) => write!(f, "MergeKey({:?})", payload),
+            Self::MergeKeySignature(payload, _) => write!(f, "MergeKeySignature({:?})", payload),
+            Self::ParsecRejoin => write!(f, "ParsecRejoin"),
             Self::ParsecRequest(version, _) => write!(f, "ParsecRequest({}, ..)", version),
             Self::ParsecResponse(version, _) => write!(f, "ParsecResponse({}, ..)", version),
         }
@@ -102,6 +264,14 @@ pub enum BootstrapResponse {
     /// The new peer should retry bootstrapping with another section. The set of connection infos
     /// of the members of that section is provided.
     Rebootstrap(Vec<ConnectionInfo>),
+    /// The section can't admit the new peer at the moment. It should retry joining after the given
+    /// delay.
+    RetryLater(Duration),
+    /// The section refused the new peer. The reason is provided.
+    Rejected(String),
+    /// The new peer doesn't speak the protocol version the section agreed on. The version is
+    /// provided.
+    UnsupportedVersion(u16),
 }
 
 /// Request to join a section
@@ -111,6 +281,13 @@ pub struct JoinRequest {
     pub elders_version: u64,
     /// If the peer is being relocated, contains `RelocatePayload`. Otherwise contains `None`.
     pub relocate_payload: Option<RelocatePayload>,
+    /// Digest of the consensus-critical network parameters of the peer.
+    pub network_params_digest: Digest256,
+    /// Application payload, passed to the join policy of the section. At most
+    /// `MAX_APP_PAYLOAD_SIZE` bytes.
+    pub app_payload: Vec<u8>,
+    /// Protocol versions the peer speaks.
+    pub versions: VersionRange,
 }
 
 impl Debug for JoinRequest {
@@ -125,6 +302,15 @@ impl Debug for JoinRequest {
                     .as_ref()
                     .map(|payload| payload.relocate_details()),
             )
+            .field(
+                "network_params_digest",
+                &HexFmt(&self.network_params_digest),
+            )
+            .field(
+                "app_payload",
+                &format_args!("{:<8}", HexFmt(&self.app_payload)),
+            )
+            .field("versions", &self.versions)
             .finish()
     }
 }
@@ -0,0 +1,85 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use std::fmt::{self, Display, Formatter};
+
+/// Newest version of the routing protocol this node speaks. The messages we send are encoded with
+/// it.
+///
+/// A section switches to a newer version only once all its members speak it, so nodes can be
+/// upgraded one at a time.
+pub const PROTOCOL_VERSION: u16 = 1;
+/// Oldest version of the routing protocol this node still speaks.
+pub const MIN_PROTOCOL_VERSION: u16 = 1;
+
+/// Range of protocol versions a node speaks, both ends included.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct VersionRange {
+    /// Oldest version.
+    pub min: u16,
+    /// Newest version.
+    pub max: u16,
+}
+
+impl VersionRange {
+    /// The versions this node speaks.
+    pub const OURS: Self = Self {
+        min: MIN_PROTOCOL_VERSION,
+        max: PROTOCOL_VERSION,
+    };
+
+    /// Returns whether `version` is in this range.
+    pub fn contains(&self, version: u16) -> bool {
+        self.min <= version && version <= self.max
+    }
+}
+
+impl Display for VersionRange {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "{}..={}", self.min, self.max)
+    }
+}
+
+/// Returns the newest version all the given ranges contain. Returns `None` if there are no ranges
+/// or they have no version in common.
+pub fn newest_common_version<'a, I>(ranges: I) -> Option<u16>
+where
+    I: IntoIterator<Item = &'a VersionRange>,
+{
+    let common = ranges.into_iter().try_fold(None, |common, range| {
+        let common = common.unwrap_or(*range);
+        let min = common.min.max(range.min);
+        let max = common.max.min(range.max);
+        if min <= max {
+            Ok(Some(VersionRange { min, max }))
+        } else {
+            Err(())
+        }
+    });
+
+    common.ok()?.map(|range| range.max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn common_version() {
+        let old = VersionRange { min: 1, max: 2 };
+        let new = VersionRange { min: 2, max: 3 };
+        let newer = VersionRange { min: 3, max: 4 };
+
+        assert_eq!(newest_common_version(&[old, new]), Some(2));
+        assert_eq!(newest_common_version(&[new, newer]), Some(3));
+        assert_eq!(newest_common_version(&[old, new, newer]), None);
+        assert_eq!(newest_common_version(&[]), None);
+        assert!(new.contains(2) && new.contains(3));
+        assert!(!new.contains(1) && !new.contains(4));
+    }
+}
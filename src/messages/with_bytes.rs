@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{DstLocation, Message, PartialMessage};
+use super::{DstLocation, Message, PartialMessage, SrcAuthority, Variant};
 use crate::{
     crypto::{self, Digest256},
     error::Result,
@@ -94,6 +94,30 @@ impl MessageWithBytes {
         &self.partial_content.dst
     }
 
+    pub fn message_version(&self) -> u16 {
+        self.partial_content.version
+    }
+
+    /// Returns the source of the message, deserializing only the head of the message if it was
+    /// received as bytes.
+    pub fn message_src(&self) -> Result<SrcAuthority> {
+        if let Some(msg) = self.full_content.as_ref() {
+            return Ok(msg.src.clone());
+        }
+
+        let (_, _, src): (u16, DstLocation, SrcAuthority) = bincode::deserialize(&self.full_bytes)?;
+        Ok(src)
+    }
+
+    /// Returns the number of times the message was re-sent, if it is awaiting a delivery receipt
+    /// and the full message is available.
+    pub fn delivery_attempt(&self) -> Option<u8> {
+        match self.full_content.as_ref()?.variant {
+            Variant::UserMessageWithReceipt { attempt, .. } => Some(attempt),
+            _ => None,
+        }
+    }
+
     fn deserialize_message(&self) -> Result<Message> {
         Message::from_bytes(&self.full_bytes)
     }
@@ -101,7 +125,10 @@ impl MessageWithBytes {
 
 #[cfg(test)]
 mod tests {
-    use super::{super::Variant, *};
+    use super::{
+        super::{Variant, PROTOCOL_VERSION},
+        *,
+    };
     use crate::{id::FullId, rng, unwrap};
     use rand::{distributions::Standard, Rng};
 
@@ -112,16 +139,24 @@ mod tests {
 
         let dst = DstLocation::Section(rng.gen());
         let variant = Variant::UserMessage(rng.sample_iter(Standard).take(6).collect());
-        let msg = unwrap!(Message::single_src(&full_id, dst, variant));
+        let msg = unwrap!(Message::single_src(
+            &full_id,
+            PROTOCOL_VERSION,
+            dst,
+            variant
+        ));
 
         let msg_with_bytes = unwrap!(MessageWithBytes::new(msg.clone(), &LogIdent::new("node")));
         let bytes = msg_with_bytes.full_bytes();
 
         let full_msg = unwrap!(Message::from_bytes(bytes));
         let partial_msg = unwrap!(PartialMessage::from_bytes(bytes));
-        let partial_msg_head = unwrap!(PartialMessage::from_bytes(&bytes.slice(0, 40)));
+        let partial_msg_head = unwrap!(PartialMessage::from_bytes(&bytes.slice(0, 42)));
 
-        let expected_partial = PartialMessage { dst: msg.dst };
+        let expected_partial = PartialMessage {
+            version: PROTOCOL_VERSION,
+            dst: msg.dst,
+        };
 
         assert_eq!(partial_msg, expected_partial);
         assert_eq!(partial_msg_head, expected_partial);
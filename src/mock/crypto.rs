@@ -52,6 +52,7 @@ pub mod signing {
         }
     }
 
+    #[derive(Serialize, Deserialize)]
     pub struct SecretKey([u8; SECRET_KEY_LENGTH]);
 
     impl SecretKey {
@@ -165,6 +166,7 @@ pub mod encryption {
         }
     }
 
+    #[derive(Serialize, Deserialize)]
     pub struct SecretKey([u8; KEY_LENGTH]);
 
     impl SecretKey {
@@ -197,4 +199,20 @@ pub mod encryption {
             true
         }
     }
+
+    /// Serde support for `SecretKey`, for use with `#[serde(with = "...")]`.
+    pub mod serde_secret_key {
+        use super::SecretKey;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        pub fn serialize<S: Serializer>(key: &SecretKey, serialiser: S) -> Result<S::Ok, S::Error> {
+            key.serialize(serialiser)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserialiser: D,
+        ) -> Result<SecretKey, D::Error> {
+            Deserialize::deserialize(deserialiser)
+        }
+    }
 }
@@ -19,6 +19,7 @@ use crossbeam_channel::Sender;
 use std::net::SocketAddr;
 
 use sending_targets_cache::SendingTargetsCache;
+pub use sending_targets_cache::MAX_RESENDS;
 
 /// Struct that handles network operations: sending and receiving messages, as well as resending on
 /// failure.
@@ -26,6 +27,7 @@ pub struct NetworkService {
     quic_p2p: QuicP2p,
     cache: SendingTargetsCache,
     next_msg_token: Token,
+    config: NetworkConfig,
     pub peer_map: PeerMap,
 }
 
@@ -43,6 +45,10 @@ impl NetworkService {
         &mut self.cache
     }
 
+    pub fn set_max_resends(&mut self, max_resends: u8) {
+        self.cache.set_max_resends(max_resends);
+    }
+
     pub fn send_message_to_initial_targets(
         &mut self,
         conn_infos: &[ConnectionInfo],
@@ -87,6 +93,17 @@ impl NetworkService {
         self.quic_p2p.our_connection_info()
     }
 
+    /// Returns the config this service was built with, pinned to the endpoint we are currently
+    /// bound to, so a service rebuilt from it is reachable at the same address.
+    pub fn pinned_config(&mut self) -> NetworkConfig {
+        let mut config = self.config.clone();
+        if let Ok(conn_info) = self.our_connection_info() {
+            config.ip = Some(conn_info.peer_addr.ip());
+            config.port = Some(conn_info.peer_addr.port());
+        }
+        config
+    }
+
     pub fn remove_and_disconnect_all(&mut self) {
         for conn_info in self.peer_map.remove_all() {
             self.quic_p2p.disconnect_from(conn_info.peer_addr);
@@ -96,18 +113,21 @@ impl NetworkService {
 
 pub struct NetworkBuilder {
     quic_p2p: Builder,
+    config: NetworkConfig,
 }
 
 impl NetworkBuilder {
     pub fn new(event_tx: Sender<NetworkEvent>) -> Self {
         Self {
             quic_p2p: Builder::new(event_tx),
+            config: Default::default(),
         }
     }
 
     pub fn with_config(self, config: NetworkConfig) -> Self {
         Self {
-            quic_p2p: self.quic_p2p.with_config(config),
+            quic_p2p: self.quic_p2p.with_config(config.clone()),
+            config,
         }
     }
 
@@ -116,6 +136,7 @@ impl NetworkBuilder {
             quic_p2p: self.quic_p2p.build()?,
             cache: Default::default(),
             next_msg_token: 0,
+            config: self.config,
             peer_map: PeerMap::new(),
         })
     }
@@ -10,7 +10,8 @@ use crate::{quic_p2p::Token, ConnectionInfo};
 use log::LogLevel;
 use std::{collections::HashMap, net::SocketAddr};
 
-const MAX_RESENDS: u8 = 3;
+/// Default number of times sending a message to a target is retried.
+pub const MAX_RESENDS: u8 = 3;
 
 enum TargetState {
     /// we don't know whether the last send attempt succeeded or failed
@@ -23,9 +24,9 @@ enum TargetState {
 }
 
 impl TargetState {
-    pub fn is_complete(&self) -> bool {
+    pub fn is_complete(&self, max_resends: u8) -> bool {
         match *self {
-            Self::Failed(x) => x > MAX_RESENDS,
+            Self::Failed(x) => x > max_resends,
             Self::Sent => true,
             Self::Sending(_) => false,
         }
@@ -39,12 +40,25 @@ impl TargetState {
     }
 }
 
-#[derive(Default)]
 pub struct SendingTargetsCache {
     cache: HashMap<Token, Vec<(ConnectionInfo, TargetState)>>,
+    max_resends: u8,
+}
+
+impl Default for SendingTargetsCache {
+    fn default() -> Self {
+        Self {
+            cache: HashMap::new(),
+            max_resends: MAX_RESENDS,
+        }
+    }
 }
 
 impl SendingTargetsCache {
+    pub fn set_max_resends(&mut self, max_resends: u8) {
+        self.max_resends = max_resends;
+    }
+
     pub fn insert_message(
         &mut self,
         token: Token,
@@ -104,11 +118,12 @@ impl SendingTargetsCache {
     }
 
     /// Finds a Failed target with the lowest number of failed attempts so far, among the ones that
-    /// failed at most MAX_RESENDS times. If there are multiple possibilities, the one with the
+    /// failed at most `max_resends` times. If there are multiple possibilities, the one with the
     /// highest priority (earliest in the list) is taken. Returns None if no such targets exist.
     fn take_next_target(&mut self, token: Token) -> Option<ConnectionInfo> {
+        let max_resends = self.max_resends;
         self.target_states_mut(token)
-            .filter(|(_info, state)| !state.is_complete())
+            .filter(|(_info, state)| !state.is_complete(max_resends))
             .filter_map(|(info, state)| match state {
                 TargetState::Failed(x) => Some((info, *x, state)),
                 _ => None,
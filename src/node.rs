@@ -8,31 +8,41 @@
 
 use crate::{
     action::Action,
-    chain::NetworkParams,
+    ageing::AgeingStrategy,
+    chain::{Misbehaviour, NetworkParams, NetworkStats, Strategies},
+    config_handler::{self, NodeConfig},
+    elder_selection::ElderSelection,
     error::RoutingError,
     event::Event,
     id::{FullId, P2pNode, PublicId},
+    join_policy::{self, JoinConfig, JoinPolicy},
     location::{DstLocation, SrcLocation},
+    messages::MessageId,
     outbox::EventBox,
     pause::PausedState,
     quic_p2p::{OurType, Token},
     rng::{self, MainRng},
+    snapshot::SnapshotStore,
+    split::{LoadMetrics, SplitPolicy},
     state_machine::{State, StateMachine},
     states::{self, BootstrappingPeer, BootstrappingPeerDetails},
-    xor_space::XorName,
+    time::Duration,
+    xor_space::{Prefix, XorName},
     ConnectionInfo, NetworkConfig,
 };
 use bytes::Bytes;
 use crossbeam_channel as mpmc;
 use rand::RngCore;
-use std::{net::SocketAddr, sync::mpsc};
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::mpsc,
+};
 
 #[cfg(feature = "mock_base")]
 use {
-    crate::{
-        chain::{Chain, SectionProofSlice},
-        Prefix,
-    },
+    crate::chain::{Chain, SectionProofSlice},
     std::{
         collections::{BTreeMap, BTreeSet},
         fmt::{self, Display, Formatter},
@@ -47,6 +57,9 @@ pub struct Builder {
     network_config: Option<NetworkConfig>,
     full_id: Option<FullId>,
     network_cfg: NetworkParams,
+    strategies: Strategies,
+    join_config: JoinConfig,
+    snapshot_dir: Option<PathBuf>,
 }
 
 impl Builder {
@@ -71,7 +84,21 @@ impl Builder {
         }
     }
 
-    /// Override the default network config.
+    /// The node will use the identity stored in the given file, encrypted with `passphrase` if
+    /// given. If the file doesn't exist yet, a new identity is generated and written to it. Fails
+    /// if the file can't be read or written, or if the passphrase is wrong.
+    pub fn full_id_file<P: AsRef<Path>>(
+        self,
+        path: P,
+        passphrase: Option<&str>,
+    ) -> Result<Self, RoutingError> {
+        let full_id =
+            config_handler::load_or_create_full_id(path.as_ref(), passphrase, &mut rng::new())?;
+        Ok(self.full_id(full_id))
+    }
+
+    /// Override the default network parameters. They are validated when the node is built, and
+    /// the node terminates immediately if they are inconsistent.
     pub fn network_cfg(self, network_cfg: NetworkParams) -> Self {
         Self {
             network_cfg,
@@ -79,6 +106,132 @@ impl Builder {
         }
     }
 
+    /// The node will age the members of its section and choose their relocation destinations with
+    /// the given strategy. It must be the same on all the nodes of the network.
+    pub fn ageing_strategy<S: AgeingStrategy + 'static>(self, strategy: S) -> Self {
+        Self {
+            strategies: Strategies {
+                ageing: Rc::new(strategy),
+                ..self.strategies
+            },
+            ..self
+        }
+    }
+
+    /// Once an elder, the node will consult the given policy to decide when its section splits.
+    /// It must be the same on all the nodes of the network. By default, a section splits once
+    /// both halves would have `safe_section_size` members.
+    pub fn split_policy<P: SplitPolicy + 'static>(self, policy: P) -> Self {
+        Self {
+            strategies: Strategies {
+                split: Rc::new(policy),
+                ..self.strategies
+            },
+            ..self
+        }
+    }
+
+    /// Once an elder, the node will choose the elders of its section with the given strategy. It
+    /// must be the same on all the nodes of the network. By default, the oldest members are
+    /// chosen.
+    pub fn elder_selection<S: ElderSelection + 'static>(self, strategy: S) -> Self {
+        Self {
+            strategies: Strategies {
+                elder_selection: Rc::new(strategy),
+                ..self.strategies
+            },
+            ..self
+        }
+    }
+
+    /// Once an elder, the node will consult the given policy before accepting nodes into its
+    /// section. By default, every node is accepted.
+    pub fn join_policy<P: JoinPolicy + 'static>(self, policy: P) -> Self {
+        Self {
+            join_config: JoinConfig {
+                policy: Box::new(policy),
+                ..self.join_config
+            },
+            ..self
+        }
+    }
+
+    /// The node will attach the given application payload to its join requests, to be checked by
+    /// the join policy of the section it joins. Fails if the payload is larger than
+    /// `MAX_APP_PAYLOAD_SIZE`.
+    pub fn join_payload(self, payload: Vec<u8>) -> Result<Self, RoutingError> {
+        join_policy::check_app_payload(&payload)?;
+        Ok(Self {
+            join_config: JoinConfig {
+                join_payload: payload,
+                ..self.join_config
+            },
+            ..self
+        })
+    }
+
+    /// Once an elder, the node will hand over the given application payload to the nodes its
+    /// section approves. The payload is part of a message signed by the section, so it must be the
+    /// same on all the elders. Fails if the payload is larger than `MAX_APP_PAYLOAD_SIZE`.
+    pub fn approval_payload(self, payload: Vec<u8>) -> Result<Self, RoutingError> {
+        join_policy::check_app_payload(&payload)?;
+        Ok(Self {
+            join_config: JoinConfig {
+                approval_payload: payload,
+                ..self.join_config
+            },
+            ..self
+        })
+    }
+
+    /// The node will periodically write snapshots of its state to the given directory. On startup,
+    /// the node restores itself from the latest valid snapshot found there, if any, instead of
+    /// joining the network as a new node.
+    pub fn snapshot_dir<P: Into<PathBuf>>(self, dir: P) -> Self {
+        Self {
+            snapshot_dir: Some(dir.into()),
+            ..self
+        }
+    }
+
+    /// Applies the given config: network config, network parameters, identity and snapshot
+    /// directory. The identity file is encrypted with the passphrase in the
+    /// `ROUTING_FULL_ID_PASSPHRASE` environment variable, if set; use `full_id_file` instead to
+    /// pass it directly. Fails if the identity file can't be read or created.
+    pub fn config(self, config: NodeConfig) -> Result<Self, RoutingError> {
+        let mut rng = rng::new();
+        let passphrase = config_handler::full_id_passphrase_from_env();
+        let passphrase = passphrase.as_ref().map(String::as_str);
+        let full_id = config
+            .full_id_file
+            .as_ref()
+            .map(|path| config_handler::load_or_create_full_id(path, passphrase, &mut rng))
+            .transpose()?;
+
+        Ok(Self {
+            network_config: config.network_config.or(self.network_config),
+            full_id: full_id.or(self.full_id),
+            network_cfg: config.network_params,
+            snapshot_dir: config.snapshot_dir.or(self.snapshot_dir),
+            ..self
+        })
+    }
+
+    /// Creates a builder configured from the JSON config file at `path`. See
+    /// [`NodeConfig`](struct.NodeConfig.html) for its content.
+    pub fn from_config_file<P: AsRef<Path>>(path: P) -> Result<Self, RoutingError> {
+        let config = config_handler::read_config_file(path.as_ref())?;
+        Node::builder().config(config)
+    }
+
+    /// Creates a builder configured from the config file in the default search path: a file named
+    /// `<executable name>.routing.config` in the directory of the executable, the user's app
+    /// directory or the system cache directory. A default config file is created if none exists.
+    pub fn from_default_config_file() -> Result<Self, RoutingError> {
+        let config = config_handler::read_default_config_file()?;
+        Node::builder().config(config)
+    }
+
     /// Use the supplied random number generator. If this is not called, a default `OsRng` is used.
     pub fn rng<R: RngCore>(self, rng: &mut R) -> Self {
         Self {
@@ -87,13 +240,47 @@ impl Builder {
         }
     }
 
-    /// Creates new `Node`.
-    pub fn create(self) -> (Node, mpmc::Receiver<Event>) {
+    /// Creates new `Node`, or resumes it from the latest snapshot if there is one. The network
+    /// config, network parameters, strategies and join policy given to the builder apply to a
+    /// resumed node too. Fails with `RoutingError::InvalidNetworkParams` if the network parameters
+    /// are inconsistent, or if the snapshot directory can't be opened or the network service
+    /// can't be started.
+    pub fn create(self) -> Result<(Node, mpmc::Receiver<Event>), RoutingError> {
+        if let Err(error) = self.network_cfg.validate() {
+            error!("Invalid network parameters {:?}", self.network_cfg);
+            return Err(error);
+        }
+
         // start the handler for routing without a restriction to become a full node
         let (interface_result_tx, interface_result_rx) = mpsc::channel();
         let (mut user_event_tx, user_event_rx) = mpmc::unbounded();
 
-        let (_, machine) = self.make_state_machine(&mut user_event_tx);
+        let snapshot_store = match self.snapshot_dir.clone() {
+            Some(dir) => Some(SnapshotStore::new(dir, self.network_cfg.snapshot_interval)?),
+            None => None,
+        };
+        let full_id = self.full_id.as_ref().map(|full_id| *full_id.public_id());
+        let restored_state = snapshot_store
+            .as_ref()
+            .and_then(SnapshotStore::load_latest)
+            .filter(|state| full_id.map_or(true, |id| id == *state.full_id.public_id()));
+
+        let (_, mut machine) = if let Some(mut state) = restored_state {
+            state.join_config = self.join_config;
+            state.chain.set_strategies(self.strategies);
+            state.chain.set_network_cfg(self.network_cfg);
+            if let Some(mut network_config) = self.network_config {
+                network_config.our_type = OurType::Node;
+                state.network_config = network_config;
+            }
+            StateMachine::resume(state)?
+        } else {
+            self.make_state_machine(&mut user_event_tx)?
+        };
+
+        if let Some(store) = snapshot_store {
+            machine.set_snapshot_store(store);
+        }
 
         let node = Node {
             user_event_tx,
@@ -102,27 +289,43 @@ impl Builder {
             machine,
         };
 
-        (node, user_event_rx)
+        Ok((node, user_event_rx))
     }
 
-    fn make_state_machine(self, outbox: &mut dyn EventBox) -> (mpmc::Sender<Action>, StateMachine) {
+    fn make_state_machine(
+        self,
+        outbox: &mut dyn EventBox,
+    ) -> Result<(mpmc::Sender<Action>, StateMachine), RoutingError> {
         let mut rng = self.rng.unwrap_or_else(rng::new);
 
         let full_id = self.full_id.unwrap_or_else(|| FullId::gen(&mut rng));
         let network_cfg = self.network_cfg;
+        let strategies = self.strategies;
+        let join_config = self.join_config;
         let first = self.first;
 
         let mut network_config = self.network_config.unwrap_or_default();
         network_config.our_type = OurType::Node;
 
         StateMachine::new(
-            move |network_service, timer, outbox| {
+            move |mut network_service, timer, outbox| {
+                network_service.set_max_resends(network_cfg.max_resends);
+
                 if first {
                     debug!("Creating a first node in the Elder state");
 
-                    states::Elder::first(network_service, full_id, network_cfg, timer, rng, outbox)
-                        .map(State::Elder)
-                        .unwrap_or(State::Terminated)
+                    states::Elder::first(
+                        network_service,
+                        full_id,
+                        network_cfg,
+                        strategies,
+                        join_config,
+                        timer,
+                        rng,
+                        outbox,
+                    )
+                    .map(State::Elder)
+                    .unwrap_or(State::Terminated)
                 } else {
                     debug!("Creating a node in the BootstrappingPeer state");
 
@@ -130,6 +333,8 @@ impl Builder {
                         network_service,
                         full_id,
                         network_cfg,
+                        strategies,
+                        join_config,
                         timer,
                         rng,
                     }))
@@ -164,6 +369,9 @@ impl Node {
             network_config: None,
             full_id: None,
             network_cfg: Default::default(),
+            strategies: Default::default(),
+            join_config: Default::default(),
+            snapshot_dir: None,
         }
     }
 
@@ -172,11 +380,11 @@ impl Node {
         self.machine.pause()
     }
 
-    /// Resume previously paused node.
-    pub fn resume(state: PausedState) -> (Self, mpmc::Receiver<Event>) {
+    /// Resume previously paused node. Fails if the network service can't be started.
+    pub fn resume(state: PausedState) -> Result<(Self, mpmc::Receiver<Event>), RoutingError> {
         let (interface_result_tx, interface_result_rx) = mpsc::channel();
         let (user_event_tx, user_event_rx) = mpmc::unbounded();
-        let (_, machine) = StateMachine::resume(state);
+        let (_, machine) = StateMachine::resume(state)?;
 
         let node = Self {
             interface_result_tx,
@@ -185,7 +393,7 @@ impl Node {
             machine,
         };
 
-        (node, user_event_rx)
+        Ok((node, user_event_rx))
     }
 
     /// Returns the first `count` names of the nodes in the routing table which are closest
@@ -199,6 +407,35 @@ impl Node {
         self.machine.current().our_elders()
     }
 
+    /// Returns the protocol version our section agreed on, once we are a member of a section.
+    pub fn protocol_version(&self) -> Option<u16> {
+        self.machine.current().protocol_version()
+    }
+
+    /// Returns the genesis key of the network, once we are a member of a section. Clients
+    /// configured with it via `ClientBuilder::genesis_key` only trust sections which prove their
+    /// key back to it.
+    pub fn genesis_key(&self) -> Option<&bls::PublicKey> {
+        self.machine.current().genesis_key()
+    }
+
+    /// Returns the prefix and the elders of the section `name` belongs to, if we know it. Besides
+    /// our own section and its neighbours, an elder learns about the other sections of the network
+    /// by querying them if `NetworkParams::section_map_capacity` is not zero.
+    pub fn section_for(&self, name: &XorName) -> Option<(Prefix<XorName>, Vec<P2pNode>)> {
+        self.machine
+            .current()
+            .section_for(name)
+            .map(|info| (*info.prefix(), info.member_nodes().cloned().collect()))
+    }
+
+    /// Returns the statistics of our section and the network our section last agreed on, if any.
+    /// Sections agree on them every `NetworkParams::network_stats_interval`, which also raises
+    /// `Event::NetworkStats`.
+    pub fn network_stats(&self) -> Option<&NetworkStats> {
+        self.machine.current().network_stats()
+    }
+
     /// Find out if the given XorName matches our prefix.
     pub fn matches_our_prefix(&self, name: &XorName) -> Result<bool, RoutingError> {
         self.machine.current().matches_our_prefix(name)
@@ -233,6 +470,62 @@ impl Node {
             .map(|elder| elder.vote_for_user_event(event));
     }
 
+    /// Reports a member of our section misbehaving, e.g. spamming us. Our section records the
+    /// misbehaviour once its elders agree on it, and demotes, penalises or evicts the member once
+    /// it misbehaved often enough. Fails if we are not an elder or the offender is not our member.
+    pub fn report_misbehaviour(
+        &mut self,
+        offender: PublicId,
+        kind: Misbehaviour,
+    ) -> Result<(), RoutingError> {
+        self.machine
+            .current_mut()
+            .elder_state_mut()
+            .ok_or(RoutingError::InvalidState)?
+            .report_misbehaviour(offender, kind)
+    }
+
+    /// Votes for the load metrics of our section, e.g. how much data it stores. Once its elders
+    /// agree on them, the split policy of our section is consulted again with them. Each value is
+    /// rounded down to a power of two before voting, so elders measuring slightly different
+    /// values still agree, as long as they fall within the same power of two. Fails if we are not
+    /// an elder.
+    pub fn vote_for_load_metrics(&mut self, metrics: LoadMetrics) -> Result<(), RoutingError> {
+        self.machine
+            .current_mut()
+            .elder_state_mut()
+            .ok_or(RoutingError::InvalidState)?
+            .vote_for_load_metrics(metrics);
+        Ok(())
+    }
+
+    /// Sets the application payload handed over to the nodes our section approves, replacing the
+    /// one given to the builder. It must be the same on all the elders of our section. Fails if
+    /// the payload is larger than `MAX_APP_PAYLOAD_SIZE`.
+    pub fn set_approval_payload(&mut self, payload: Vec<u8>) -> Result<(), RoutingError> {
+        join_policy::check_app_payload(&payload)?;
+        let join_config = self
+            .machine
+            .current_mut()
+            .join_config_mut()
+            .ok_or(RoutingError::InvalidState)?;
+        join_config.approval_payload = payload;
+        Ok(())
+    }
+
+    /// Leaves the network gracefully.
+    ///
+    /// Our section elders are notified, so they vote us offline straight away instead of waiting
+    /// for our connections to drop. An elder keeps running until its role is handed over to
+    /// another member. `Event::Left` is raised once we can shut down.
+    pub fn leave(&mut self) -> Result<(), RoutingError> {
+        let action = Action::Leave {
+            result_tx: self.interface_result_tx.clone(),
+        };
+
+        self.perform_action(action)
+    }
+
     /// Send a message.
     pub fn send_message(
         &mut self,
@@ -250,6 +543,95 @@ impl Node {
         self.perform_action(action)
     }
 
+    /// Send a message and ask its destination to confirm the delivery.
+    ///
+    /// `Event::MessageDelivered` is raised when the confirmation arrives. Otherwise the message is
+    /// re-sent through a different delivery group a few times before `Event::MessageDeliveryFailed`
+    /// is raised, so the destination might receive it more than once. When sending on behalf of
+    /// our section, every elder must use the same `id`.
+    pub fn send_message_with_receipt(
+        &mut self,
+        src: SrcLocation,
+        dst: DstLocation,
+        id: MessageId,
+        content: Vec<u8>,
+    ) -> Result<(), RoutingError> {
+        let action = Action::SendMessageWithReceipt {
+            src,
+            dst,
+            id,
+            content,
+            result_tx: self.interface_result_tx.clone(),
+        };
+
+        self.perform_action(action)
+    }
+
+    /// Send a request with the given id.
+    ///
+    /// `Event::ResponseReceived` is raised when the response from `dst` arrives, or
+    /// `Event::RequestTimedOut` if it doesn't arrive within `timeout`. When sending on behalf of
+    /// our section, every elder must use the same `id`.
+    pub fn send_request(
+        &mut self,
+        src: SrcLocation,
+        dst: DstLocation,
+        id: MessageId,
+        content: Vec<u8>,
+        timeout: Duration,
+    ) -> Result<(), RoutingError> {
+        let action = Action::SendRequest {
+            src,
+            dst,
+            id,
+            content,
+            timeout,
+            result_tx: self.interface_result_tx.clone(),
+        };
+
+        self.perform_action(action)
+    }
+
+    /// Send the response to the request with the given id.
+    pub fn send_response(
+        &mut self,
+        src: SrcLocation,
+        dst: DstLocation,
+        id: MessageId,
+        content: Vec<u8>,
+    ) -> Result<(), RoutingError> {
+        let action = Action::SendResponse {
+            src,
+            dst,
+            id,
+            content,
+            result_tx: self.interface_result_tx.clone(),
+        };
+
+        self.perform_action(action)
+    }
+
+    /// Send a message signed by our section to the client with the given name, via its proxy
+    /// node.
+    ///
+    /// The message is only sent once enough elders of our section have sent the same one, and the
+    /// client can verify the section signature itself.
+    pub fn send_message_to_client_from_section(
+        &mut self,
+        proxy: XorName,
+        client: XorName,
+        content: Vec<u8>,
+    ) -> Result<(), RoutingError> {
+        let action = Action::SendMessageToClientFromSection {
+            proxy,
+            client,
+            content,
+            result_tx: self.interface_result_tx.clone(),
+        };
+
+        self.perform_action(action)
+    }
+
     /// Send a message to a client peer.
     pub fn send_message_to_client(
         &mut self,
@@ -287,6 +669,15 @@ impl Node {
         self.interface_result_rx.recv()?
     }
 
+    /// Returns clones of the channels the node receives from, so another thread can wait for them
+    /// to be ready.
+    #[cfg(feature = "async")]
+    pub(crate) fn receivers(
+        &self,
+    ) -> (mpmc::Receiver<crate::NetworkEvent>, mpmc::Receiver<Action>) {
+        self.machine.receivers()
+    }
+
     /// Register the node event channels with the provided
     /// [selector](https://docs.rs/crossbeam-channel/0.3/crossbeam_channel/struct.Select.html).
     pub fn register<'a>(&'a mut self, select: &mut mpmc::Select<'a>) {
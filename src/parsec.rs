@@ -45,15 +45,15 @@ pub use inner::{DkgResult, DkgResultWrapper};
 // The maximum number of parsec instances to store.
 const MAX_PARSECS: usize = 10;
 
-// Limit in production
+// Default limit in production
 #[cfg(not(feature = "mock_base"))]
-const PARSEC_SIZE_LIMIT: u64 = 1_000_000_000;
+pub const PARSEC_SIZE_LIMIT: u64 = 1_000_000_000;
 // Limit in integration tests
 #[cfg(all(feature = "mock_base", not(feature = "mock")))]
-const PARSEC_SIZE_LIMIT: u64 = 20_000_000;
+pub const PARSEC_SIZE_LIMIT: u64 = 20_000_000;
 // Limit for integration tests with mock-parsec
 #[cfg(feature = "mock")]
-const PARSEC_SIZE_LIMIT: u64 = 500;
+pub const PARSEC_SIZE_LIMIT: u64 = 500;
 
 // Keep track of size in case we need to prune.
 #[derive(Default, Debug, PartialEq, Eq)]
@@ -67,8 +67,8 @@ impl ParsecSizeCounter {
         self.size_counter += size;
     }
 
-    fn needs_pruning(&self) -> bool {
-        self.size_counter > PARSEC_SIZE_LIMIT && !self.pruning_voted_for
+    fn needs_pruning(&self, size_limit: u64) -> bool {
+        self.size_counter > size_limit && !self.pruning_voted_for
     }
 
     fn set_pruning_voted_for(&mut self) {
@@ -82,13 +82,27 @@ impl fmt::Display for ParsecSizeCounter {
     }
 }
 
-#[derive(Default)]
 pub struct ParsecMap {
     map: BTreeMap<u64, Parsec>,
     size_counter: ParsecSizeCounter,
+    size_limit: u64,
+}
+
+impl Default for ParsecMap {
+    fn default() -> Self {
+        Self {
+            map: Default::default(),
+            size_counter: Default::default(),
+            size_limit: PARSEC_SIZE_LIMIT,
+        }
+    }
 }
 
 impl ParsecMap {
+    pub fn with_size_limit(self, size_limit: u64) -> Self {
+        Self { size_limit, ..self }
+    }
+
     pub fn with_init(
         mut self,
         rng: &mut MainRng,
@@ -221,6 +235,16 @@ impl ParsecMap {
         None
     }
 
+    // Drops all the instances, e.g. when joining a section with a different genesis.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.size_counter = ParsecSizeCounter::default();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
     pub fn last_version(&self) -> u64 {
         if let Some(version) = self.map.keys().last() {
             *version
@@ -283,7 +307,7 @@ impl ParsecMap {
     }
 
     pub fn needs_pruning(&self) -> bool {
-        self.size_counter.needs_pruning()
+        self.size_counter.needs_pruning(self.size_limit)
     }
 
     pub fn set_pruning_voted_for(&mut self) {
@@ -297,7 +321,7 @@ impl ParsecMap {
                 "{} - Parsec size is now estimated to: {} / {}.",
                 log_ident,
                 self.size_counter,
-                PARSEC_SIZE_LIMIT,
+                self.size_limit,
             );
         }
     }
@@ -432,11 +456,11 @@ mod tests {
     #[test]
     fn parsec_size_counter() {
         let mut counter = ParsecSizeCounter::default();
-        assert!(!counter.needs_pruning());
+        assert!(!counter.needs_pruning(PARSEC_SIZE_LIMIT));
         counter.increase_size(PARSEC_SIZE_LIMIT);
-        assert!(!counter.needs_pruning());
+        assert!(!counter.needs_pruning(PARSEC_SIZE_LIMIT));
         counter.increase_size(1);
-        assert!(counter.needs_pruning());
+        assert!(counter.needs_pruning(PARSEC_SIZE_LIMIT));
     }
 
     fn create_full_ids(rng: &mut MainRng) -> Vec<FullId> {
@@ -7,42 +7,132 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    chain::{Chain, GenesisPfxInfo},
+    ageing::AgeingStrategy,
+    chain::{Chain, GenesisPfxInfo, Strategies},
+    elder_selection::ElderSelection,
+    error::RoutingError,
     id::FullId,
+    join_policy::{self, JoinConfig, JoinPolicy},
     messages::QueuedMessage,
-    network_service::NetworkService,
     parsec::ParsecMap,
+    rng::MainRng,
     routing_message_filter::RoutingMessageFilter,
     signature_accumulator::SignatureAccumulator,
-    NetworkEvent,
+    split::SplitPolicy,
+    utils::LogIdent,
+    NetworkConfig,
 };
-use crossbeam_channel as mpmc;
-use std::collections::VecDeque;
+use std::{collections::VecDeque, rc::Rc};
 
 /// A type that wraps the internal state of a node while it is paused in order to be upgraded and/or
 /// restarted. A value of this type is obtained by pausing a node and can be then used to resume
 /// it.
-// TODO: this is just a placeholder for now which allows us to have the pause/resume API in place
-// and have tests passing. To make it actually work for all its intended purposes, we need to make
-// sure this type is serialisable/deserialisable in a forward compatible way - that is, we must be
-// able to create a value of this type in routing version X and use it to resume the node in any
-// version >= X.
+///
+/// The state can be converted to bytes with `to_bytes` and restored with `from_bytes`, so the node
+/// can be resumed in a different process. The network layer is not part of the state: only its
+/// config is stored and the network service is rebuilt on resume, bound to the same endpoint.
+/// The message filter and the parsec instances are not serialised either: parsec provides no way
+/// to serialise its instances, and the filter expires its entries based on `Instant`s, which mean
+/// nothing in another process. The filter starts empty after a round-trip through bytes. An adult
+/// re-initialises parsec from `gen_pfx_info`, while an elder - which would fork the graph of its
+/// section by voting in a fresh instance of the same version - asks the other elders to start a
+/// new instance and joins that one. The join
+/// policy, payloads and ageing strategy are lost too, and can be set again with `set_join_policy`,
+/// `set_join_payload`, `set_approval_payload` and `set_ageing_strategy`.
+// TODO: the serialised form is not yet forward compatible - that is, a value serialised by routing
+// version X is only guaranteed to be resumable by the same version X.
+#[derive(Serialize, Deserialize)]
 pub struct PausedState {
     pub(super) chain: Chain,
     pub(super) full_id: FullId,
     pub(super) gen_pfx_info: GenesisPfxInfo,
+    #[serde(skip)]
     pub(super) msg_filter: RoutingMessageFilter,
     pub(super) msg_queue: VecDeque<QueuedMessage>,
     pub(super) msg_backlog: Vec<QueuedMessage>,
-    // TODO: instead of storing both network_service and network_rx, store only the network config.
-    pub(super) network_service: NetworkService,
-    pub(super) network_rx: Option<mpmc::Receiver<NetworkEvent>>,
+    pub(super) network_config: NetworkConfig,
     pub(super) sig_accumulator: SignatureAccumulator,
+    #[serde(skip)]
     pub(super) parsec_map: ParsecMap,
+    #[serde(skip)]
+    pub(super) join_config: JoinConfig,
 }
 
 impl PausedState {
+    /// Serialises the state so it can be stored and later restored with `from_bytes`.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, RoutingError> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    /// Restores a state previously serialised with `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RoutingError> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+
+    /// Sets the policy applied to nodes joining our section once the node is resumed.
+    pub fn set_join_policy<P: JoinPolicy + 'static>(&mut self, policy: P) {
+        self.join_config.policy = Box::new(policy);
+    }
+
+    /// Sets the payload attached to our join requests once the node is resumed. Fails if the
+    /// payload is larger than `MAX_APP_PAYLOAD_SIZE`.
+    pub fn set_join_payload(&mut self, payload: Vec<u8>) -> Result<(), RoutingError> {
+        join_policy::check_app_payload(&payload)?;
+        self.join_config.join_payload = payload;
+        Ok(())
+    }
+
+    /// Sets the payload handed over to the nodes our section approves once the node is resumed.
+    /// Fails if the payload is larger than `MAX_APP_PAYLOAD_SIZE`.
+    pub fn set_approval_payload(&mut self, payload: Vec<u8>) -> Result<(), RoutingError> {
+        join_policy::check_app_payload(&payload)?;
+        self.join_config.approval_payload = payload;
+        Ok(())
+    }
+
+    /// Sets the strategy used to age the members of our section once the node is resumed.
+    pub fn set_ageing_strategy<S: AgeingStrategy + 'static>(&mut self, strategy: S) {
+        self.chain.set_strategies(Strategies {
+            ageing: Rc::new(strategy),
+            ..self.chain.strategies().clone()
+        });
+    }
+
+    /// Sets the policy deciding when our section splits once the node is resumed.
+    pub fn set_split_policy<P: SplitPolicy + 'static>(&mut self, policy: P) {
+        self.chain.set_strategies(Strategies {
+            split: Rc::new(policy),
+            ..self.chain.strategies().clone()
+        });
+    }
+
+    /// Sets the strategy choosing the elders of our section once the node is resumed.
+    pub fn set_elder_selection<S: ElderSelection + 'static>(&mut self, strategy: S) {
+        self.chain.set_strategies(Strategies {
+            elder_selection: Rc::new(strategy),
+            ..self.chain.strategies().clone()
+        });
+    }
+
     pub(super) fn is_elder(&self) -> bool {
         self.chain.is_self_elder()
     }
+
+    // Re-initialises the parsec map and the message filter if they were dropped by a round-trip
+    // through bytes. The parsec map of an elder is left empty until it rejoins parsec.
+    pub(super) fn ensure_parsec_map(&mut self, rng: &mut MainRng) {
+        if self.parsec_map.is_empty() {
+            let network_cfg = self.chain.network_cfg();
+            self.parsec_map = ParsecMap::default().with_size_limit(network_cfg.parsec_size_limit);
+            if !self.is_elder() {
+                let log_ident = LogIdent::new(self.full_id.public_id());
+                self.parsec_map
+                    .init(rng, self.full_id.clone(), &self.gen_pfx_info, &log_ident);
+            }
+            self.msg_filter = RoutingMessageFilter::with_expiry_durations(
+                network_cfg.incoming_filter_expiry,
+                network_cfg.outgoing_filter_expiry,
+            );
+        }
+    }
 }
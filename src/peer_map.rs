@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::ConnectionInfo;
+use crate::{xor_space::XorName, ConnectionInfo};
 use fxhash::{FxHashMap as HashMap, FxHashSet as HashSet};
 use std::net::SocketAddr;
 
@@ -14,6 +14,7 @@ use std::net::SocketAddr;
 pub struct PeerMap {
     connections: HashMap<SocketAddr, ConnectionInfo>,
     clients: HashSet<SocketAddr>,
+    client_names: HashMap<XorName, SocketAddr>,
 }
 
 impl PeerMap {
@@ -54,10 +55,29 @@ impl PeerMap {
     // Inserts a new client entry
     pub fn remove_client(&mut self, peer_addr: &SocketAddr) {
         let _ = self.clients.remove(peer_addr);
+        self.client_names.retain(|_, addr| addr != peer_addr);
+    }
+
+    // Associates the name of a client with its address.
+    pub fn set_client_name(&mut self, peer_addr: SocketAddr, name: XorName) {
+        let _ = self.client_names.insert(name, peer_addr);
+    }
+
+    // Returns the address of the client with the given name.
+    pub fn get_client_addr(&self, name: &XorName) -> Option<SocketAddr> {
+        self.client_names
+            .get(name)
+            .filter(|peer_addr| self.clients.contains(peer_addr))
+            .copied()
     }
 
     // Return true if we know of that peer as a client
     pub fn is_known_client(&self, peer_addr: &SocketAddr) -> bool {
         self.clients.contains(peer_addr)
     }
+
+    // Returns the addresses of all our clients.
+    pub fn clients(&self) -> impl Iterator<Item = &SocketAddr> {
+        self.clients.iter()
+    }
 }
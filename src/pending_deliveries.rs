@@ -0,0 +1,127 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    location::{DstLocation, SrcLocation},
+    messages::MessageId,
+    pending_requests::is_response_from,
+    time::Duration,
+};
+use std::collections::BTreeMap;
+
+/// Time to wait for the delivery receipt of a message before re-sending it.
+pub const DELIVERY_RECEIPT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Number of times a message is sent before giving up waiting for its delivery receipt.
+pub const MAX_DELIVERY_ATTEMPTS: u8 = 3;
+
+/// Messages we sent and are waiting for the delivery receipt of.
+#[derive(Default)]
+pub struct PendingDeliveries {
+    deliveries: BTreeMap<MessageId, PendingDelivery>,
+}
+
+/// Message waiting for its delivery receipt.
+pub struct PendingDelivery {
+    pub src: SrcLocation,
+    pub dst: DstLocation,
+    pub content: Vec<u8>,
+    /// Number of times the message was re-sent.
+    pub attempt: u8,
+    timer_token: u64,
+}
+
+impl PendingDeliveries {
+    /// Starts tracking the delivery of a message. `timer_token` identifies the timeout of the
+    /// current attempt.
+    pub fn insert(
+        &mut self,
+        id: MessageId,
+        src: SrcLocation,
+        dst: DstLocation,
+        content: Vec<u8>,
+        attempt: u8,
+        timer_token: u64,
+    ) {
+        let _ = self.deliveries.insert(
+            id,
+            PendingDelivery {
+                src,
+                dst,
+                content,
+                attempt,
+                timer_token,
+            },
+        );
+    }
+
+    /// Handles a delivery receipt with the given `id` from `src`. If it confirms the delivery of
+    /// one of our messages, the message is no longer pending and its destination is returned.
+    pub fn handle_receipt(&mut self, id: &MessageId, src: &SrcLocation) -> Option<DstLocation> {
+        if !self
+            .deliveries
+            .get(id)
+            .map_or(false, |delivery| is_response_from(&delivery.dst, src))
+        {
+            return None;
+        }
+
+        self.deliveries.remove(id).map(|delivery| delivery.dst)
+    }
+
+    /// Returns the message whose current attempt timed out, if `timer_token` identifies the
+    /// timeout of one of our messages. The message is no longer pending.
+    pub fn handle_timeout(&mut self, timer_token: u64) -> Option<(MessageId, PendingDelivery)> {
+        let id = *self
+            .deliveries
+            .iter()
+            .find(|(_, delivery)| delivery.timer_token == timer_token)?
+            .0;
+        self.deliveries.remove(&id).map(|delivery| (id, delivery))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        id::FullId,
+        rng, unwrap,
+        xor_space::{Prefix, XorName},
+    };
+
+    #[test]
+    fn receipt_and_timeout() {
+        let mut rng = rng::new();
+        let full_id = FullId::gen(&mut rng);
+        let src = SrcLocation::Node(*full_id.public_id());
+        let prefix: Prefix<XorName> = unwrap!("10".parse());
+        let dst = DstLocation::Section(prefix.lower_bound());
+
+        let mut pending = PendingDeliveries::default();
+        let delivered_id = MessageId::gen(&mut rng);
+        let failed_id = MessageId::gen(&mut rng);
+        pending.insert(delivered_id, src, dst, vec![0], 0, 1);
+        pending.insert(failed_id, src, dst, vec![1], 2, 2);
+
+        assert_eq!(pending.handle_receipt(&delivered_id, &src), None);
+        assert_eq!(
+            pending.handle_receipt(&delivered_id, &SrcLocation::Section(prefix)),
+            Some(dst)
+        );
+        assert_eq!(pending.handle_timeout(1).map(|(id, _)| id), None);
+
+        let (id, delivery) = unwrap!(pending.handle_timeout(2));
+        assert_eq!(id, failed_id);
+        assert_eq!(delivery.content, vec![1]);
+        assert_eq!(delivery.attempt, 2);
+        assert_eq!(
+            pending.handle_receipt(&failed_id, &SrcLocation::Section(prefix)),
+            None
+        );
+    }
+}
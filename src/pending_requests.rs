@@ -0,0 +1,152 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    location::{DstLocation, SrcLocation},
+    messages::MessageId,
+};
+use std::{collections::BTreeMap, mem};
+
+/// Requests we sent and are waiting for the response to.
+#[derive(Default)]
+pub struct PendingRequests {
+    requests: BTreeMap<MessageId, PendingRequest>,
+}
+
+struct PendingRequest {
+    dst: DstLocation,
+    timer_token: u64,
+}
+
+impl PendingRequests {
+    /// Starts tracking a request sent to `dst`. `timer_token` identifies its timeout.
+    pub fn insert(&mut self, id: MessageId, dst: DstLocation, timer_token: u64) {
+        let _ = self
+            .requests
+            .insert(id, PendingRequest { dst, timer_token });
+    }
+
+    /// Returns whether a response with the given `id` from `src` is the response to one of our
+    /// requests. If it is, the request is no longer pending.
+    pub fn handle_response(&mut self, id: &MessageId, src: &SrcLocation) -> bool {
+        let is_match = self
+            .requests
+            .get(id)
+            .map_or(false, |request| is_response_from(&request.dst, src));
+        if is_match {
+            let _ = self.requests.remove(id);
+        }
+        is_match
+    }
+
+    /// Returns the id and destination of the request which timed out, if `timer_token`
+    /// identifies the timeout of one of our requests.
+    pub fn handle_timeout(&mut self, timer_token: u64) -> Option<(MessageId, DstLocation)> {
+        let id = *self
+            .requests
+            .iter()
+            .find(|(_, request)| request.timer_token == timer_token)?
+            .0;
+        self.requests.remove(&id).map(|request| (id, request.dst))
+    }
+
+    /// Stops tracking all the requests, returning their ids and destinations.
+    pub fn drain(&mut self) -> impl Iterator<Item = (MessageId, DstLocation)> {
+        mem::replace(&mut self.requests, BTreeMap::new())
+            .into_iter()
+            .map(|(id, request)| (id, request.dst))
+    }
+}
+
+/// Returns whether a response from `src` can be the response to a message sent to `dst`.
+/// Responses to messages sent to a section must come from the section itself, not a single node of
+/// it.
+pub fn is_response_from(dst: &DstLocation, src: &SrcLocation) -> bool {
+    match (dst, src) {
+        (DstLocation::Node(name), SrcLocation::Node(public_id)) => public_id.name() == name,
+        (DstLocation::Section(name), SrcLocation::Section(prefix)) => prefix.matches(name),
+        (DstLocation::Prefix(dst_prefix), SrcLocation::Section(prefix)) => {
+            dst_prefix.is_compatible(prefix)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        id::FullId,
+        rng, unwrap,
+        xor_space::{Prefix, XorName},
+    };
+    use rand::Rng;
+
+    #[test]
+    fn response_from_destination() {
+        let mut rng = rng::new();
+        let full_id = FullId::gen(&mut rng);
+        let other_full_id = FullId::gen(&mut rng);
+
+        let mut pending = PendingRequests::default();
+        let id = MessageId::gen(&mut rng);
+        pending.insert(id, DstLocation::Node(*full_id.public_id().name()), 0);
+
+        // Wrong id or wrong source.
+        assert!(!pending.handle_response(
+            &MessageId::gen(&mut rng),
+            &SrcLocation::Node(*full_id.public_id())
+        ));
+        assert!(!pending.handle_response(&id, &SrcLocation::Node(*other_full_id.public_id())));
+        assert!(!pending.handle_response(&id, &SrcLocation::Section(Prefix::default())));
+
+        assert!(pending.handle_response(&id, &SrcLocation::Node(*full_id.public_id())));
+        // Only the first response matches.
+        assert!(!pending.handle_response(&id, &SrcLocation::Node(*full_id.public_id())));
+    }
+
+    #[test]
+    fn response_from_section() {
+        let mut rng = rng::new();
+        let prefix: Prefix<XorName> = unwrap!("01".parse());
+        let other_prefix: Prefix<XorName> = unwrap!("1".parse());
+
+        let mut pending = PendingRequests::default();
+        let id = MessageId::gen(&mut rng);
+        pending.insert(id, DstLocation::Section(prefix.lower_bound()), 0);
+
+        assert!(!pending.handle_response(&id, &SrcLocation::Section(other_prefix)));
+        assert!(pending.handle_response(&id, &SrcLocation::Section(prefix)));
+    }
+
+    #[test]
+    fn timeout() {
+        let mut rng = rng::new();
+        let mut pending = PendingRequests::default();
+        let id = MessageId::gen(&mut rng);
+        let dst = DstLocation::Section(rng.gen());
+        pending.insert(id, dst, 7);
+
+        assert_eq!(pending.handle_timeout(6), None);
+        assert_eq!(pending.handle_timeout(7), Some((id, dst)));
+        assert_eq!(pending.handle_timeout(7), None);
+        assert!(!pending.handle_response(&id, &SrcLocation::Section(Prefix::default())));
+    }
+
+    #[test]
+    fn drain() {
+        let mut rng = rng::new();
+        let mut pending = PendingRequests::default();
+        let id = MessageId::gen(&mut rng);
+        let dst = DstLocation::Section(rng.gen());
+        pending.insert(id, dst, 7);
+
+        assert_eq!(pending.drain().collect::<Vec<_>>(), vec![(id, dst)]);
+        assert_eq!(pending.handle_timeout(7), None);
+    }
+}
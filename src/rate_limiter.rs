@@ -0,0 +1,69 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    chain::NetworkParams,
+    time::{Duration, Instant},
+    xor_space::XorName,
+};
+use std::collections::HashMap;
+
+/// Default time over which the messages from each member are counted.
+pub const SPAM_INTERVAL: Duration = Duration::from_secs(10);
+/// Default maximum number of messages a member can send us within `SPAM_INTERVAL`.
+pub const SPAM_MESSAGE_LIMIT: usize = 1000;
+
+/// Counts the messages each of our members sends us, to detect the ones flooding us.
+#[derive(Default)]
+pub struct RateLimiter {
+    // Start of the current interval and number of messages within it, for each sender.
+    counts: HashMap<XorName, (Instant, usize)>,
+}
+
+impl RateLimiter {
+    /// Records a message from `sender`. Returns whether it exceeds the limit, which happens at most
+    /// once per interval and sender.
+    pub fn count(&mut self, sender: XorName, network_cfg: &NetworkParams) -> bool {
+        let now = Instant::now();
+        let interval = network_cfg.spam_interval;
+        self.counts
+            .retain(|_, (start, _)| now.duration_since(*start) < interval);
+
+        let (_, count) = self.counts.entry(sender).or_insert((now, 0));
+        *count += 1;
+        *count == network_cfg.spam_message_limit + 1
+    }
+}
+
+#[cfg(all(test, feature = "mock_base"))]
+mod tests {
+    use super::*;
+    use fake_clock::FakeClock;
+
+    #[test]
+    fn limit_per_sender() {
+        let network_cfg = NetworkParams {
+            spam_interval: Duration::from_secs(10),
+            spam_message_limit: 2,
+            ..Default::default()
+        };
+        let mut limiter = RateLimiter::default();
+        let sender = XorName([1; 32]);
+        let other_sender = XorName([2; 32]);
+
+        assert!(!limiter.count(sender, &network_cfg));
+        assert!(!limiter.count(sender, &network_cfg));
+        assert!(!limiter.count(other_sender, &network_cfg));
+        assert!(limiter.count(sender, &network_cfg));
+        // Only reported once per interval.
+        assert!(!limiter.count(sender, &network_cfg));
+
+        FakeClock::advance_time(10_000);
+        assert!(!limiter.count(sender, &network_cfg));
+    }
+}
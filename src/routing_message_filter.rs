@@ -13,8 +13,10 @@ use crate::{
 use lru_time_cache::LruCache;
 use std::time::Duration;
 
-const INCOMING_EXPIRY_DURATION_SECS: u64 = 60 * 20;
-const OUTGOING_EXPIRY_DURATION_SECS: u64 = 60 * 10;
+/// Default time during which an incoming message is remembered.
+pub const INCOMING_EXPIRY_DURATION: Duration = Duration::from_secs(60 * 20);
+/// Default time during which an outgoing message is remembered.
+pub const OUTGOING_EXPIRY_DURATION: Duration = Duration::from_secs(60 * 10);
 
 /// An enum representing a result of message filtering
 #[derive(Eq, PartialEq)]
@@ -42,12 +44,13 @@ pub struct RoutingMessageFilter {
 
 impl RoutingMessageFilter {
     pub fn new() -> Self {
-        let incoming_duration = Duration::from_secs(INCOMING_EXPIRY_DURATION_SECS);
-        let outgoing_duration = Duration::from_secs(OUTGOING_EXPIRY_DURATION_SECS);
+        Self::with_expiry_durations(INCOMING_EXPIRY_DURATION, OUTGOING_EXPIRY_DURATION)
+    }
 
+    pub fn with_expiry_durations(incoming_expiry: Duration, outgoing_expiry: Duration) -> Self {
         Self {
-            incoming: MessageFilter::with_expiry_duration(incoming_duration),
-            outgoing: LruCache::with_expiry_duration(outgoing_duration),
+            incoming: MessageFilter::with_expiry_duration(incoming_expiry),
+            outgoing: LruCache::with_expiry_duration(outgoing_expiry),
         }
     }
 
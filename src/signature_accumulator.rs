@@ -13,10 +13,10 @@ use crate::{
     utils::LogIdent,
 };
 use itertools::Itertools;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
 
-/// Time (in seconds) within which a message and a quorum of signatures need to arrive to
-/// accumulate.
+/// Default time within which a message and a quorum of signatures need to arrive to accumulate.
 pub const ACCUMULATION_TIMEOUT: Duration = Duration::from_secs(120);
 
 #[derive(Default)]
@@ -26,13 +26,15 @@ pub struct SignatureAccumulator {
 
 impl SignatureAccumulator {
     /// Adds the given signature to the list of pending signatures or to the appropriate
-    /// `Message`. Returns the message, if it has enough signatures now.
+    /// `Message`. Returns the message, if it has enough signatures now. Messages which didn't
+    /// accumulate within `timeout` are dropped.
     pub fn add_proof(
         &mut self,
         msg: AccumulatingMessage,
+        timeout: Duration,
         log_ident: &LogIdent,
     ) -> Option<MessageWithBytes> {
-        self.remove_expired();
+        self.remove_expired(timeout);
         let hash = msg.crypto_hash().ok()?;
         if let Some((existing_msg, _)) = self.msgs.get_mut(&hash) {
             if let Some(existing_msg) = existing_msg {
@@ -52,11 +54,11 @@ impl SignatureAccumulator {
         }
     }
 
-    fn remove_expired(&mut self) {
+    fn remove_expired(&mut self, timeout: Duration) {
         let expired_msgs = self
             .msgs
             .iter()
-            .filter(|&(_, &(_, ref time))| time.elapsed() > ACCUMULATION_TIMEOUT)
+            .filter(|&(_, &(_, ref time))| time.elapsed() > timeout)
             .map(|(hash, _)| *hash)
             .collect_vec();
         for hash in expired_msgs {
@@ -80,6 +82,32 @@ impl SignatureAccumulator {
     }
 }
 
+// Timestamps can't be serialised, so the pending messages are restored with a fresh expiry.
+impl Serialize for SignatureAccumulator {
+    fn serialize<S: Serializer>(&self, serialiser: S) -> Result<S::Ok, S::Error> {
+        let msgs: Vec<_> = self
+            .msgs
+            .iter()
+            .map(|(hash, (msg, _))| (hash, msg))
+            .collect();
+        msgs.serialize(serialiser)
+    }
+}
+
+impl<'de> Deserialize<'de> for SignatureAccumulator {
+    fn deserialize<D: Deserializer<'de>>(deserialiser: D) -> Result<Self, D::Error> {
+        let msgs: Vec<(Digest256, Option<AccumulatingMessage>)> =
+            Deserialize::deserialize(deserialiser)?;
+        let now = Instant::now();
+        Ok(Self {
+            msgs: msgs
+                .into_iter()
+                .map(|(hash, msg)| (hash, (msg, now)))
+                .collect(),
+        })
+    }
+}
+
 #[cfg(test)]
 #[cfg(feature = "mock_base")]
 mod tests {
@@ -88,7 +116,7 @@ mod tests {
         chain::{EldersInfo, SectionKeyInfo, SectionKeyShare, SectionProofSlice},
         id::{FullId, P2pNode},
         location::{DstLocation, SrcLocation},
-        messages::{Message, PlainMessage, Variant},
+        messages::{Message, PlainMessage, Variant, PROTOCOL_VERSION},
         parsec::generate_bls_threshold_secret_key,
         rng, unwrap, ConnectionInfo, Prefix, XorName,
     };
@@ -109,6 +137,7 @@ mod tests {
             pk_set: &bls::PublicKeySet,
         ) -> Self {
             let content = PlainMessage {
+                version: PROTOCOL_VERSION,
                 src: Prefix::default(),
                 dst: DstLocation::Section(rand::random()),
                 variant: Variant::UserMessage(vec![rand::random(), rand::random(), rand::random()]),
@@ -133,6 +162,7 @@ mod tests {
                 .map(|(id, bls_id)| {
                     unwrap!(Message::single_src(
                         id,
+                        PROTOCOL_VERSION,
                         DstLocation::Direct,
                         Variant::MessageSignature(Box::new(unwrap!(AccumulatingMessage::new(
                             content.clone(),
@@ -209,7 +239,7 @@ mod tests {
         // Add each message with the section list added - none should accumulate.
         env.msgs_and_sigs.iter().foreach(|msg_and_sigs| {
             let signed_msg = msg_and_sigs.signed_msg.clone();
-            let result = sig_accumulator.add_proof(signed_msg, &log_ident);
+            let result = sig_accumulator.add_proof(signed_msg, ACCUMULATION_TIMEOUT, &log_ident);
             assert!(result.is_none());
         });
         let expected_msgs_count = env.msgs_and_sigs.len();
@@ -222,7 +252,9 @@ mod tests {
                 let old_num_msgs = sig_accumulator.msgs.len();
 
                 let result = match signature_msg.variant {
-                    Variant::MessageSignature(msg) => sig_accumulator.add_proof(*msg, &log_ident),
+                    Variant::MessageSignature(msg) => {
+                        sig_accumulator.add_proof(*msg, ACCUMULATION_TIMEOUT, &log_ident)
+                    }
                     unexpected_msg => panic!("Unexpected message: {:?}", unexpected_msg),
                 };
 
@@ -250,7 +282,7 @@ mod tests {
 
         FakeClock::advance_time(ACCUMULATION_TIMEOUT.as_secs() * 1000 + 1000);
 
-        sig_accumulator.remove_expired();
+        sig_accumulator.remove_expired(ACCUMULATION_TIMEOUT);
         assert!(sig_accumulator.msgs.is_empty());
     }
 }
@@ -0,0 +1,246 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    chain::{Chain, GenesisPfxInfo},
+    error::RoutingError,
+    id::FullId,
+    messages::QueuedMessage,
+    pause::PausedState,
+    signature_accumulator::SignatureAccumulator,
+    time::{Duration, Instant},
+    NetworkConfig,
+};
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, SyncSender, TrySendError},
+    thread::{self, JoinHandle},
+};
+
+/// Default interval between two consecutive snapshots of the node state.
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(60);
+
+const SNAPSHOT_EXTENSION: &str = "snapshot";
+const TMP_EXTENSION: &str = "tmp";
+// Number of snapshots kept on disk. Keeping more than one lets us fall back to an older snapshot
+// if the latest one turns out to be invalid.
+const MAX_SNAPSHOTS: usize = 2;
+
+/// Borrowed view of the parts of a node's state which are written to a snapshot.
+///
+/// Like `PausedState`, it doesn't contain the parsec graph, so a restored elder rejoins parsec at
+/// a new version started by the other elders of its section.
+#[derive(Serialize)]
+pub struct Snapshot<'a> {
+    pub chain: &'a Chain,
+    pub full_id: &'a FullId,
+    pub gen_pfx_info: &'a GenesisPfxInfo,
+    pub network_config: NetworkConfig,
+    // Only elders have a message queue.
+    pub msg_queue: Option<&'a VecDeque<QueuedMessage>>,
+    pub msg_backlog: &'a [QueuedMessage],
+    pub sig_accumulator: &'a SignatureAccumulator,
+}
+
+#[derive(Deserialize)]
+struct SnapshotData {
+    chain: Chain,
+    full_id: FullId,
+    gen_pfx_info: GenesisPfxInfo,
+    network_config: NetworkConfig,
+    msg_queue: Option<VecDeque<QueuedMessage>>,
+    msg_backlog: Vec<QueuedMessage>,
+    sig_accumulator: SignatureAccumulator,
+}
+
+impl SnapshotData {
+    fn is_valid(&self) -> bool {
+        self.chain.our_id() == self.full_id.public_id() && self.chain.validate_our_history()
+    }
+
+    fn into_paused_state(self) -> PausedState {
+        PausedState {
+            chain: self.chain,
+            full_id: self.full_id,
+            gen_pfx_info: self.gen_pfx_info,
+            msg_filter: Default::default(),
+            msg_queue: self.msg_queue.unwrap_or_default(),
+            msg_backlog: self.msg_backlog,
+            network_config: self.network_config,
+            sig_accumulator: self.sig_accumulator,
+            parsec_map: Default::default(),
+            join_config: Default::default(),
+        }
+    }
+}
+
+/// Periodically writes snapshots of the node state to a directory and restores the latest valid
+/// one on startup.
+///
+/// The state is serialised on the thread of the node, as it can't be shared with another thread,
+/// but the snapshots are written to disk by a background thread, so the node doesn't wait for
+/// the disk.
+pub struct SnapshotStore {
+    dir: PathBuf,
+    interval: Duration,
+    next_index: u64,
+    last_saved: Option<Instant>,
+    writer: Option<(SyncSender<(PathBuf, Vec<u8>)>, JoinHandle<()>)>,
+}
+
+impl SnapshotStore {
+    /// Opens the store in `dir`, creating the directory if it doesn't exist yet. A snapshot is
+    /// written every `interval`.
+    pub fn new(dir: PathBuf, interval: Duration) -> Result<Self, RoutingError> {
+        fs::create_dir_all(&dir)?;
+        let next_index = list_snapshots(&dir)?
+            .last()
+            .map_or(0, |(index, _)| index + 1);
+
+        // Only one snapshot waits to be written at a time: if the disk can't keep up, the newer
+        // ones are skipped until it does.
+        let (snapshot_tx, snapshot_rx) = mpsc::sync_channel::<(PathBuf, Vec<u8>)>(1);
+        let writer_dir = dir.clone();
+        let handle = thread::spawn(move || {
+            for (path, bytes) in snapshot_rx {
+                if let Err(error) = write_snapshot(&writer_dir, &path, &bytes) {
+                    warn!("Failed to save snapshot {:?}: {:?}", path, error);
+                }
+            }
+        });
+
+        Ok(Self {
+            dir,
+            interval,
+            next_index,
+            last_saved: None,
+            writer: Some((snapshot_tx, handle)),
+        })
+    }
+
+    /// Returns whether it's time to write a new snapshot.
+    pub fn is_due(&self) -> bool {
+        self.last_saved
+            .map_or(true, |last_saved| last_saved.elapsed() >= self.interval)
+    }
+
+    /// Serialises a new snapshot and hands it over to the background thread to be written.
+    pub fn save(&mut self, snapshot: &Snapshot) -> Result<(), RoutingError> {
+        let bytes = bincode::serialize(snapshot)?;
+        let path = self.path(self.next_index);
+
+        if let Some((snapshot_tx, _)) = self.writer.as_ref() {
+            match snapshot_tx.try_send((path, bytes)) {
+                Ok(()) => (),
+                Err(TrySendError::Full(_)) => {
+                    debug!("Skipping snapshot - the previous one is still being written");
+                    return Ok(());
+                }
+                Err(TrySendError::Disconnected(_)) => return Err(RoutingError::InvalidState),
+            }
+        }
+
+        self.next_index += 1;
+        self.last_saved = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Returns the state stored in the latest valid snapshot, if any.
+    pub fn load_latest(&self) -> Option<PausedState> {
+        let snapshots = match list_snapshots(&self.dir) {
+            Ok(snapshots) => snapshots,
+            Err(error) => {
+                warn!("Failed to list snapshots in {:?}: {:?}", self.dir, error);
+                return None;
+            }
+        };
+
+        for (_, path) in snapshots.into_iter().rev() {
+            match read_snapshot(&path) {
+                Ok(ref data) if !data.is_valid() => warn!("Invalid snapshot {:?}", path),
+                Ok(data) => {
+                    info!("Restoring from snapshot {:?}", path);
+                    return Some(data.into_paused_state());
+                }
+                Err(error) => warn!("Failed to read snapshot {:?}: {:?}", path, error),
+            }
+        }
+
+        None
+    }
+
+    fn path(&self, index: u64) -> PathBuf {
+        self.dir
+            .join(format!("{:016x}", index))
+            .with_extension(SNAPSHOT_EXTENSION)
+    }
+}
+
+impl Drop for SnapshotStore {
+    // Waits for the pending snapshot to be written, so it can be restored right away.
+    fn drop(&mut self) {
+        if let Some((snapshot_tx, handle)) = self.writer.take() {
+            drop(snapshot_tx);
+            let _ = handle.join();
+        }
+    }
+}
+
+// Writes a snapshot to `path` and removes the ones no longer needed.
+fn write_snapshot(dir: &Path, path: &Path, bytes: &[u8]) -> Result<(), RoutingError> {
+    // Write to a temporary file first, so a crash in the middle of writing never leaves a
+    // truncated snapshot behind.
+    let tmp_path = path.with_extension(TMP_EXTENSION);
+    {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp_path, path)?;
+
+    let snapshots = list_snapshots(dir)?;
+    let remove_count = snapshots.len().saturating_sub(MAX_SNAPSHOTS);
+    for (_, path) in snapshots.into_iter().take(remove_count) {
+        if let Err(error) = fs::remove_file(&path) {
+            warn!("Failed to remove old snapshot {:?}: {:?}", path, error);
+        }
+    }
+
+    Ok(())
+}
+
+fn read_snapshot(path: &Path) -> Result<SnapshotData, RoutingError> {
+    let mut bytes = Vec::new();
+    let _ = File::open(path)?.read_to_end(&mut bytes)?;
+    Ok(bincode::deserialize(&bytes)?)
+}
+
+// Returns the snapshot files in `dir` sorted from the oldest to the newest.
+fn list_snapshots(dir: &Path) -> Result<Vec<(u64, PathBuf)>, RoutingError> {
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some(SNAPSHOT_EXTENSION) {
+            continue;
+        }
+
+        let index = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| u64::from_str_radix(stem, 16).ok());
+        if let Some(index) = index {
+            snapshots.push((index, path));
+        }
+    }
+
+    snapshots.sort();
+    Ok(snapshots)
+}
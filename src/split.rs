@@ -0,0 +1,113 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::xor_space::{Prefix, XorName};
+use std::collections::BTreeMap;
+
+/// Application-defined load metrics of our section, e.g. the number of bytes it stores, by name.
+/// They are agreed on by the elders via `Node::vote_for_load_metrics`, and cleared whenever the
+/// prefix of our section changes. The agreed values are rounded down to a power of two.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct LoadMetrics(pub BTreeMap<String, u64>);
+
+impl LoadMetrics {
+    /// Returns the value of the given metric, or zero if it was not agreed on.
+    pub fn get(&self, name: &str) -> u64 {
+        self.0.get(name).cloned().unwrap_or(0)
+    }
+
+    /// Returns the metrics with every value rounded down to a power of two, so elders which
+    /// measured slightly different values still vote for the same metrics.
+    pub(crate) fn quantised(&self) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|(name, value)| (name.clone(), quantise(*value)))
+                .collect(),
+        )
+    }
+}
+
+// Rounds `value` down to a power of two, keeping zero as is.
+fn quantise(value: u64) -> u64 {
+    if value == 0 {
+        0
+    } else {
+        1 << (63 - value.leading_zeros())
+    }
+}
+
+/// A possible split of our section, as seen by all its elders.
+#[derive(Clone, Copy, Debug)]
+pub struct SplitCandidate<'a> {
+    /// Prefix of our section.
+    pub prefix: &'a Prefix<XorName>,
+    /// Number of elders and adults of our section that would be in our half after the split.
+    pub our_new_size: usize,
+    /// Number of elders and adults of our section that would be in our sibling's half.
+    pub sibling_new_size: usize,
+    /// `NetworkParams::safe_section_size`.
+    pub safe_section_size: usize,
+    /// Load metrics our section agreed on last.
+    pub load: &'a LoadMetrics,
+}
+
+/// Rule deciding when our section splits.
+///
+/// The policy is consulted by all the elders of a section whenever its members or load metrics
+/// change, so it must be deterministic and the same on all of them, otherwise they would not agree
+/// on the split. Regardless of the policy, a section never splits into a half with fewer than
+/// `NetworkParams::elder_size` members.
+pub trait SplitPolicy {
+    /// Returns whether our section should split.
+    fn should_split(&self, candidate: &SplitCandidate) -> bool;
+}
+
+/// The default policy: split once both halves would have at least `safe_section_size` members,
+/// regardless of the load.
+pub(crate) struct DefaultSplit;
+
+impl SplitPolicy for DefaultSplit {
+    fn should_split(&self, candidate: &SplitCandidate) -> bool {
+        candidate.our_new_size >= candidate.safe_section_size
+            && candidate.sibling_new_size >= candidate.safe_section_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantised_metrics() {
+        let metrics = |values: &[(&str, u64)]| {
+            LoadMetrics(
+                values
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), *value))
+                    .collect(),
+            )
+        };
+
+        assert_eq!(
+            metrics(&[
+                ("a", 0),
+                ("b", 1),
+                ("c", 1000),
+                ("d", 1024),
+                ("e", u64::max_value())
+            ])
+            .quantised(),
+            metrics(&[("a", 0), ("b", 1), ("c", 512), ("d", 1024), ("e", 1 << 63)])
+        );
+        assert_eq!(
+            metrics(&[("stored_bytes", 5000)]).quantised(),
+            metrics(&[("stored_bytes", 4500)]).quantised()
+        );
+    }
+}
@@ -8,13 +8,15 @@
 
 use crate::{
     action::Action,
-    chain::{EldersInfo, GenesisPfxInfo},
+    chain::{EldersInfo, GenesisPfxInfo, NetworkStats},
     error::RoutingError,
     id::{P2pNode, PublicId},
+    join_policy::JoinConfig,
     network_service::{NetworkBuilder, NetworkService},
     outbox::EventBox,
     pause::PausedState,
     relocation::{RelocatePayload, SignedRelocateDetails},
+    snapshot::SnapshotStore,
     states::{common::Base, Adult, BootstrappingPeer, Elder, JoiningPeer},
     timer::Timer,
     xor_space::{Prefix, XorName},
@@ -56,6 +58,7 @@ pub struct StateMachine {
     action_rx: mpmc::Receiver<Action>,
     action_rx_idx: usize,
     is_running: bool,
+    snapshot_store: Option<SnapshotStore>,
 }
 
 // FIXME - See https://maidsafe.atlassian.net/browse/MAID-2026 for info on removing this exclusion.
@@ -123,6 +126,38 @@ impl State {
         }
     }
 
+    pub fn protocol_version(&self) -> Option<u16> {
+        match *self {
+            Self::Adult(ref state) => Some(state.protocol_version()),
+            Self::Elder(ref state) => Some(state.protocol_version()),
+            Self::BootstrappingPeer(_) | Self::JoiningPeer(_) | Self::Terminated => None,
+        }
+    }
+
+    pub fn genesis_key(&self) -> Option<&bls::PublicKey> {
+        match *self {
+            Self::Adult(ref state) => Some(state.genesis_key()),
+            Self::Elder(ref state) => Some(state.genesis_key()),
+            Self::BootstrappingPeer(_) | Self::JoiningPeer(_) | Self::Terminated => None,
+        }
+    }
+
+    pub fn section_for(&self, name: &XorName) -> Option<&EldersInfo> {
+        match *self {
+            Self::Adult(ref state) => state.section_for(name),
+            Self::Elder(ref state) => state.section_for(name),
+            Self::BootstrappingPeer(_) | Self::JoiningPeer(_) | Self::Terminated => None,
+        }
+    }
+
+    pub fn network_stats(&self) -> Option<&NetworkStats> {
+        match *self {
+            Self::Adult(ref state) => state.network_stats(),
+            Self::Elder(ref state) => state.network_stats(),
+            Self::BootstrappingPeer(_) | Self::JoiningPeer(_) | Self::Terminated => None,
+        }
+    }
+
     pub fn matches_our_prefix(&self, name: &XorName) -> Result<bool, RoutingError> {
         match *self {
             Self::Elder(ref state) => Ok(state.our_prefix().matches(name)),
@@ -154,6 +189,22 @@ impl State {
         )
     }
 
+    fn save_snapshot(&mut self, store: &mut SnapshotStore) -> Result<(), RoutingError> {
+        match *self {
+            Self::Elder(ref mut state) => store.save(&state.snapshot()),
+            Self::Adult(ref mut state) => store.save(&state.snapshot()),
+            Self::BootstrappingPeer(_) | Self::JoiningPeer(_) | Self::Terminated => Ok(()),
+        }
+    }
+
+    pub fn join_config_mut(&mut self) -> Option<&mut JoinConfig> {
+        state_dispatch!(
+            *self,
+            ref mut state => Some(state.join_config_mut()),
+            Terminated => None
+        )
+    }
+
     /// Returns this elder mut state.
     pub fn elder_state_mut(&mut self) -> Option<&mut Elder> {
         match *self {
@@ -329,20 +380,16 @@ impl StateMachine {
         init_state: F,
         network_config: NetworkConfig,
         outbox: &mut dyn EventBox,
-    ) -> (mpmc::Sender<Action>, Self)
+    ) -> Result<(mpmc::Sender<Action>, Self), RoutingError>
     where
         F: FnOnce(NetworkService, Timer, &mut dyn EventBox) -> State,
     {
         let (action_tx, action_rx) = mpmc::unbounded();
         let (network_tx, network_rx) = mpmc::unbounded();
 
-        let network_service = match NetworkBuilder::new(network_tx)
+        let network_service = NetworkBuilder::new(network_tx)
             .with_config(network_config)
-            .build()
-        {
-            Ok(network_service) => network_service,
-            Err(err) => panic!("Unable to start network service: {:?}", err),
-        };
+            .build()?;
 
         let timer = Timer::new(action_tx.clone());
         let state = init_state(network_service, timer, outbox);
@@ -358,33 +405,35 @@ impl StateMachine {
             action_rx,
             action_rx_idx: 0,
             is_running,
+            snapshot_store: None,
         };
 
-        (action_tx, machine)
+        Ok((action_tx, machine))
     }
 
     pub fn pause(self) -> Result<PausedState, RoutingError> {
         info!("{} - Pause", self.current());
 
-        let mut paused_state = match self.state {
-            State::Elder(state) => state.pause(),
-            State::Adult(state) => state.pause(),
-            _ => return Err(RoutingError::InvalidState),
-        };
-
-        paused_state.network_rx = Some(self.network_rx);
-        Ok(paused_state)
+        match self.state {
+            State::Elder(state) => Ok(state.pause()),
+            State::Adult(state) => Ok(state.pause()),
+            _ => Err(RoutingError::InvalidState),
+        }
     }
 
-    pub fn resume(mut state: PausedState) -> (mpmc::Sender<Action>, Self) {
+    pub fn resume(state: PausedState) -> Result<(mpmc::Sender<Action>, Self), RoutingError> {
         let (action_tx, action_rx) = mpmc::unbounded();
-        let network_rx = state.network_rx.take().expect("PausedState is incomplete");
+        let (network_tx, network_rx) = mpmc::unbounded();
+
+        let network_service = NetworkBuilder::new(network_tx)
+            .with_config(state.network_config.clone())
+            .build()?;
 
         let timer = Timer::new(action_tx.clone());
         let state = if state.is_elder() {
-            State::Elder(Elder::resume(state, timer))
+            State::Elder(Elder::resume(state, network_service, timer))
         } else {
-            State::Adult(Adult::resume(state, timer))
+            State::Adult(Adult::resume(state, network_service, timer))
         };
 
         let machine = Self {
@@ -394,11 +443,29 @@ impl StateMachine {
             action_rx,
             action_rx_idx: 0,
             is_running: true,
+            snapshot_store: None,
         };
 
         info!("{} - Resume", machine.current());
 
-        (action_tx, machine)
+        Ok((action_tx, machine))
+    }
+
+    /// Periodically write snapshots of our state to the given store.
+    pub fn set_snapshot_store(&mut self, store: SnapshotStore) {
+        self.snapshot_store = Some(store);
+    }
+
+    fn save_snapshot_if_due(&mut self) {
+        if let Some(store) = self.snapshot_store.as_mut() {
+            if !store.is_due() {
+                return;
+            }
+
+            if let Err(error) = self.state.save_snapshot(store) {
+                warn!("{} - Failed to save snapshot: {:?}", self.state, error);
+            }
+        }
     }
 
     fn handle_network_event(&mut self, event: NetworkEvent, outbox: &mut dyn EventBox) {
@@ -461,6 +528,13 @@ impl StateMachine {
         self.is_running = false;
     }
 
+    /// Returns clones of the channels the state machine receives from, so another thread can wait
+    /// for them to be ready.
+    #[cfg(feature = "async")]
+    pub(crate) fn receivers(&self) -> (mpmc::Receiver<NetworkEvent>, mpmc::Receiver<Action>) {
+        (self.network_rx.clone(), self.action_rx.clone())
+    }
+
     /// Register the state machine event channels with the provided [selector](mpmc::Select).
     pub fn register<'a>(&'a mut self, select: &mut mpmc::Select<'a>) {
         // Populate action_rx timeouts
@@ -497,21 +571,24 @@ impl StateMachine {
         if !self.is_running {
             return Err(mpmc::RecvError);
         }
-        match op_index {
+        let status = match op_index {
             idx if idx == self.network_rx_idx => {
                 let event = self.network_rx.recv()?;
                 self.handle_network_event(event, outbox);
-                Ok(true)
+                true
             }
             idx if idx == self.action_rx_idx => {
                 let action = self.action_rx.recv()?;
 
                 let status = is_busy(&action);
                 self.handle_action(action, outbox);
-                Ok(status)
+                status
             }
-            _idx => Err(mpmc::RecvError),
-        }
+            _idx => return Err(mpmc::RecvError),
+        };
+
+        self.save_snapshot_if_due();
+        Ok(status)
     }
 
     /// Get reference to the current state.
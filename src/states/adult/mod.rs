@@ -16,12 +16,13 @@ use super::{
 };
 use crate::{
     chain::{
-        Chain, EldersChange, EldersInfo, GenesisPfxInfo, NetworkParams, OnlinePayload,
-        SectionKeyInfo, SendAckMessagePayload,
+        Chain, EldersChange, EldersInfo, GenesisPfxInfo, NetworkParams, NetworkStats,
+        OnlinePayload, SectionKeyInfo, SectionProofChain, SendAckMessagePayload, Strategies,
     },
     error::{Result, RoutingError},
     event::Event,
     id::{FullId, P2pNode, PublicId},
+    join_policy::JoinConfig,
     location::DstLocation,
     messages::{
         AccumulatingMessage, BootstrapResponse, Message, MessageWithBytes, QueuedMessage, Variant,
@@ -36,6 +37,7 @@ use crate::{
     rng::{self, MainRng},
     routing_message_filter::RoutingMessageFilter,
     signature_accumulator::SignatureAccumulator,
+    snapshot::Snapshot,
     state_machine::{State, Transition},
     time::Duration,
     timer::Timer,
@@ -53,6 +55,10 @@ use std::{
 
 // Send our knowledge in a similar speed as GOSSIP_TIMEOUT
 const KNOWLEDGE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Time to wait for our section to vote us offline before sending our leave notice again.
+pub const LEAVE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Number of times we send our leave notice before leaving without waiting for our section.
+pub const MAX_LEAVE_ATTEMPTS: u8 = 3;
 
 pub struct AdultDetails {
     pub network_service: NetworkService,
@@ -64,6 +70,8 @@ pub struct AdultDetails {
     pub msg_filter: RoutingMessageFilter,
     pub timer: Timer,
     pub network_cfg: NetworkParams,
+    pub strategies: Strategies,
+    pub join_config: JoinConfig,
     pub rng: MainRng,
 }
 
@@ -80,6 +88,14 @@ pub struct Adult {
     knowledge_timer_token: u64,
     msg_filter: RoutingMessageFilter,
     timer: Timer,
+    join_config: JoinConfig,
+    // Whether we sent a leave notice and wait for our section to vote us offline.
+    leaving: bool,
+    leave_timer_token: Option<u64>,
+    // Number of times we sent our leave notice.
+    leave_attempts: u8,
+    // Our elders info as signed by our section, to pass on to our clients.
+    signed_section_info: Option<Message>,
     rng: MainRng,
 }
 
@@ -92,18 +108,21 @@ impl Adult {
         let public_id = *details.full_id.public_id();
         let knowledge_timer_token = details.timer.schedule(KNOWLEDGE_TIMEOUT);
 
-        let parsec_map = parsec_map.with_init(
-            &mut details.rng,
-            details.full_id.clone(),
-            &details.gen_pfx_info,
-        );
+        let parsec_map = parsec_map
+            .with_size_limit(details.network_cfg.parsec_size_limit)
+            .with_init(
+                &mut details.rng,
+                details.full_id.clone(),
+                &details.gen_pfx_info,
+            );
 
         let chain = Chain::new(
             details.network_cfg,
             public_id,
             details.gen_pfx_info.clone(),
             None,
-        );
+        )
+        .with_strategies(details.strategies);
 
         let node = Self {
             chain,
@@ -117,6 +136,11 @@ impl Adult {
             msg_filter: details.msg_filter,
             timer: details.timer,
             knowledge_timer_token,
+            join_config: details.join_config,
+            leaving: false,
+            leave_timer_token: None,
+            leave_attempts: 0,
+            signed_section_info: None,
             rng: details.rng,
         };
 
@@ -140,6 +164,8 @@ impl Adult {
                 network_service: self.network_service,
                 full_id,
                 network_cfg,
+                strategies: self.chain.strategies().clone(),
+                join_config: self.join_config,
                 timer: self.timer,
                 rng: self.rng,
             },
@@ -156,6 +182,8 @@ impl Adult {
                 network_service: self.network_service,
                 full_id: self.full_id,
                 network_cfg: self.chain.network_cfg(),
+                strategies: self.chain.strategies().clone(),
+                join_config: self.join_config,
                 timer: self.timer,
                 rng: self.rng,
             },
@@ -181,15 +209,20 @@ impl Adult {
             parsec_map: self.parsec_map,
             // we reset the message filter so that the node can correctly process some messages as
             // an Elder even if it has already seen them as an Adult
-            msg_filter: RoutingMessageFilter::new(),
+            msg_filter: RoutingMessageFilter::with_expiry_durations(
+                self.chain.network_cfg().incoming_filter_expiry,
+                self.chain.network_cfg().outgoing_filter_expiry,
+            ),
             timer: self.timer,
+            join_config: self.join_config,
             rng: self.rng,
         };
 
         Elder::from_adult(details, old_pfx, outbox).map(State::Elder)
     }
 
-    pub fn pause(self) -> PausedState {
+    pub fn pause(mut self) -> PausedState {
+        let network_config = self.network_service.pinned_config();
         PausedState {
             chain: self.chain,
             full_id: self.full_id,
@@ -197,19 +230,38 @@ impl Adult {
             msg_filter: self.msg_filter,
             msg_queue: VecDeque::new(),
             msg_backlog: self.msg_backlog,
-            network_service: self.network_service,
-            network_rx: None,
+            network_config,
             sig_accumulator: self.sig_accumulator,
             parsec_map: self.parsec_map,
+            join_config: self.join_config,
         }
     }
 
-    pub fn resume(state: PausedState, timer: Timer) -> Self {
+    pub fn snapshot(&mut self) -> Snapshot {
+        Snapshot {
+            network_config: self.network_service.pinned_config(),
+            chain: &self.chain,
+            full_id: &self.full_id,
+            gen_pfx_info: &self.gen_pfx_info,
+            msg_queue: None,
+            msg_backlog: &self.msg_backlog,
+            sig_accumulator: &self.sig_accumulator,
+        }
+    }
+
+    pub fn resume(
+        mut state: PausedState,
+        mut network_service: NetworkService,
+        timer: Timer,
+    ) -> Self {
+        network_service.set_max_resends(state.chain.network_cfg().max_resends);
         let knowledge_timer_token = timer.schedule(KNOWLEDGE_TIMEOUT);
+        let mut rng = rng::new();
+        state.ensure_parsec_map(&mut rng);
 
         Self {
             chain: state.chain,
-            network_service: state.network_service,
+            network_service,
             event_backlog: Vec::new(),
             full_id: state.full_id,
             gen_pfx_info: state.gen_pfx_info,
@@ -219,10 +271,23 @@ impl Adult {
             knowledge_timer_token,
             msg_filter: state.msg_filter,
             timer,
-            rng: rng::new(),
+            join_config: state.join_config,
+            rng,
         }
     }
 
+    pub fn genesis_key(&self) -> &bls::PublicKey {
+        self.chain.our_history().first_key_info().key()
+    }
+
+    pub fn section_for(&self, name: &XorName) -> Option<&EldersInfo> {
+        self.chain.section_for(name)
+    }
+
+    pub fn network_stats(&self) -> Option<&NetworkStats> {
+        self.chain.network_stats()
+    }
+
     pub fn our_prefix(&self) -> &Prefix<XorName> {
         self.chain.our_prefix()
     }
@@ -294,17 +359,48 @@ impl Adult {
         )
     }
 
+    fn send_leave(&mut self) {
+        self.leave_attempts += 1;
+
+        let targets: Vec<_> = self
+            .chain
+            .our_elders()
+            .map(|p2p_node| p2p_node.connection_info().clone())
+            .collect();
+        for conn_info in targets {
+            self.send_direct_message(&conn_info, Variant::Leave);
+        }
+
+        self.leave_timer_token = Some(self.timer.schedule(LEAVE_TIMEOUT));
+    }
+
+    fn finish_leaving(&mut self, outbox: &mut dyn EventBox) -> Transition {
+        info!("{} - Left the network.", self);
+        outbox.send_event(Event::Left);
+        Transition::Terminate
+    }
+
     fn handle_genesis_update(
         &mut self,
         gen_pfx_info: GenesisPfxInfo,
     ) -> Result<Transition, RoutingError> {
         info!("{} - Received GenesisUpdate: {:?}", self, gen_pfx_info);
 
+        // Our section was absorbed by our sibling, whose parsec versions are unrelated to ours.
+        let is_merge = self.chain.is_merging_into(gen_pfx_info.first_info.prefix());
+
         // An Adult can receive the same message from multiple Elders - bail early if we are
         // already up to date
-        if gen_pfx_info.parsec_version <= self.gen_pfx_info.parsec_version {
+        if !is_merge && gen_pfx_info.parsec_version <= self.gen_pfx_info.parsec_version {
             return Ok(Transition::Stay);
         }
+        if is_merge {
+            info!(
+                "{} - Absorbed by our sibling: {:?}",
+                self, gen_pfx_info.first_info
+            );
+            self.parsec_map.clear();
+        }
         self.gen_pfx_info = gen_pfx_info.clone();
         self.parsec_map.init(
             &mut self.rng,
@@ -312,7 +408,8 @@ impl Adult {
             &self.gen_pfx_info,
             &LogIdent::new(self.full_id.public_id()),
         );
-        self.chain = Chain::new(self.chain.network_cfg(), *self.id(), gen_pfx_info, None);
+        self.chain = Chain::new(self.chain.network_cfg(), *self.id(), gen_pfx_info, None)
+            .with_strategies(self.chain.strategies().clone());
 
         // We were not promoted during the last section change, so we are not going to need these
         // messages anymore. This also prevents the messages from becoming stale (fail the trust
@@ -337,7 +434,11 @@ impl Adult {
             );
         }
 
-        if let Some(msg) = self.sig_accumulator.add_proof(msg, &self.log_ident()) {
+        let timeout = self.chain.network_cfg().accumulation_timeout;
+        if let Some(msg) = self
+            .sig_accumulator
+            .add_proof(msg, timeout, &self.log_ident())
+        {
             self.try_handle_message(None, msg, outbox)
         } else {
             Ok(Transition::Stay)
@@ -393,10 +494,41 @@ impl Base for Adult {
         &mut self.timer
     }
 
+    fn join_config_mut(&mut self) -> &mut JoinConfig {
+        &mut self.join_config
+    }
+
     fn rng(&mut self) -> &mut MainRng {
         &mut self.rng
     }
 
+    fn our_section_info(&self) -> Option<(&EldersInfo, &SectionProofChain)> {
+        Some((self.chain.our_info(), self.chain.our_history()))
+    }
+
+    fn signed_section_info(&self) -> Option<&Message> {
+        self.signed_section_info.as_ref()
+    }
+
+    fn set_signed_section_info(&mut self, msg: Message) {
+        self.signed_section_info = Some(msg);
+    }
+
+    fn request_signed_section_info(&mut self) {
+        let targets: Vec<_> = self
+            .chain
+            .our_elders()
+            .map(|p2p_node| p2p_node.connection_info().clone())
+            .collect();
+        for conn_info in targets {
+            self.send_direct_message(&conn_info, Variant::SectionInfoRequest);
+        }
+    }
+
+    fn protocol_version(&self) -> u16 {
+        self.chain.protocol_version()
+    }
+
     fn finish_handle_transition(&mut self, outbox: &mut dyn EventBox) -> Transition {
         debug!("{} - State changed to Adult finished.", self);
 
@@ -426,18 +558,57 @@ impl Base for Adult {
         transition
     }
 
-    fn handle_timeout(&mut self, token: u64, _: &mut dyn EventBox) -> Transition {
+    fn handle_timeout(&mut self, token: u64, outbox: &mut dyn EventBox) -> Transition {
         if self.knowledge_timer_token == token {
             // TODO: send this only when the knowledge changes, not periodically.
             self.send_member_knowledge();
             self.knowledge_timer_token = self.timer.schedule(KNOWLEDGE_TIMEOUT);
+        } else if self.leave_timer_token == Some(token) {
+            if self.leave_attempts >= MAX_LEAVE_ATTEMPTS {
+                info!(
+                    "{} - Not voted offline after {} leave notices - leaving anyway.",
+                    self, self.leave_attempts
+                );
+                return self.finish_leaving(outbox);
+            }
+
+            debug!(
+                "{} - Not voted offline yet - re-sending leave notice (attempt {}).",
+                self,
+                self.leave_attempts + 1
+            );
+            self.send_leave();
         }
 
         Transition::Stay
     }
 
-    fn handle_peer_lost(&mut self, peer_addr: SocketAddr, _: &mut dyn EventBox) -> Transition {
+    fn handle_leave(&mut self, _: &mut dyn EventBox) -> Result<Transition, RoutingError> {
+        if self.leaving {
+            return Ok(Transition::Stay);
+        }
+
+        info!("{} - Leaving the network.", self);
+        self.leaving = true;
+        self.send_leave();
+
+        Ok(Transition::Stay)
+    }
+
+    fn handle_peer_lost(&mut self, peer_addr: SocketAddr, outbox: &mut dyn EventBox) -> Transition {
         debug!("{} - Lost peer {}", self, peer_addr);
+
+        // Our elders disconnect from us once they voted us offline.
+        let peer_map = self.peer_map();
+        if self.leaving
+            && !self
+                .chain
+                .our_elders()
+                .any(|p2p_node| peer_map.has(p2p_node.peer_addr()))
+        {
+            return self.finish_leaving(outbox);
+        }
+
         Transition::Stay
     }
 
@@ -471,7 +642,7 @@ impl Base for Adult {
             Variant::ParsecResponse(version, response) => {
                 self.handle_parsec_response(version, response, *msg.src.as_node()?, outbox)
             }
-            Variant::BootstrapRequest(name) => {
+            Variant::BootstrapRequest { name, .. } => {
                 self.handle_bootstrap_request(msg.src.to_sender_node(sender)?, name);
                 Ok(Transition::Stay)
             }
@@ -483,6 +654,26 @@ impl Base for Adult {
                 );
                 Ok(Transition::Stay)
             }
+            Variant::UserMessageToClient { client, content } => {
+                let msg = Message {
+                    version: msg.version,
+                    dst: msg.dst,
+                    src: msg.src,
+                    variant: Variant::UserMessageToClient { client, content },
+                };
+                self.send_message_to_known_client(&client, msg);
+                Ok(Transition::Stay)
+            }
+            Variant::SectionInfoResponse(elders_info) => {
+                let msg = Message {
+                    version: msg.version,
+                    dst: msg.dst,
+                    src: msg.src,
+                    variant: Variant::SectionInfoResponse(elders_info),
+                };
+                self.handle_signed_section_info(msg);
+                Ok(Transition::Stay)
+            }
             _ => unreachable!(),
         }
     }
@@ -510,15 +701,30 @@ impl Base for Adult {
             | Variant::MessageSignature(_)
             | Variant::ParsecRequest(..)
             | Variant::ParsecResponse(..)
-            | Variant::BootstrapRequest(_)
-            | Variant::ConnectionResponse => true,
+            | Variant::BootstrapRequest { .. }
+            | Variant::ConnectionResponse
+            | Variant::UserMessageToClient { .. }
+            | Variant::SectionInfoResponse(_) => true,
 
             Variant::NeighbourInfo(_)
             | Variant::UserMessage(_)
-            | Variant::NodeApproval(_)
+            | Variant::UserRequest { .. }
+            | Variant::UserResponse { .. }
+            | Variant::UserMessageWithReceipt { .. }
+            | Variant::DeliveryReceipt(_)
+            | Variant::NodeApproval { .. }
             | Variant::AckMessage { .. }
             | Variant::JoinRequest(_)
+            | Variant::ResourceChallenge { .. }
+            | Variant::ResourceProofResponse { .. }
             | Variant::MemberKnowledge(_)
+            | Variant::Leave
+            | Variant::ParsecRejoin
+            | Variant::SectionInfoRequest
+            | Variant::NetworkStats(_)
+            | Variant::Merge(_)
+            | Variant::MergeKey(_)
+            | Variant::MergeKeySignature(..)
             | Variant::BootstrapResponse(_) => false,
         }
     }
@@ -621,6 +827,15 @@ impl Approved for Adult {
         Ok(())
     }
 
+    fn handle_our_offline(&mut self, outbox: &mut dyn EventBox) -> Transition {
+        if !self.leaving {
+            debug!("{} - Voted offline by our section.", self);
+            return Transition::Stay;
+        }
+
+        self.finish_leaving(outbox)
+    }
+
     fn handle_member_relocated(
         &mut self,
         _details: RelocateDetails,
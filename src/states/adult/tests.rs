@@ -7,15 +7,22 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{super::test_utils, *};
-use crate::{messages::PlainMessage, parsec::generate_bls_threshold_secret_key, unwrap};
+use crate::{
+    messages::{PlainMessage, PROTOCOL_VERSION},
+    parsec::generate_bls_threshold_secret_key,
+    unwrap,
+};
 use mock_quic_p2p::Network;
 use std::collections::BTreeMap;
 
 const ELDER_SIZE: usize = 3;
-const NETWORK_PARAMS: NetworkParams = NetworkParams {
-    elder_size: ELDER_SIZE,
-    safe_section_size: ELDER_SIZE + 1,
-};
+fn network_params() -> NetworkParams {
+    NetworkParams {
+        elder_size: ELDER_SIZE,
+        safe_section_size: ELDER_SIZE + 1,
+        ..Default::default()
+    }
+}
 
 struct AdultUnderTest {
     rng: MainRng,
@@ -60,6 +67,7 @@ impl AdultUnderTest {
 
     fn genesis_update_message(&self, gen_pfx_info: GenesisPfxInfo) -> Message {
         let content = PlainMessage {
+            version: PROTOCOL_VERSION,
             src: Prefix::default(),
             dst: DstLocation::Node(*self.adult.name()),
             variant: Variant::GenesisUpdate(Box::new(gen_pfx_info)),
@@ -119,7 +127,7 @@ fn create_elders(
             let gen_pfx_info =
                 test_utils::create_gen_pfx_info(elders_info.clone(), public_key_set.clone(), 0);
             let chain = Chain::new(
-                NETWORK_PARAMS,
+                network_params(),
                 *id,
                 gen_pfx_info,
                 Some(secret_key_set.secret_key_share(index)),
@@ -157,7 +165,9 @@ fn new_adult_state(
         sig_accumulator: Default::default(),
         msg_filter: Default::default(),
         timer: test_utils::create_timer(),
-        network_cfg: NETWORK_PARAMS,
+        network_cfg: network_params(),
+        strategies: Default::default(),
+        join_config: Default::default(),
         rng: rng::new_from(rng),
     };
 
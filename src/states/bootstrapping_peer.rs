@@ -8,12 +8,13 @@
 
 use super::{common::Base, joining_peer::JoiningPeerDetails};
 use crate::{
-    chain::{EldersInfo, NetworkParams},
+    chain::{EldersInfo, NetworkParams, Strategies},
     error::{Result, RoutingError},
     event::Event,
     id::FullId,
+    join_policy::JoinConfig,
     location::{DstLocation, SrcLocation},
-    messages::{BootstrapResponse, Message, MessageWithBytes, Variant, VerifyStatus},
+    messages::{BootstrapResponse, Message, MessageWithBytes, Variant, VerifyStatus, VersionRange},
     network_service::NetworkService,
     outbox::EventBox,
     peer_map::PeerMap,
@@ -33,13 +34,15 @@ use std::{
     time::Duration,
 };
 
-/// Time after which bootstrap is cancelled (and possibly retried).
+/// Default time after which bootstrap is cancelled (and possibly retried).
 pub const BOOTSTRAP_TIMEOUT: Duration = Duration::from_secs(20);
 
 pub struct BootstrappingPeerDetails {
     pub network_service: NetworkService,
     pub full_id: FullId,
     pub network_cfg: NetworkParams,
+    pub strategies: Strategies,
+    pub join_config: JoinConfig,
     pub timer: Timer,
     pub rng: MainRng,
 }
@@ -54,6 +57,8 @@ pub struct BootstrappingPeer {
     rng: MainRng,
     relocate_details: Option<SignedRelocateDetails>,
     network_cfg: NetworkParams,
+    strategies: Strategies,
+    join_config: JoinConfig,
 }
 
 impl BootstrappingPeer {
@@ -68,6 +73,8 @@ impl BootstrappingPeer {
             rng: details.rng,
             relocate_details: None,
             network_cfg: details.network_cfg,
+            strategies: details.strategies,
+            join_config: details.join_config,
         }
     }
 
@@ -86,6 +93,8 @@ impl BootstrappingPeer {
             rng: details.rng,
             relocate_details: Some(relocate_details),
             network_cfg: details.network_cfg,
+            strategies: details.strategies,
+            join_config: details.join_config,
         };
 
         for conn_info in conn_infos {
@@ -105,6 +114,8 @@ impl BootstrappingPeer {
             network_service: self.network_service,
             full_id: self.full_id,
             network_cfg: self.network_cfg,
+            strategies: self.strategies,
+            join_config: self.join_config,
             timer: self.timer,
             rng: self.rng,
             elders_info,
@@ -121,12 +132,15 @@ impl BootstrappingPeer {
 
         debug!("{} Sending BootstrapRequest to {}.", self, dst.peer_addr);
 
-        let token = self.timer.schedule(BOOTSTRAP_TIMEOUT);
+        let token = self.timer.schedule(self.network_cfg.bootstrap_timeout);
         let _ = self.timeout_tokens.insert(token, dst.peer_addr);
 
-        let destination = self.get_destination();
+        let variant = Variant::BootstrapRequest {
+            name: self.get_destination(),
+            versions: VersionRange::OURS,
+        };
 
-        self.send_direct_message(&dst, Variant::BootstrapRequest(destination));
+        self.send_direct_message(&dst, variant);
         self.peer_map_mut().connect(dst);
     }
 
@@ -178,6 +192,21 @@ impl BootstrappingPeer {
         })
     }
 
+    fn handle_unsupported_version(
+        &mut self,
+        version: u16,
+        outbox: &mut dyn EventBox,
+    ) -> Transition {
+        let reason = format!(
+            "the section speaks protocol version {}, we speak {}",
+            version,
+            VersionRange::OURS
+        );
+        info!("{} - Bootstrap rejected: {}. Terminating.", self, reason);
+        outbox.send_event(Event::JoinRejected(reason));
+        Transition::Terminate
+    }
+
     fn reconnect_to_new_section(&mut self, new_conn_infos: Vec<ConnectionInfo>) {
         self.network_service_mut().remove_and_disconnect_all();
 
@@ -228,6 +257,10 @@ impl Base for BootstrappingPeer {
         &mut self.timer
     }
 
+    fn join_config_mut(&mut self) -> &mut JoinConfig {
+        &mut self.join_config
+    }
+
     fn rng(&mut self) -> &mut MainRng {
         &mut self.rng
     }
@@ -288,7 +321,7 @@ impl Base for BootstrappingPeer {
         &mut self,
         sender: Option<ConnectionInfo>,
         msg: Message,
-        _: &mut dyn EventBox,
+        outbox: &mut dyn EventBox,
     ) -> Result<Transition> {
         let p2p_node = msg.src.to_sender_node(sender)?;
 
@@ -318,6 +351,18 @@ impl Base for BootstrappingPeer {
                 self.reconnect_to_new_section(new_conn_infos);
                 Ok(Transition::Stay)
             }
+            Variant::BootstrapResponse(BootstrapResponse::UnsupportedVersion(version)) => {
+                Ok(self.handle_unsupported_version(version, outbox))
+            }
+            Variant::BootstrapResponse(BootstrapResponse::RetryLater(_))
+            | Variant::BootstrapResponse(BootstrapResponse::Rejected(_)) => {
+                // Only sent in response to `JoinRequest`, which we haven't sent yet.
+                debug!(
+                    "{} - Ignoring unexpected response to JoinRequest from {}.",
+                    self, p2p_node
+                );
+                Ok(Transition::Stay)
+            }
             _ => unreachable!(),
         }
     }
@@ -335,15 +380,30 @@ impl Base for BootstrappingPeer {
             Variant::BootstrapResponse(_) => true,
             Variant::NeighbourInfo(_)
             | Variant::UserMessage(_)
-            | Variant::NodeApproval(_)
+            | Variant::UserRequest { .. }
+            | Variant::UserResponse { .. }
+            | Variant::UserMessageWithReceipt { .. }
+            | Variant::DeliveryReceipt(_)
+            | Variant::UserMessageToClient { .. }
+            | Variant::NodeApproval { .. }
             | Variant::AckMessage { .. }
             | Variant::GenesisUpdate(_)
             | Variant::Relocate(_)
             | Variant::MessageSignature(_)
-            | Variant::BootstrapRequest(_)
+            | Variant::BootstrapRequest { .. }
             | Variant::JoinRequest(_)
+            | Variant::ResourceChallenge { .. }
+            | Variant::ResourceProofResponse { .. }
             | Variant::ConnectionResponse
             | Variant::MemberKnowledge { .. }
+            | Variant::Leave
+            | Variant::ParsecRejoin
+            | Variant::SectionInfoRequest
+            | Variant::SectionInfoResponse(_)
+            | Variant::NetworkStats(_)
+            | Variant::Merge(_)
+            | Variant::MergeKey(_)
+            | Variant::MergeKeySignature(..)
             | Variant::ParsecRequest(..)
             | Variant::ParsecResponse(..) => false,
         }
@@ -410,19 +470,21 @@ mod tests {
 
         let mut node_b_outbox = Vec::new();
 
-        let (_node_b_action_tx, mut node_b_state_machine) = StateMachine::new(
+        let (_node_b_action_tx, mut node_b_state_machine) = unwrap!(StateMachine::new(
             move |network_service, timer, _outbox2| {
                 State::BootstrappingPeer(BootstrappingPeer::new(BootstrappingPeerDetails {
                     network_service,
                     full_id: node_b_full_id,
                     network_cfg,
+                    strategies: Default::default(),
+                    join_config: Default::default(),
                     timer,
                     rng,
                 }))
             },
             config,
             &mut node_b_outbox,
-        );
+        ));
 
         // Check the network service received `ConnectedTo`.
         env.poll();
@@ -448,7 +510,7 @@ mod tests {
 
             let message = unwrap!(Message::from_bytes(&msg));
             match message.variant {
-                Variant::BootstrapRequest(_) => (),
+                Variant::BootstrapRequest { .. } => (),
                 _ => panic!("Should have received a `BootstrapRequest`."),
             };
         } else {
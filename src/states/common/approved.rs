@@ -10,7 +10,8 @@ use super::Base;
 use crate::{
     chain::{
         AccumulatedEvent, AccumulatingEvent, Chain, EldersChange, EldersInfo, MemberState,
-        OnlinePayload, PollAccumulated, Proof, ProofSet, SectionKeyInfo, SendAckMessagePayload,
+        Misbehaviour, MisbehaviourReport, OnlinePayload, PollAccumulated, Proof, ProofSet,
+        Punishment, SectionKeyInfo, SendAckMessagePayload,
     },
     error::RoutingError,
     event::Event,
@@ -60,6 +61,11 @@ pub trait Approved: Base {
         outbox: &mut dyn EventBox,
     ) -> Result<(), RoutingError>;
 
+    /// Handles our own removal from the section. Elders stay until their role is handed over.
+    fn handle_our_offline(&mut self, _outbox: &mut dyn EventBox) -> Transition {
+        Transition::Stay
+    }
+
     /// Handle a member relocated.
     fn handle_member_relocated(
         &mut self,
@@ -118,6 +124,39 @@ pub trait Approved: Base {
         Ok(())
     }
 
+    /// Handle an accumulated `ProtocolVersion` event
+    fn handle_protocol_version_event(&mut self, version: u16, outbox: &mut dyn EventBox) {
+        info!(
+            "{} - Section switched to protocol version {}.",
+            self, version
+        );
+        self.send_event(Event::ProtocolVersionChanged(version), outbox);
+    }
+
+    /// Handle an accumulated `StatsRound` event
+    fn handle_stats_round_event(&mut self, outbox: &mut dyn EventBox) {
+        if let Some(stats) = self.chain().network_stats().cloned() {
+            self.send_event(Event::NetworkStats(stats), outbox);
+        }
+    }
+
+    /// Handle an accumulated `ProposeMerge` event
+    fn handle_propose_merge_event(&mut self) {}
+
+    /// Handle an accumulated `WithdrawMerge` event
+    fn handle_withdraw_merge_event(&mut self) {}
+
+    /// Handle an accumulated `SiblingMerge` event
+    fn handle_sibling_merge_event(&mut self) {}
+
+    /// Handle an accumulated `SignMergeKey` event
+    fn handle_sign_merge_key_event(&mut self) {}
+
+    /// Handle an accumulated `MergeKeySignature` event
+    fn handle_merge_key_signature_event(&mut self) -> Result<(), RoutingError> {
+        Ok(())
+    }
+
     /// Handles an accumulated `ParsecPrune` event.
     fn handle_prune_event(&mut self) -> Result<(), RoutingError>;
 
@@ -128,6 +167,16 @@ pub trait Approved: Base {
         p2p_node: P2pNode,
         outbox: &mut dyn EventBox,
     ) -> Result<Transition, RoutingError> {
+        if self.parsec_map().is_empty() {
+            trace!(
+                "{} - ignore parsec request v{} from {} - no parsec graph",
+                self,
+                msg_version,
+                p2p_node.public_id(),
+            );
+            return Ok(Transition::Stay);
+        }
+
         trace!(
             "{} - handle parsec request v{} from {} (last: v{})",
             self,
@@ -190,13 +239,14 @@ pub trait Approved: Base {
         let (version, gossip_target) = match target {
             Some((v, p)) => (v, p),
             None => {
-                let version = self.parsec_map().last_version();
                 let recipients = self.parsec_map().gossip_recipients();
                 if recipients.is_empty() {
                     trace!("{} Not sending gossip", self);
-                    // Parsec hasn't caught up with the event of us joining yet.
+                    // Parsec hasn't caught up with the event of us joining yet, or we lost our
+                    // graph and wait for a new one.
                     return;
                 }
+                let version = self.parsec_map().last_version();
 
                 let p2p_recipients: Vec<_> = recipients
                     .into_iter()
@@ -247,9 +297,12 @@ pub trait Approved: Base {
         while let Some(block) = self.parsec_map_mut().poll() {
             let parsec_version = self.parsec_map_mut().last_version();
             match block.payload() {
-                Observation::Accusation { .. } => {
-                    // FIXME: Handle properly
-                    unreachable!("...")
+                Observation::Accusation { offender, .. } => {
+                    info!(
+                        "{} Parsec Accusation {}: {}",
+                        self, parsec_version, offender
+                    );
+                    self.vote_for_misbehaviour(*offender, Misbehaviour::InvalidVote);
                 }
                 Observation::Genesis {
                     group,
@@ -376,6 +429,9 @@ pub trait Approved: Base {
             }
             AccumulatingEvent::Offline(pub_id) => {
                 self.handle_offline_event(pub_id, outbox)?;
+                if pub_id == *self.id() {
+                    return Ok(self.handle_our_offline(outbox));
+                }
             }
             AccumulatingEvent::SectionInfo(_, _) => {
                 return self.handle_section_info_event(old_pfx, event.elders_change, outbox);
@@ -398,6 +454,23 @@ pub trait Approved: Base {
                 self.handle_relocate_prepare_event(pub_id, count, outbox);
             }
             AccumulatingEvent::User(payload) => self.handle_user_event(payload, outbox)?,
+            AccumulatingEvent::ProtocolVersion(version) => {
+                self.handle_protocol_version_event(version, outbox)
+            }
+            AccumulatingEvent::Misbehaviour(report) => {
+                return self.handle_misbehaviour_event(report, outbox);
+            }
+            AccumulatingEvent::StatsRound(_) => self.handle_stats_round_event(outbox),
+            AccumulatingEvent::ProposeMerge => self.handle_propose_merge_event(),
+            AccumulatingEvent::WithdrawMerge => self.handle_withdraw_merge_event(),
+            AccumulatingEvent::SiblingMerge(_) => self.handle_sibling_merge_event(),
+            AccumulatingEvent::SignMergeKey(_) => self.handle_sign_merge_key_event(),
+            AccumulatingEvent::MergeKeySignature(_, _) => {
+                self.handle_merge_key_signature_event()?;
+            }
+            AccumulatingEvent::NeighbourStats(_) | AccumulatingEvent::LoadMetrics(_) => {
+                // Update neighbour_stats and load_metrics is handled within the chain.
+            }
         }
 
         Ok(Transition::Stay)
@@ -414,6 +487,22 @@ pub trait Approved: Base {
                 &log_ident,
             );
         }
+
+        let lagging_nodes = self.chain_mut().check_lagging_members();
+        for pub_id in lagging_nodes.difference(&unresponsive_nodes) {
+            self.vote_for_misbehaviour(*pub_id, Misbehaviour::Unresponsive);
+        }
+    }
+
+    // Vote to record a new occurrence of misbehaviour of one of our members.
+    fn vote_for_misbehaviour(&mut self, offender: PublicId, kind: Misbehaviour) {
+        let report = self.chain().misbehaviour_report(offender, kind);
+        let log_ident = self.log_ident();
+        info!("{} Voting for misbehaviour {:?}", log_ident, report);
+        self.parsec_map_mut().vote_for(
+            AccumulatingEvent::Misbehaviour(report).into_network_event(),
+            &log_ident,
+        );
     }
 
     fn disconnect_by_id_lookup(&mut self, pub_id: &PublicId) {
@@ -442,7 +531,7 @@ pub trait Approved: Base {
 
             let pub_id = *payload.p2p_node.public_id();
             self.chain_mut()
-                .add_member(payload.p2p_node.clone(), payload.age);
+                .add_member(payload.p2p_node.clone(), payload.age, payload.versions);
             self.chain_mut().increment_age_counters(&pub_id);
             self.handle_member_added(payload, outbox)?;
         }
@@ -469,6 +558,40 @@ pub trait Approved: Base {
         Ok(())
     }
 
+    fn handle_misbehaviour_event(
+        &mut self,
+        report: MisbehaviourReport,
+        outbox: &mut dyn EventBox,
+    ) -> Result<Transition, RoutingError> {
+        let punishment = if let Some(punishment) = self.chain_mut().record_misbehaviour(&report) {
+            punishment
+        } else {
+            info!("{} - ignore Misbehaviour: {:?}.", self, report);
+            return Ok(Transition::Stay);
+        };
+
+        info!(
+            "{} - handle Misbehaviour: {:?}, punishment: {:?}.",
+            self, report, punishment
+        );
+
+        if punishment != Punishment::Evict {
+            return Ok(Transition::Stay);
+        }
+
+        let pub_id = report.offender;
+        let _ = self.chain_mut().remove_member(&pub_id);
+        self.disconnect_by_id_lookup(&pub_id);
+        self.handle_member_removed(pub_id, outbox)?;
+        self.send_event(Event::MemberEvicted(pub_id), outbox);
+
+        if pub_id == *self.id() {
+            Ok(self.handle_our_offline(outbox))
+        } else {
+            Ok(Transition::Stay)
+        }
+    }
+
     fn handle_relocate_event(
         &mut self,
         details: RelocateDetails,
@@ -8,18 +8,23 @@
 
 use crate::{
     action::Action,
-    chain::SectionKeyInfo,
+    chain::{EldersInfo, SectionKeyInfo, SectionProofChain},
     error::{Result, RoutingError},
     event::Client,
     id::{FullId, PublicId},
+    join_policy::JoinConfig,
     location::{DstLocation, SrcLocation},
-    messages::{Message, MessageWithBytes, Variant},
+    messages::{
+        ClientMessage, Message, MessageId, MessageWithBytes, SrcAuthority, Variant, VersionRange,
+        PROTOCOL_VERSION,
+    },
     network_service::NetworkService,
     outbox::EventBox,
     peer_map::PeerMap,
     quic_p2p::{Peer, Token},
     rng::MainRng,
     state_machine::Transition,
+    time::Duration,
     timer::Timer,
     utils::LogIdent,
     xor_space::{Prefix, XorName},
@@ -31,6 +36,7 @@ use itertools::Itertools;
 use log::LogLevel;
 use std::{
     fmt::{Debug, Display},
+    iter,
     net::SocketAddr,
     slice,
 };
@@ -45,6 +51,7 @@ pub trait Base: Display {
     fn peer_map_mut(&mut self) -> &mut PeerMap;
     fn timer(&mut self) -> &mut Timer;
     fn rng(&mut self) -> &mut MainRng;
+    fn join_config_mut(&mut self) -> &mut JoinConfig;
 
     fn log_ident(&self) -> LogIdent {
         LogIdent::new(self)
@@ -72,6 +79,18 @@ pub trait Base: Display {
 
     fn unhandled_message(&mut self, sender: Option<ConnectionInfo>, message: Message);
 
+    /// Called for each message received from a peer, before it is fully parsed.
+    fn count_incoming_message(&mut self, _src_addr: &SocketAddr, _msg: &MessageWithBytes) {}
+
+    /// Handles a message that failed verification with the given error.
+    fn handle_unverified_message(
+        &mut self,
+        _sender: Option<&ConnectionInfo>,
+        _message: &Message,
+        _error: &RoutingError,
+    ) {
+    }
+
     fn handle_action(&mut self, action: Action, outbox: &mut dyn EventBox) -> Transition {
         match action {
             Action::SendMessage {
@@ -83,6 +102,47 @@ pub trait Base: Display {
                 let result = self.handle_send_message(src, dst, content);
                 let _ = result_tx.send(result);
             }
+            Action::SendMessageWithReceipt {
+                src,
+                dst,
+                id,
+                content,
+                result_tx,
+            } => {
+                let result = self.handle_send_message_with_receipt(src, dst, id, content);
+                let _ = result_tx.send(result);
+            }
+            Action::SendRequest {
+                src,
+                dst,
+                id,
+                content,
+                timeout,
+                result_tx,
+            } => {
+                let result = self.handle_send_request(src, dst, id, content, timeout);
+                let _ = result_tx.send(result);
+            }
+            Action::SendResponse {
+                src,
+                dst,
+                id,
+                content,
+                result_tx,
+            } => {
+                let result = self.handle_send_response(src, dst, id, content);
+                let _ = result_tx.send(result);
+            }
+            Action::SendMessageToClientFromSection {
+                proxy,
+                client,
+                content,
+                result_tx,
+            } => {
+                let result =
+                    self.handle_send_message_to_client_from_section(proxy, client, content);
+                let _ = result_tx.send(result);
+            }
             Action::HandleTimeout(token) => match self.handle_timeout(token, outbox) {
                 Transition::Stay => (),
                 transition => {
@@ -106,6 +166,18 @@ pub trait Base: Display {
                 self.send_message_to_client(peer_addr, msg, token);
                 let _ = result_tx.send(Ok(()));
             }
+            Action::Leave { result_tx } => match self.handle_leave(outbox) {
+                Ok(Transition::Stay) => {
+                    let _ = result_tx.send(Ok(()));
+                }
+                Ok(transition) => {
+                    let _ = result_tx.send(Ok(()));
+                    return transition;
+                }
+                Err(error) => {
+                    let _ = result_tx.send(Err(error));
+                }
+            },
         }
 
         self.finish_handle_action(outbox)
@@ -121,6 +193,61 @@ pub trait Base: Display {
         Err(RoutingError::InvalidState)
     }
 
+    fn handle_send_message_with_receipt(
+        &mut self,
+        _src: SrcLocation,
+        _dst: DstLocation,
+        _id: MessageId,
+        _content: Vec<u8>,
+    ) -> Result<(), RoutingError> {
+        warn!(
+            "{} - Cannot handle SendMessageWithReceipt - invalid state.",
+            self
+        );
+        Err(RoutingError::InvalidState)
+    }
+
+    fn handle_send_request(
+        &mut self,
+        _src: SrcLocation,
+        _dst: DstLocation,
+        _id: MessageId,
+        _content: Vec<u8>,
+        _timeout: Duration,
+    ) -> Result<(), RoutingError> {
+        warn!("{} - Cannot handle SendRequest - invalid state.", self);
+        Err(RoutingError::InvalidState)
+    }
+
+    fn handle_send_response(
+        &mut self,
+        _src: SrcLocation,
+        _dst: DstLocation,
+        _id: MessageId,
+        _content: Vec<u8>,
+    ) -> Result<(), RoutingError> {
+        warn!("{} - Cannot handle SendResponse - invalid state.", self);
+        Err(RoutingError::InvalidState)
+    }
+
+    fn handle_send_message_to_client_from_section(
+        &mut self,
+        _proxy: XorName,
+        _client: XorName,
+        _content: Vec<u8>,
+    ) -> Result<(), RoutingError> {
+        warn!(
+            "{} - Cannot handle SendMessageToClientFromSection - invalid state.",
+            self
+        );
+        Err(RoutingError::InvalidState)
+    }
+
+    fn handle_leave(&mut self, _outbox: &mut dyn EventBox) -> Result<Transition, RoutingError> {
+        warn!("{} - Cannot handle Leave - invalid state.", self);
+        Err(RoutingError::InvalidState)
+    }
+
     fn handle_timeout(&mut self, _token: u64, _outbox: &mut dyn EventBox) -> Transition {
         Transition::Stay
     }
@@ -165,9 +292,7 @@ pub trait Base: Display {
             }
             NewMessage { peer_addr, msg } => {
                 if self.peer_map().is_known_client(&peer_addr) {
-                    let client_event = Client::NewMessage { peer_addr, msg };
-                    outbox.send_event(client_event.into());
-                    Transition::Stay
+                    self.handle_client_message(peer_addr, msg, outbox)
                 } else {
                     self.handle_new_message(peer_addr, msg, outbox)
                 }
@@ -260,6 +385,18 @@ pub trait Base: Display {
             }
         };
 
+        self.count_incoming_message(&src_addr, &msg);
+
+        if !VersionRange::OURS.contains(msg.message_version()) {
+            debug!(
+                "{} - Ignoring message from {} with unsupported protocol version {}.",
+                self,
+                src_addr,
+                msg.message_version()
+            );
+            return Transition::Stay;
+        }
+
         let sender = self.peer_map().get_connection_info(&src_addr).cloned();
         match self.try_handle_message(sender, msg, outbox) {
             Ok(transition) => transition,
@@ -308,11 +445,21 @@ pub trait Base: Display {
         }
 
         let msg = msg.take_or_deserialize_message()?;
-        if self.should_handle_message(&msg) && self.verify_message(&msg)? {
-            self.handle_message(sender, msg, outbox)
-        } else {
+        if !self.should_handle_message(&msg) {
             self.unhandled_message(sender, msg);
-            Ok(Transition::Stay)
+            return Ok(Transition::Stay);
+        }
+
+        match self.verify_message(&msg) {
+            Ok(true) => self.handle_message(sender, msg, outbox),
+            Ok(false) => {
+                self.unhandled_message(sender, msg);
+                Ok(Transition::Stay)
+            }
+            Err(error) => {
+                self.handle_unverified_message(sender.as_ref(), &msg, &error);
+                Err(error)
+            }
         }
     }
 
@@ -325,6 +472,177 @@ pub trait Base: Display {
         }
     }
 
+    fn handle_client_message(
+        &mut self,
+        peer_addr: SocketAddr,
+        bytes: Bytes,
+        outbox: &mut dyn EventBox,
+    ) -> Transition {
+        match ClientMessage::from_bytes(&bytes) {
+            Ok(ClientMessage::GetSectionInfo) => {
+                self.send_section_info_to_client(peer_addr);
+                Transition::Stay
+            }
+            Ok(ClientMessage::Message(msg)) => {
+                self.handle_routing_message_from_client(peer_addr, msg, outbox)
+            }
+            Ok(msg) => {
+                debug!(
+                    "{} - Unexpected message from client {}: {:?}",
+                    self, peer_addr, msg
+                );
+                Transition::Stay
+            }
+            Err(_) => {
+                // Not a routing message - pass it to the user library as is.
+                let client_event = Client::NewMessage {
+                    peer_addr,
+                    msg: bytes,
+                };
+                outbox.send_event(client_event.into());
+                Transition::Stay
+            }
+        }
+    }
+
+    fn send_section_info_to_client(&mut self, peer_addr: SocketAddr) {
+        let (elders_info, proof) = if let Some((elders_info, proof)) = self.our_section_info() {
+            (elders_info.clone(), proof.clone())
+        } else {
+            debug!(
+                "{} - Cannot send section info to client {} - not a section member.",
+                self, peer_addr
+            );
+            return;
+        };
+
+        let msg = ClientMessage::SectionInfo {
+            proxy_id: *self.id(),
+            proof,
+        };
+        self.send_client_message(peer_addr, &msg);
+
+        // The client only trusts our elders once our section signed them. If we don't have them
+        // signed yet, all our clients get them once we do.
+        let signed_info = self
+            .signed_section_info()
+            .filter(|msg| match &msg.variant {
+                Variant::SectionInfoResponse(info) => *info == elders_info,
+                _ => false,
+            })
+            .cloned();
+        if let Some(msg) = signed_info {
+            self.send_client_message(peer_addr, &ClientMessage::Message(msg));
+        } else {
+            self.request_signed_section_info();
+        }
+    }
+
+    // Keeps the elders info of our section signed by our section, and passes it on to all our
+    // clients.
+    fn handle_signed_section_info(&mut self, msg: Message) {
+        let is_current = match (&msg.src, &msg.variant, self.our_section_info()) {
+            (
+                SrcAuthority::Section { prefix, .. },
+                Variant::SectionInfoResponse(elders_info),
+                Some((our_info, _)),
+            ) => prefix == elders_info.prefix() && elders_info == our_info,
+            _ => false,
+        };
+        if !is_current {
+            debug!(
+                "{} - Ignoring outdated or invalid section info: {:?}",
+                self, msg
+            );
+            return;
+        }
+
+        let clients: Vec<_> = self.peer_map().clients().copied().collect();
+        for peer_addr in clients {
+            self.send_client_message(peer_addr, &ClientMessage::Message(msg.clone()));
+        }
+
+        self.set_signed_section_info(msg);
+    }
+
+    fn send_client_message(&mut self, peer_addr: SocketAddr, msg: &ClientMessage) {
+        match msg.to_bytes() {
+            Ok(bytes) => {
+                let token = self.network_service_mut().next_msg_token();
+                self.send_message_to_client(peer_addr, bytes, token);
+            }
+            Err(error) => error!(
+                "{} - Failed to serialize client message {:?}: {:?}",
+                self, msg, error
+            ),
+        }
+    }
+
+    // Routes a message signed by a client towards its destination, as if it was received from
+    // another node.
+    fn handle_routing_message_from_client(
+        &mut self,
+        peer_addr: SocketAddr,
+        msg: Message,
+        outbox: &mut dyn EventBox,
+    ) -> Transition {
+        // Clients are only allowed to send user messages signed by themselves.
+        let client_name = match (&msg.src, &msg.variant) {
+            (SrcAuthority::Node { public_id, .. }, Variant::UserMessage(_)) => *public_id.name(),
+            _ => {
+                debug!(
+                    "{} - Invalid message from client {}: {:?}",
+                    self, peer_addr, msg
+                );
+                return Transition::Stay;
+            }
+        };
+
+        if let Err(error) = msg.verify(iter::empty()) {
+            debug!(
+                "{} - Failed to verify message from client {}: {:?}",
+                self, peer_addr, error
+            );
+            return Transition::Stay;
+        }
+
+        // The signature proves the client holds the key of the name, so we can deliver the
+        // messages addressed to that name to it.
+        self.peer_map_mut().set_client_name(peer_addr, client_name);
+
+        let msg = match MessageWithBytes::new(msg, &self.log_ident()) {
+            Ok(msg) => msg,
+            Err(error) => {
+                debug!("{} - Failed to serialize message: {:?}", self, error);
+                return Transition::Stay;
+            }
+        };
+
+        match self.try_handle_message(None, msg, outbox) {
+            Ok(transition) => transition,
+            Err(error) => {
+                debug!(
+                    "{} - Failed to handle message from client {}: {:?}",
+                    self, peer_addr, error
+                );
+                Transition::Stay
+            }
+        }
+    }
+
+    // Delivers a message addressed to one of our clients. The message is passed on as is, so the
+    // client can verify its signature itself.
+    fn send_message_to_known_client(&mut self, client: &XorName, msg: Message) {
+        if let Some(peer_addr) = self.peer_map().get_client_addr(client) {
+            self.send_client_message(peer_addr, &ClientMessage::Message(msg));
+        } else {
+            debug!(
+                "{} - Cannot deliver message to unknown client {}: {:?}",
+                self, client, msg
+            );
+        }
+    }
+
     fn handle_unsent_message(
         &mut self,
         peer_addr: SocketAddr,
@@ -386,8 +704,35 @@ pub trait Base: Display {
         None
     }
 
+    // Returns the elders info and the key history of our section, or `None` if we are not a member
+    // of any section yet.
+    fn our_section_info(&self) -> Option<(&EldersInfo, &SectionProofChain)> {
+        None
+    }
+
+    // Returns the last `SectionInfoResponse` our section sent us about itself, if any.
+    fn signed_section_info(&self) -> Option<&Message> {
+        None
+    }
+
+    fn set_signed_section_info(&mut self, _msg: Message) {}
+
+    // Asks our section to send us its elders info, signed, so we can pass it on to our clients.
+    fn request_signed_section_info(&mut self) {}
+
+    // Returns the protocol version to stamp our outgoing messages with. Once we are a member of a
+    // section, this is the version our section agreed on.
+    fn protocol_version(&self) -> u16 {
+        PROTOCOL_VERSION
+    }
+
     fn send_direct_message(&mut self, recipient: &ConnectionInfo, variant: Variant) {
-        let message = match Message::single_src(self.full_id(), DstLocation::Direct, variant) {
+        let message = match Message::single_src(
+            self.full_id(),
+            self.protocol_version(),
+            DstLocation::Direct,
+            variant,
+        ) {
             Ok(message) => message,
             Err(error) => {
                 error!("{} - Failed to create message: {:?}", self, error);
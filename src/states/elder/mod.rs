@@ -17,27 +17,36 @@ use super::{
 use crate::{
     chain::{
         delivery_group_size, AccumulatingEvent, AckMessagePayload, Chain, EldersChange, EldersInfo,
-        EventSigPayload, GenesisPfxInfo, IntoAccumulatingEvent, NetworkEvent, NetworkParams,
-        OnlinePayload, ParsecResetData, SectionKeyInfo, SendAckMessagePayload, MIN_AGE,
-        MIN_AGE_COUNTER,
+        EventSigPayload, GenesisPfxInfo, IntoAccumulatingEvent, MergeRequest, Misbehaviour,
+        NetworkEvent, NetworkParams, NetworkStats, OnlinePayload, ParsecResetData, SectionKeyInfo,
+        SectionProofChain, SendAckMessagePayload, Strategies, MIN_AGE, MIN_AGE_COUNTER,
     },
+    crypto,
     error::{Result, RoutingError},
     event::{Connected, Event},
     id::{FullId, P2pNode, PublicId},
+    join_admission::{JoinAdmission, SectionLoad},
+    join_policy::{JoinConfig, JoinDecision, MAX_APP_PAYLOAD_SIZE},
     location::{DstLocation, SrcLocation},
     messages::{
-        AccumulatingMessage, BootstrapResponse, JoinRequest, MemberKnowledge, Message,
+        AccumulatingMessage, BootstrapResponse, JoinRequest, MemberKnowledge, Message, MessageId,
         MessageWithBytes, PlainMessage, QueuedMessage, SrcAuthority, Variant, VerifyStatus,
+        VersionRange,
     },
     network_service::NetworkService,
     outbox::EventBox,
     parsec::{self, generate_first_dkg_result, DkgResultWrapper, ParsecMap},
     pause::PausedState,
     peer_map::PeerMap,
+    pending_deliveries::PendingDeliveries,
+    pending_requests::PendingRequests,
+    rate_limiter::RateLimiter,
     relocation::RelocateDetails,
     rng::{self, MainRng},
     routing_message_filter::RoutingMessageFilter,
     signature_accumulator::SignatureAccumulator,
+    snapshot::Snapshot,
+    split::LoadMetrics,
     state_machine::{State, Transition},
     time::Duration,
     timer::Timer,
@@ -47,6 +56,7 @@ use crate::{
 use hex_fmt::HexFmt;
 use itertools::Itertools;
 use log::LogLevel;
+use resource_proof::ResourceProof;
 use std::{
     cmp,
     collections::{BTreeMap, BTreeSet, HashSet, VecDeque},
@@ -55,8 +65,20 @@ use std::{
     net::SocketAddr,
 };
 
-/// Time after which an Elder should send a new Gossip.
-const GOSSIP_TIMEOUT: Duration = Duration::from_secs(2);
+/// Default time after which an Elder should send a new Gossip.
+pub const GOSSIP_TIMEOUT: Duration = Duration::from_secs(2);
+/// Default time within which a joining peer has to solve its resource proof challenge.
+pub const RESOURCE_PROOF_TIMEOUT: Duration = Duration::from_secs(300);
+// Default resource proof challenge in production
+#[cfg(not(feature = "mock_base"))]
+pub const RESOURCE_PROOF_DIFFICULTY: u8 = 8;
+#[cfg(not(feature = "mock_base"))]
+pub const RESOURCE_PROOF_TARGET_SIZE: usize = 1024 * 1024;
+// Default resource proof challenge in tests, cheap enough to not slow them down
+#[cfg(feature = "mock_base")]
+pub const RESOURCE_PROOF_DIFFICULTY: u8 = 1;
+#[cfg(feature = "mock_base")]
+pub const RESOURCE_PROOF_TARGET_SIZE: usize = 1024;
 /// Number of RelocatePrepare to consensus before actually relocating a node.
 /// This helps avoid relocated node receiving message they need to process from previous section.
 const INITIAL_RELOCATE_COOL_DOWN_COUNT_DOWN: i32 = 10;
@@ -82,6 +104,16 @@ enum PendingMessageKey {
     },
 }
 
+/// Resource proof challenge sent to a joining peer.
+struct PendingChallenge {
+    /// The nonce the proof must be computed with.
+    nonce: [u8; 32],
+    /// The payload to vote for once the peer proved its resources.
+    payload: OnlinePayload,
+    /// Identifies the timeout of the challenge.
+    timer_token: u64,
+}
+
 pub struct ElderDetails {
     pub chain: Chain,
     pub network_service: NetworkService,
@@ -94,6 +126,7 @@ pub struct ElderDetails {
     pub parsec_map: ParsecMap,
     pub msg_filter: RoutingMessageFilter,
     pub timer: Timer,
+    pub join_config: JoinConfig,
     pub rng: MainRng,
 }
 
@@ -110,22 +143,47 @@ pub struct Elder {
     parsec_map: ParsecMap,
     gen_pfx_info: GenesisPfxInfo,
     gossip_timer_token: u64,
+    // Set while we periodically query other sections for their elders.
+    section_map_timer_token: Option<u64>,
+    // Set while our section periodically agrees on its statistics.
+    stats_timer_token: Option<u64>,
     chain: Chain,
     pfx_is_successfully_polled: bool,
     // DKG cache
     dkg_cache: BTreeMap<BTreeSet<PublicId>, EldersInfo>,
+    // The merged section and its key, waiting for the sibling we absorb to sign the key.
+    merge_dkg_result: Option<(EldersInfo, bls::PublicKey)>,
     // Messages we received but not accumulated yet, so may need to re-swarm.
     pending_voted_msgs: BTreeMap<PendingMessageKey, Message>,
     /// The knowledge of the non-elder members about our section.
     members_knowledge: BTreeMap<XorName, MemberKnowledge>,
+    // Requests we sent and are waiting for the response to.
+    pending_requests: PendingRequests,
+    // Messages we sent and are waiting for the delivery receipt of.
+    pending_deliveries: PendingDeliveries,
+    // Resource proof challenges sent to joining peers and waiting for their solution.
+    pending_challenges: BTreeMap<PublicId, PendingChallenge>,
+    // Elders of our section which lost their parsec graph and wait for a new instance to join.
+    parsec_rejoins: BTreeSet<PublicId>,
+    join_admission: JoinAdmission,
+    // Counts the messages from our members, to detect spam.
+    rate_limiter: RateLimiter,
+    // Our elders info as signed by our section, to pass on to our clients.
+    signed_section_info: Option<Message>,
+    join_config: JoinConfig,
+    // Whether we are leaving the network and only wait for the handover of our elder role.
+    leaving: bool,
     rng: MainRng,
 }
 
 impl Elder {
+    #[allow(clippy::too_many_arguments)]
     pub fn first(
         mut network_service: NetworkService,
         full_id: FullId,
         network_cfg: NetworkParams,
+        strategies: Strategies,
+        join_config: JoinConfig,
         timer: Timer,
         mut rng: MainRng,
         outbox: &mut dyn EventBox,
@@ -144,13 +202,16 @@ impl Elder {
             latest_info: EldersInfo::default(),
             parsec_version: 0,
         };
-        let parsec_map = ParsecMap::default().with_init(&mut rng, full_id.clone(), &gen_pfx_info);
+        let parsec_map = ParsecMap::default()
+            .with_size_limit(network_cfg.parsec_size_limit)
+            .with_init(&mut rng, full_id.clone(), &gen_pfx_info);
         let chain = Chain::new(
             network_cfg,
             public_id,
             gen_pfx_info.clone(),
             first_dkg_result.secret_key_share,
-        );
+        )
+        .with_strategies(strategies);
 
         let details = ElderDetails {
             chain,
@@ -162,8 +223,12 @@ impl Elder {
             msg_backlog: Default::default(),
             sig_accumulator: Default::default(),
             parsec_map,
-            msg_filter: RoutingMessageFilter::new(),
+            msg_filter: RoutingMessageFilter::with_expiry_durations(
+                network_cfg.incoming_filter_expiry,
+                network_cfg.outgoing_filter_expiry,
+            ),
             timer,
+            join_config,
             rng,
         };
 
@@ -189,10 +254,24 @@ impl Elder {
     }
 
     pub fn demote(
-        self,
+        mut self,
         gen_pfx_info: GenesisPfxInfo,
         outbox: &mut dyn EventBox,
     ) -> Result<State, RoutingError> {
+        if self
+            .chain
+            .our_prefix()
+            .is_extension_of(gen_pfx_info.first_info.prefix())
+        {
+            // Absorbed by our sibling: our parsec instances are unrelated to the merged section's.
+            self.parsec_map.clear();
+        }
+
+        // Adults don't receive responses, so the requests we are waiting for can't complete.
+        for (id, dst) in self.pending_requests.drain() {
+            outbox.send_event(Event::RequestTimedOut { id, dst });
+        }
+
         let details = AdultDetails {
             network_service: self.network_service,
             event_backlog: Vec::new(),
@@ -203,12 +282,15 @@ impl Elder {
             msg_filter: self.msg_filter,
             timer: self.timer,
             network_cfg: self.chain.network_cfg(),
+            strategies: self.chain.strategies().clone(),
+            join_config: self.join_config,
             rng: self.rng,
         };
         Adult::new(details, self.parsec_map, outbox).map(State::Adult)
     }
 
-    pub fn pause(self) -> PausedState {
+    pub fn pause(mut self) -> PausedState {
+        let network_config = self.network_service.pinned_config();
         PausedState {
             chain: self.chain,
             full_id: self.full_id,
@@ -216,17 +298,37 @@ impl Elder {
             msg_filter: self.msg_filter,
             msg_queue: self.msg_queue,
             msg_backlog: self.msg_backlog,
-            network_service: self.network_service,
-            network_rx: None,
+            network_config,
             sig_accumulator: self.sig_accumulator,
             parsec_map: self.parsec_map,
+            join_config: self.join_config,
+        }
+    }
+
+    pub fn snapshot(&mut self) -> Snapshot {
+        Snapshot {
+            network_config: self.network_service.pinned_config(),
+            chain: &self.chain,
+            full_id: &self.full_id,
+            gen_pfx_info: &self.gen_pfx_info,
+            msg_queue: Some(&self.msg_queue),
+            msg_backlog: &self.msg_backlog,
+            sig_accumulator: &self.sig_accumulator,
         }
     }
 
-    pub fn resume(state: PausedState, timer: Timer) -> Self {
-        Self::new(ElderDetails {
+    pub fn resume(
+        mut state: PausedState,
+        mut network_service: NetworkService,
+        timer: Timer,
+    ) -> Self {
+        network_service.set_max_resends(state.chain.network_cfg().max_resends);
+        let mut rng = rng::new();
+        state.ensure_parsec_map(&mut rng);
+
+        let mut elder = Self::new(ElderDetails {
             chain: state.chain,
-            network_service: state.network_service,
+            network_service,
             event_backlog: Vec::new(),
             full_id: state.full_id,
             gen_pfx_info: state.gen_pfx_info,
@@ -236,8 +338,27 @@ impl Elder {
             parsec_map: state.parsec_map,
             msg_filter: state.msg_filter,
             timer,
-            rng: rng::new(),
-        })
+            join_config: state.join_config,
+            rng,
+        });
+
+        if elder.parsec_map.is_empty() {
+            elder.send_parsec_rejoin();
+        }
+
+        elder
+    }
+
+    pub fn genesis_key(&self) -> &bls::PublicKey {
+        self.chain.our_history().first_key_info().key()
+    }
+
+    pub fn section_for(&self, name: &XorName) -> Option<&EldersInfo> {
+        self.chain.section_for(name)
+    }
+
+    pub fn network_stats(&self) -> Option<&NetworkStats> {
+        self.chain.network_stats()
     }
 
     pub fn our_elders(&self) -> impl Iterator<Item = &P2pNode> {
@@ -254,7 +375,16 @@ impl Elder {
 
     fn new(details: ElderDetails) -> Self {
         let timer = details.timer;
-        let gossip_timer_token = timer.schedule(GOSSIP_TIMEOUT);
+        let network_cfg = details.chain.network_cfg();
+        let gossip_timer_token = timer.schedule(network_cfg.gossip_timeout);
+        let section_map_timer_token = if network_cfg.section_map_capacity > 0 {
+            Some(timer.schedule(network_cfg.section_map_query_interval))
+        } else {
+            None
+        };
+        let stats_timer_token = network_cfg
+            .network_stats_interval
+            .map(|interval| timer.schedule(interval));
 
         Self {
             network_service: details.network_service,
@@ -267,11 +397,23 @@ impl Elder {
             parsec_map: details.parsec_map,
             gen_pfx_info: details.gen_pfx_info,
             gossip_timer_token,
+            section_map_timer_token,
+            stats_timer_token,
             chain: details.chain,
             pfx_is_successfully_polled: false,
             dkg_cache: Default::default(),
+            merge_dkg_result: None,
             pending_voted_msgs: Default::default(),
             members_knowledge: Default::default(),
+            pending_requests: Default::default(),
+            pending_deliveries: Default::default(),
+            pending_challenges: Default::default(),
+            parsec_rejoins: Default::default(),
+            join_admission: Default::default(),
+            rate_limiter: Default::default(),
+            signed_section_info: None,
+            join_config: details.join_config,
+            leaving: false,
             rng: details.rng,
         }
     }
@@ -356,6 +498,102 @@ impl Elder {
         self.send_parsec_gossip(Some((payload.parsec_version, p2p_node)))
     }
 
+    fn handle_leave_notice(&mut self, pub_id: PublicId) {
+        if !self.chain.is_peer_our_member(&pub_id) {
+            debug!(
+                "{} - Ignoring leave notice from {} - not a member of our section.",
+                self, pub_id
+            );
+            return;
+        }
+
+        info!("{} - Member {} is leaving.", self, pub_id);
+        self.vote_for_event(AccumulatingEvent::Offline(pub_id));
+    }
+
+    // Asks the other elders of our section for a new parsec instance, as we lost our graph and
+    // can't take part in the current one without forking it.
+    fn send_parsec_rejoin(&mut self) {
+        info!(
+            "{} - Lost our parsec graph v{}, asking our section for a new one.",
+            self, self.gen_pfx_info.parsec_version
+        );
+
+        let targets: Vec<_> = self
+            .chain
+            .our_elders()
+            .filter(|p2p_node| p2p_node.public_id() != self.id())
+            .map(|p2p_node| p2p_node.connection_info().clone())
+            .collect();
+        for conn_info in targets {
+            self.send_direct_message(&conn_info, Variant::ParsecRejoin);
+        }
+    }
+
+    fn handle_parsec_rejoin(&mut self, pub_id: PublicId) {
+        if pub_id == *self.id() || !self.chain.our_info().is_member(&pub_id) {
+            debug!(
+                "{} - Ignoring ParsecRejoin from {} - not another elder of our section.",
+                self, pub_id
+            );
+            return;
+        }
+
+        info!("{} - Elder {} lost its parsec graph.", self, pub_id);
+        let _ = self.parsec_rejoins.insert(pub_id);
+
+        // A split resets parsec anyway, and sends the new genesis to the elder when it completes.
+        if !self.chain.split_in_progress() {
+            self.vote_for_event(AccumulatingEvent::ParsecPrune);
+            self.parsec_map.set_pruning_voted_for();
+        }
+    }
+
+    // Joins the parsec instance our section started for us after we lost our graph.
+    fn handle_parsec_rejoin_genesis(&mut self, gen_pfx_info: GenesisPfxInfo) -> Transition {
+        if gen_pfx_info.parsec_version <= self.gen_pfx_info.parsec_version {
+            return Transition::Stay;
+        }
+
+        if !gen_pfx_info.first_info.is_member(self.id()) {
+            info!(
+                "{} - No longer an elder of the new parsec instance, demoting: {:?}",
+                self, gen_pfx_info
+            );
+            return Transition::Demote { gen_pfx_info };
+        }
+
+        // Our key share is only valid if the section key didn't change while we were away.
+        let secret_key_share = if *self.chain.our_section_bls_keys() == gen_pfx_info.first_bls_keys
+        {
+            self.chain
+                .our_section_bls_secret_key_share()
+                .ok()
+                .map(|share| share.key.clone())
+        } else {
+            None
+        };
+        if secret_key_share.is_none() {
+            warn!(
+                "{} - Rejoining parsec without a key share of our section.",
+                self
+            );
+        }
+
+        info!("{} - Rejoining parsec: {:?}", self, gen_pfx_info);
+        self.chain = Chain::new(
+            self.chain.network_cfg(),
+            *self.id(),
+            gen_pfx_info.clone(),
+            secret_key_share,
+        )
+        .with_strategies(self.chain.strategies().clone());
+        self.gen_pfx_info = gen_pfx_info;
+        self.init_parsec();
+
+        Transition::Stay
+    }
+
     // Connect to all elders from our section or neighbour sections that we are not yet connected
     // to and disconnect from peers that are no longer elders of neighbour sections.
     fn update_peer_connections(&mut self, change: &EldersChange) {
@@ -447,7 +685,17 @@ impl Elder {
                 | AccumulatingEvent::NeighbourInfo(_)
                 | AccumulatingEvent::TheirKeyInfo(_)
                 | AccumulatingEvent::SendAckMessage(_)
-                | AccumulatingEvent::User(_) => false,
+                | AccumulatingEvent::User(_)
+                | AccumulatingEvent::ProtocolVersion(_)
+                | AccumulatingEvent::Misbehaviour(_)
+                | AccumulatingEvent::StatsRound(_)
+                | AccumulatingEvent::NeighbourStats(_)
+                | AccumulatingEvent::ProposeMerge
+                | AccumulatingEvent::WithdrawMerge
+                | AccumulatingEvent::SiblingMerge(_)
+                | AccumulatingEvent::SignMergeKey(_)
+                | AccumulatingEvent::MergeKeySignature(_, _)
+                | AccumulatingEvent::LoadMetrics(_) => false,
             })
             .cloned()
             .collect();
@@ -461,6 +709,9 @@ impl Elder {
                         our_pfx.matches(payload.p2p_node.name())
                     }
                     AccumulatingEvent::Offline(pub_id) => our_pfx.matches(pub_id.name()),
+                    AccumulatingEvent::Misbehaviour(ref report) => {
+                        our_pfx.matches(report.offender.name())
+                    }
                     AccumulatingEvent::AckMessage(ref payload) => {
                         our_pfx.matches(&payload.dst_name)
                     }
@@ -469,7 +720,25 @@ impl Elder {
                         our_pfx.matches(details.pub_id.name())
                     }
                     // Drop: no longer relevant after prefix change.
-                    AccumulatingEvent::StartDkg(_) | AccumulatingEvent::ParsecPrune => false,
+                    AccumulatingEvent::StartDkg(_)
+                    | AccumulatingEvent::ParsecPrune
+                    | AccumulatingEvent::StatsRound(_)
+                    | AccumulatingEvent::ProposeMerge
+                    | AccumulatingEvent::WithdrawMerge
+                    | AccumulatingEvent::SignMergeKey(_)
+                    | AccumulatingEvent::MergeKeySignature(_, _)
+                    | AccumulatingEvent::LoadMetrics(_) => false,
+
+                    // Keep: Still relevant if from one of our neighbours.
+                    AccumulatingEvent::NeighbourStats(ref stats) => {
+                        our_pfx.is_neighbour(&stats.section.prefix)
+                    }
+
+                    // Keep: Still relevant if from our sibling.
+                    AccumulatingEvent::SiblingMerge(ref request) => {
+                        our_pfx.bit_count() > 0
+                            && our_pfx.sibling() == *request.elders_info.prefix()
+                    }
 
                     // Keep: Additional signatures for neighbours for sec-msg-relay.
                     AccumulatingEvent::SectionInfo(ref elders_info, _)
@@ -480,7 +749,8 @@ impl Elder {
                     // Keep: Still relevant after prefix change.
                     AccumulatingEvent::TheirKeyInfo(_)
                     | AccumulatingEvent::SendAckMessage(_)
-                    | AccumulatingEvent::User(_) => true,
+                    | AccumulatingEvent::User(_)
+                    | AccumulatingEvent::ProtocolVersion(_) => true,
                 }
             })
             .collect();
@@ -509,7 +779,17 @@ impl Elder {
             | evt @ AccumulatingEvent::NeighbourInfo(_)
             | evt @ AccumulatingEvent::TheirKeyInfo(_)
             | evt @ AccumulatingEvent::SendAckMessage(_)
-            | evt @ AccumulatingEvent::User(_) => {
+            | evt @ AccumulatingEvent::User(_)
+            | evt @ AccumulatingEvent::ProtocolVersion(_)
+            | evt @ AccumulatingEvent::Misbehaviour(_)
+            | evt @ AccumulatingEvent::StatsRound(_)
+            | evt @ AccumulatingEvent::NeighbourStats(_)
+            | evt @ AccumulatingEvent::ProposeMerge
+            | evt @ AccumulatingEvent::WithdrawMerge
+            | evt @ AccumulatingEvent::SiblingMerge(_)
+            | evt @ AccumulatingEvent::SignMergeKey(_)
+            | evt @ AccumulatingEvent::MergeKeySignature(_, _)
+            | evt @ AccumulatingEvent::LoadMetrics(_) => {
                 log_or_panic!(LogLevel::Error, "unexpected event {:?}", evt);
             }
             AccumulatingEvent::Online(payload) => {
@@ -599,6 +879,15 @@ impl Elder {
         Ok(complete_data)
     }
 
+    fn prepare_finalise_merge(&mut self) -> Result<CompleteParsecReset, RoutingError> {
+        let reset_data = self
+            .chain
+            .finalise_prefix_change(self.parsec_map.last_version().saturating_add(1))?;
+        let mut complete_data = self.complete_parsec_reset_data(reset_data);
+        complete_data.event_to_send = Some(Event::SectionMerged(*self.our_prefix()));
+        Ok(complete_data)
+    }
+
     fn send_neighbour_infos(&mut self) {
         self.chain.other_prefixes().iter().for_each(|pfx| {
             let src = SrcLocation::Section(*self.our_prefix());
@@ -626,11 +915,21 @@ impl Elder {
                 Variant::MessageSignature(Box::new(msg)),
             );
         }
+
+        self.parsec_rejoins.clear();
     }
 
     fn create_genesis_updates(&self) -> Vec<(P2pNode, AccumulatingMessage)> {
+        // Elders which lost their parsec graph need the genesis of the new instance too.
+        let rejoining_elders = self
+            .parsec_rejoins
+            .iter()
+            .filter(|pub_id| self.chain.our_info().is_member(pub_id))
+            .filter_map(|pub_id| self.chain.get_member_p2p_node(pub_id.name()));
+
         self.chain
             .adults_and_infants_p2p_nodes()
+            .chain(rejoining_elders)
             .cloned()
             .filter_map(|recipient| {
                 let variant = Variant::GenesisUpdate(Box::new(self.gen_pfx_info.clone()));
@@ -668,7 +967,11 @@ impl Elder {
             return Err(RoutingError::InvalidSource);
         }
 
-        if let Some(msg) = self.sig_accumulator.add_proof(msg, &self.log_ident()) {
+        let timeout = self.chain.network_cfg().accumulation_timeout;
+        if let Some(msg) = self
+            .sig_accumulator
+            .add_proof(msg, timeout, &self.log_ident())
+        {
             self.handle_accumulated_message(msg)?
         }
 
@@ -730,7 +1033,12 @@ impl Elder {
         outbox: &mut dyn EventBox,
     ) -> Result<Transition, RoutingError> {
         match msg.variant {
-            Variant::UserMessage { .. } => (),
+            Variant::UserMessage { .. }
+            | Variant::UserRequest { .. }
+            | Variant::UserResponse { .. }
+            | Variant::UserMessageWithReceipt { .. }
+            | Variant::DeliveryReceipt(_)
+            | Variant::UserMessageToClient { .. } => (),
             _ => trace!("{} Got {:?}.", self, msg),
         }
 
@@ -740,7 +1048,7 @@ impl Elder {
                 let _: &Prefix<_> = msg.src.as_section()?;
                 let _: &Prefix<_> = msg.dst.as_prefix()?;
 
-                self.handle_neighbour_info(elders_info, msg.src, msg.dst)?;
+                self.handle_neighbour_info(elders_info, msg.version, msg.src, msg.dst)?;
             }
             Variant::UserMessage(content) => {
                 outbox.send_event(Event::MessageReceived {
@@ -749,6 +1057,59 @@ impl Elder {
                     dst: msg.dst,
                 });
             }
+            Variant::UserMessageWithReceipt { id, content, .. } => {
+                let src = msg.src.location();
+                outbox.send_event(Event::MessageReceived {
+                    content,
+                    src,
+                    dst: msg.dst,
+                });
+                self.send_delivery_receipt(id, src, msg.dst)?;
+            }
+            Variant::DeliveryReceipt(id) => {
+                let src = msg.src.location();
+                if let Some(dst) = self.pending_deliveries.handle_receipt(&id, &src) {
+                    outbox.send_event(Event::MessageDelivered { id, dst });
+                } else {
+                    debug!(
+                        "{} - Ignoring delivery receipt {:?} from {:?} - no matching message.",
+                        self, id, src
+                    );
+                }
+            }
+            Variant::UserRequest { id, content } => {
+                outbox.send_event(Event::RequestReceived {
+                    id,
+                    content,
+                    src: msg.src.location(),
+                    dst: msg.dst,
+                });
+            }
+            Variant::UserResponse { id, content } => {
+                let src = msg.src.location();
+                if self.pending_requests.handle_response(&id, &src) {
+                    outbox.send_event(Event::ResponseReceived {
+                        id,
+                        content,
+                        src,
+                        dst: msg.dst,
+                    });
+                } else {
+                    debug!(
+                        "{} - Ignoring response {:?} from {:?} - no matching request.",
+                        self, id, src
+                    );
+                }
+            }
+            Variant::UserMessageToClient { client, content } => {
+                let msg = Message {
+                    version: msg.version,
+                    dst: msg.dst,
+                    src: msg.src,
+                    variant: Variant::UserMessageToClient { client, content },
+                };
+                self.send_message_to_known_client(&client, msg);
+            }
             Variant::AckMessage {
                 src_prefix,
                 ack_version,
@@ -767,8 +1128,8 @@ impl Elder {
                     outbox,
                 );
             }
-            Variant::BootstrapRequest(name) => {
-                self.handle_bootstrap_request(msg.src.to_sender_node(sender)?, name)
+            Variant::BootstrapRequest { name, versions } => {
+                self.handle_bootstrap_request(msg.src.to_sender_node(sender)?, name, versions)
             }
             Variant::ConnectionResponse => {
                 self.handle_connection_response(*msg.src.as_node()?, outbox)
@@ -776,9 +1137,50 @@ impl Elder {
             Variant::JoinRequest(join_request) => {
                 self.handle_join_request(msg.src.to_sender_node(sender)?, *join_request)
             }
+            Variant::ResourceProofResponse { nonce, proof } => {
+                self.handle_resource_proof_response(*msg.src.as_node()?, nonce, proof)
+            }
             Variant::MemberKnowledge(payload) => {
                 self.handle_member_knowledge(msg.src.to_sender_node(sender)?, payload)
             }
+            Variant::Leave => self.handle_leave_notice(*msg.src.as_node()?),
+            Variant::ParsecRejoin => self.handle_parsec_rejoin(*msg.src.as_node()?),
+            Variant::SectionInfoRequest => self.handle_section_info_request(*msg.src.as_node()?),
+            Variant::SectionInfoResponse(elders_info) => {
+                if elders_info.prefix() == self.our_prefix() {
+                    let msg = Message {
+                        version: msg.version,
+                        dst: msg.dst,
+                        src: msg.src,
+                        variant: Variant::SectionInfoResponse(elders_info),
+                    };
+                    self.handle_signed_section_info(msg);
+                } else {
+                    self.handle_section_info_response(elders_info, &msg.src)
+                }
+            }
+            Variant::NetworkStats(stats) => match msg.dst {
+                DstLocation::Prefix(_) => {
+                    self.handle_neighbour_stats(stats, *msg.src.as_section()?)
+                }
+                _ => return Err(RoutingError::BadLocation),
+            },
+            Variant::Merge(request) => match msg.dst {
+                DstLocation::Prefix(_) => {
+                    self.handle_merge_request(*request, *msg.src.as_section()?)
+                }
+                _ => return Err(RoutingError::BadLocation),
+            },
+            Variant::MergeKey(key_info) => match msg.dst {
+                DstLocation::Prefix(_) => self.handle_merge_key(key_info, *msg.src.as_section()?),
+                _ => return Err(RoutingError::BadLocation),
+            },
+            Variant::MergeKeySignature(key_info, sig) => match msg.dst {
+                DstLocation::Prefix(_) => {
+                    self.handle_merge_key_signature(key_info, sig, *msg.src.as_section()?)
+                }
+                _ => return Err(RoutingError::BadLocation),
+            },
             Variant::ParsecRequest(version, request) => {
                 return self.handle_parsec_request(
                     version,
@@ -790,11 +1192,30 @@ impl Elder {
             Variant::ParsecResponse(version, response) => {
                 return self.handle_parsec_response(version, response, *msg.src.as_node()?, outbox);
             }
+            Variant::GenesisUpdate(ref info)
+                if self.chain.is_merging_into(info.first_info.prefix()) =>
+            {
+                let _: &Prefix<_> = msg.src.as_section()?;
+                info!(
+                    "{} - Absorbed by our sibling, demoting: {:?}",
+                    self, info.first_info
+                );
+                outbox.send_event(Event::SectionMerged(*info.first_info.prefix()));
+                return Ok(Transition::Demote {
+                    gen_pfx_info: (**info).clone(),
+                });
+            }
+            Variant::GenesisUpdate(ref info) if self.parsec_map.is_empty() => {
+                let _: &Prefix<_> = msg.src.as_section()?;
+                return Ok(self.handle_parsec_rejoin_genesis((**info).clone()));
+            }
             Variant::GenesisUpdate(_) | Variant::Relocate(_) => {
                 debug!("{} Unhandled message, adding to backlog: {:?}", self, msg);
                 self.msg_backlog.push(msg.into_queued(sender));
             }
-            Variant::BootstrapResponse(_) | Variant::NodeApproval(_) => {
+            Variant::BootstrapResponse(_)
+            | Variant::NodeApproval { .. }
+            | Variant::ResourceChallenge { .. } => {
                 debug!("{} Unhandled message, ignoring: {:?}", self, msg);
             }
         }
@@ -868,7 +1289,10 @@ impl Elder {
         };
 
         let src = SrcLocation::Section(*trimmed_info.first_info.prefix());
-        let variant = Variant::NodeApproval(Box::new(trimmed_info));
+        let variant = Variant::NodeApproval {
+            gen_pfx_info: Box::new(trimmed_info),
+            app_payload: self.join_config.approval_payload.clone(),
+        };
         if let Err(error) = self.send_routing_message(src, dst, variant, their_knowledge) {
             debug!(
                 "{} Failed sending NodeApproval to {}: {:?}",
@@ -890,15 +1314,42 @@ impl Elder {
     }
 
     // If this returns an error, the peer will be dropped.
-    fn handle_bootstrap_request(&mut self, p2p_node: P2pNode, name: XorName) {
+    fn handle_bootstrap_request(
+        &mut self,
+        p2p_node: P2pNode,
+        name: XorName,
+        versions: VersionRange,
+    ) {
         debug!(
             "{} - Received BootstrapRequest to section at {} from {:?}.",
             self, name, p2p_node
         );
 
+        if !self.check_protocol_version(&p2p_node, versions) {
+            return;
+        }
+
         self.respond_to_bootstrap_request(&p2p_node, &name);
     }
 
+    // Checks the peer speaks the protocol version our section agreed on, otherwise tells it so.
+    fn check_protocol_version(&mut self, p2p_node: &P2pNode, versions: VersionRange) -> bool {
+        let version = self.chain.protocol_version();
+        if versions.contains(version) {
+            return true;
+        }
+
+        debug!(
+            "{} - Refusing {} - it speaks protocol versions {}, we speak {}.",
+            self, p2p_node, versions, version
+        );
+        self.send_direct_message(
+            p2p_node.connection_info(),
+            Variant::BootstrapResponse(BootstrapResponse::UnsupportedVersion(version)),
+        );
+        false
+    }
+
     fn respond_to_bootstrap_request(&mut self, p2p_node: &P2pNode, name: &XorName) {
         let response = if self.our_prefix().matches(name) {
             let our_info = self.chain.our_info().clone();
@@ -934,6 +1385,24 @@ impl Elder {
             self, p2p_node, join_request.elders_version
         );
 
+        if join_request.network_params_digest != self.chain.network_cfg().consensus_digest() {
+            debug!(
+                "{} - Refusing JoinRequest from {} - network parameters don't match ours.",
+                self, p2p_node
+            );
+            self.send_direct_message(
+                p2p_node.connection_info(),
+                Variant::BootstrapResponse(BootstrapResponse::Rejected(
+                    "network parameters don't match ours".to_string(),
+                )),
+            );
+            return;
+        }
+
+        if !self.check_protocol_version(&p2p_node, join_request.versions) {
+            return;
+        }
+
         if join_request.elders_version < self.chain.our_info().version() {
             self.resend_bootstrap_response_join(&p2p_node);
         }
@@ -957,6 +1426,14 @@ impl Elder {
             return;
         }
 
+        if self.chain.is_evicted(pub_id.name()) {
+            debug!(
+                "{} - Ignoring JoinRequest from {} - evicted from our section.",
+                self, pub_id
+            );
+            return;
+        }
+
         if self.chain.is_in_online_backlog(&pub_id) {
             debug!(
                 "{} - Ignoring JoinRequest from {} - already in backlog.",
@@ -965,6 +1442,37 @@ impl Elder {
             return;
         }
 
+        if self.pending_challenges.contains_key(&pub_id) {
+            debug!(
+                "{} - Ignoring JoinRequest from {} - already challenged.",
+                self, pub_id
+            );
+            return;
+        }
+
+        if self.chain.merge_pending() {
+            let retry_after = self.chain.network_cfg().join_retry_delay;
+            debug!(
+                "{} - Refusing JoinRequest from {} - merging with our sibling, retry in {:?}.",
+                self, pub_id, retry_after
+            );
+            self.send_direct_message(
+                p2p_node.connection_info(),
+                Variant::BootstrapResponse(BootstrapResponse::RetryLater(retry_after)),
+            );
+            return;
+        }
+
+        if join_request.app_payload.len() > MAX_APP_PAYLOAD_SIZE {
+            debug!(
+                "{} - Ignoring JoinRequest from {} - application payload too large.",
+                self, pub_id
+            );
+            return;
+        }
+
+        let is_relocating = join_request.relocate_payload.is_some();
+
         // This joining node is being relocated to us.
         let (age, their_knowledge) = if let Some(payload) = join_request.relocate_payload {
             if !payload.verify_identity(&pub_id) {
@@ -990,20 +1498,149 @@ impl Elder {
             }
 
             if !self.check_signed_relocation_details(&payload.details) {
+                // The old id signed the new one, so its owner forged the relocation.
+                let old_pub_id = details.pub_id;
+                if self.chain.is_peer_our_member(&old_pub_id) {
+                    self.vote_for_misbehaviour(old_pub_id, Misbehaviour::ForgedRelocation);
+                }
                 return;
             }
 
             (details.age, Some(details.destination_key_info.version()))
         } else {
+            if let Err(retry_after) = self.admit_new_node(&p2p_node) {
+                debug!(
+                    "{} - Refusing JoinRequest from {} - retry in {:?}.",
+                    self, pub_id, retry_after
+                );
+                self.send_direct_message(
+                    p2p_node.connection_info(),
+                    Variant::BootstrapResponse(BootstrapResponse::RetryLater(retry_after)),
+                );
+                return;
+            }
+
             (MIN_AGE, None)
         };
 
-        self.send_direct_message(p2p_node.connection_info(), Variant::ConnectionResponse);
-        self.vote_for_event(AccumulatingEvent::Online(OnlinePayload {
+        match self
+            .join_config
+            .policy
+            .decide(&p2p_node, is_relocating, &join_request.app_payload)
+        {
+            JoinDecision::Accept => (),
+            JoinDecision::Reject(reason) => {
+                debug!(
+                    "{} - Rejecting JoinRequest from {} - {}.",
+                    self, pub_id, reason
+                );
+                self.send_direct_message(
+                    p2p_node.connection_info(),
+                    Variant::BootstrapResponse(BootstrapResponse::Rejected(reason)),
+                );
+                return;
+            }
+            JoinDecision::Defer => {
+                let retry_after = self.chain.network_cfg().join_retry_delay;
+                debug!(
+                    "{} - Deferring JoinRequest from {} - retry in {:?}.",
+                    self, pub_id, retry_after
+                );
+                self.send_direct_message(
+                    p2p_node.connection_info(),
+                    Variant::BootstrapResponse(BootstrapResponse::RetryLater(retry_after)),
+                );
+                return;
+            }
+        }
+
+        self.send_resource_challenge(OnlinePayload {
             p2p_node,
             age,
             their_knowledge,
-        }))
+            versions: join_request.versions,
+        })
+    }
+
+    // Applies our admission policy to a node joining the network for the first time. Relocated
+    // nodes are always admitted.
+    fn admit_new_node(&mut self, p2p_node: &P2pNode) -> Result<(), Duration> {
+        let (infants, adults) = self.chain.our_infant_and_adult_counts();
+        let joining = self.pending_challenges.len() + self.chain.online_backlog_len();
+        let load = SectionLoad {
+            joining,
+            infants,
+            adults,
+        };
+
+        self.join_admission
+            .admit(p2p_node.peer_addr().ip(), load, &self.chain.network_cfg())
+    }
+
+    // Challenges the joining peer to prove it has the required resources. We only vote for it to
+    // go online once it sent a valid proof. All our elders send the same challenge, so the peer
+    // only needs to solve it once.
+    fn send_resource_challenge(&mut self, payload: OnlinePayload) {
+        let network_cfg = self.chain.network_cfg();
+        let nonce = resource_challenge_nonce(payload.p2p_node.name(), self.chain.our_info());
+        let timer_token = self.timer.schedule(network_cfg.resource_proof_timeout);
+
+        debug!(
+            "{} - Sending ResourceChallenge to {}.",
+            self, payload.p2p_node
+        );
+        self.send_direct_message(
+            payload.p2p_node.connection_info(),
+            Variant::ResourceChallenge {
+                target_size: network_cfg.resource_proof_target_size,
+                difficulty: network_cfg.resource_proof_difficulty,
+                nonce,
+            },
+        );
+
+        let _ = self.pending_challenges.insert(
+            *payload.p2p_node.public_id(),
+            PendingChallenge {
+                nonce,
+                payload,
+                timer_token,
+            },
+        );
+    }
+
+    fn handle_resource_proof_response(&mut self, pub_id: PublicId, nonce: [u8; 32], proof: u64) {
+        let challenge = if let Some(challenge) = self.pending_challenges.remove(&pub_id) {
+            challenge
+        } else {
+            debug!(
+                "{} - Ignoring ResourceProofResponse from {} - not challenged.",
+                self, pub_id
+            );
+            return;
+        };
+
+        let network_cfg = self.chain.network_cfg();
+        let resource_proof = ResourceProof::new(
+            network_cfg.resource_proof_target_size,
+            network_cfg.resource_proof_difficulty,
+        );
+        if challenge.nonce != nonce || !resource_proof.validate_proof(&nonce, proof) {
+            debug!(
+                "{} - Rejecting joining peer {} - invalid resource proof.",
+                self, pub_id
+            );
+            self.send_direct_message(
+                challenge.payload.p2p_node.connection_info(),
+                Variant::BootstrapResponse(BootstrapResponse::Rejected(
+                    "invalid resource proof".to_string(),
+                )),
+            );
+            return;
+        }
+
+        let p2p_node = &challenge.payload.p2p_node;
+        self.send_direct_message(p2p_node.connection_info(), Variant::ConnectionResponse);
+        self.vote_for_event(AccumulatingEvent::Online(challenge.payload))
     }
 
     fn update_our_knowledge(&mut self, msg: &Message) {
@@ -1024,20 +1661,229 @@ impl Elder {
         }
     }
 
-    fn handle_neighbour_info(
-        &mut self,
-        elders_info: EldersInfo,
-        src: SrcAuthority,
-        dst: DstLocation,
-    ) -> Result<()> {
-        if self.chain.is_new_neighbour(&elders_info) {
-            let _ = self
-                .pending_voted_msgs
-                .entry(PendingMessageKey::NeighbourInfo {
-                    version: elders_info.version(),
-                    prefix: *elders_info.prefix(),
+    // Asks another section for its elders, to complete or refresh our section map.
+    fn send_section_info_request(&mut self) {
+        let name = if let Some(name) = self.chain.section_map_query_target(&mut self.rng) {
+            name
+        } else {
+            return;
+        };
+
+        let src = SrcLocation::Node(*self.id());
+        let dst = DstLocation::Section(name);
+        if let Err(error) = self.send_routing_message(src, dst, Variant::SectionInfoRequest, None) {
+            debug!(
+                "{} - Failed to send SectionInfoRequest to {:?}: {:?}",
+                self, dst, error
+            );
+        }
+    }
+
+    fn handle_section_info_request(&mut self, requester: PublicId) {
+        let src = SrcLocation::Section(*self.our_prefix());
+        let dst = DstLocation::Node(*requester.name());
+        let variant = Variant::SectionInfoResponse(self.chain.our_info().clone());
+        // Our members pass the response on to their clients, which may only trust the start of
+        // our history.
+        let node_knowledge_override = if self.our_prefix().matches(requester.name()) {
+            Some(0)
+        } else {
+            None
+        };
+        if let Err(error) = self.send_routing_message(src, dst, variant, node_knowledge_override) {
+            debug!(
+                "{} - Failed to send SectionInfoResponse to {}: {:?}",
+                self, requester, error
+            );
+        }
+    }
+
+    fn handle_section_info_response(&mut self, elders_info: EldersInfo, src: &SrcAuthority) {
+        let proof_key_info = match src {
+            SrcAuthority::Section { prefix, proof, .. } if prefix == elders_info.prefix() => {
+                proof.last_new_key_info()
+            }
+            _ => {
+                debug!(
+                    "{} - Ignoring SectionInfoResponse not sent by the section itself: {:?}",
+                    self, elders_info
+                );
+                return;
+            }
+        };
+
+        if self
+            .chain
+            .handle_section_info_response(elders_info, proof_key_info)
+        {
+            trace!("{} - Section map updated.", self);
+        }
+    }
+
+    // Sends the statistics our section just agreed on to our neighbours.
+    fn send_network_stats(&mut self, stats: &NetworkStats) {
+        for prefix in self.chain.other_prefixes() {
+            let src = SrcLocation::Section(*self.our_prefix());
+            let dst = DstLocation::Prefix(prefix);
+            let variant = Variant::NetworkStats(stats.clone());
+
+            if let Err(err) = self.send_routing_message(src, dst, variant, None) {
+                debug!("{} Failed to send NetworkStats: {:?}.", self, err);
+            }
+        }
+    }
+
+    // Sends our merge request to our sibling.
+    fn send_merge_request(&mut self) {
+        let src = SrcLocation::Section(*self.our_prefix());
+        let dst = DstLocation::Prefix(self.our_prefix().sibling());
+        let variant = Variant::Merge(Box::new(self.chain.merge_request()));
+
+        if let Err(err) = self.send_routing_message(src, dst, variant, None) {
+            debug!("{} Failed to send Merge: {:?}.", self, err);
+        }
+    }
+
+    // Proposes to merge with our sibling if we are below the threshold, withdraws our proposal if
+    // we grew back to it, or tells our sibling about our new members if we already agreed to.
+    fn update_merge(&mut self) {
+        if self.chain.should_propose_merge() {
+            self.vote_for_event(AccumulatingEvent::ProposeMerge);
+        } else if self.chain.should_withdraw_merge() {
+            self.vote_for_event(AccumulatingEvent::WithdrawMerge);
+        } else if self.chain.merge_proposed() {
+            self.send_merge_request();
+        }
+    }
+
+    fn handle_merge_request(&mut self, request: MergeRequest, src_prefix: Prefix<XorName>) {
+        if *request.elders_info.prefix() != src_prefix {
+            debug!(
+                "{} - Ignoring Merge of {:?} sent by {:?}.",
+                self,
+                request.elders_info.prefix(),
+                src_prefix
+            );
+            return;
+        }
+
+        if self.chain.is_new_sibling_merge(&request) {
+            self.vote_for_event(AccumulatingEvent::SiblingMerge(request));
+        } else {
+            trace!(
+                "{} - Ignoring outdated or unexpected Merge from {:?}.",
+                self,
+                src_prefix
+            );
+        }
+    }
+
+    // Asks the sibling we absorb to sign the key of the merged section.
+    fn send_merge_key(&mut self) {
+        let key_info = match &self.merge_dkg_result {
+            Some((info, key)) => SectionKeyInfo::from_elders_info(info, *key),
+            None => return,
+        };
+        let src = SrcLocation::Section(*self.our_prefix());
+        let dst = DstLocation::Prefix(self.our_prefix().sibling());
+        let variant = Variant::MergeKey(key_info);
+
+        if let Err(err) = self.send_routing_message(src, dst, variant, None) {
+            debug!("{} Failed to send MergeKey: {:?}.", self, err);
+        }
+    }
+
+    fn handle_merge_key(&mut self, key_info: SectionKeyInfo, src_prefix: Prefix<XorName>) {
+        let our_prefix = *self.our_prefix();
+        if our_prefix.bit_count() == 0
+            || src_prefix != our_prefix.sibling()
+            || *key_info.prefix() != our_prefix.popped()
+            || !self.chain.is_being_absorbed()
+        {
+            debug!(
+                "{} - Ignoring unexpected MergeKey {:?} from {:?}.",
+                self, key_info, src_prefix
+            );
+            return;
+        }
+
+        let signature_payload = match self
+            .chain
+            .our_section_bls_secret_key_share()
+            .and_then(|share| EventSigPayload::new_for_section_key_info(&share.key, &key_info))
+        {
+            Ok(payload) => payload,
+            Err(err) => {
+                debug!("{} - Failed to sign MergeKey: {:?}.", self, err);
+                return;
+            }
+        };
+        let event = AccumulatingEvent::SignMergeKey(key_info)
+            .into_network_event_with(Some(signature_payload));
+        self.vote_for_network_event(event);
+    }
+
+    fn handle_merge_key_signature(
+        &mut self,
+        key_info: SectionKeyInfo,
+        sig: bls::Signature,
+        src_prefix: Prefix<XorName>,
+    ) {
+        let is_expected = self.merge_dkg_result.as_ref().map_or(false, |(info, key)| {
+            SectionKeyInfo::from_elders_info(info, *key) == key_info
+        });
+        if self.our_prefix().bit_count() == 0
+            || src_prefix != self.our_prefix().sibling()
+            || !is_expected
+            || !self.chain.is_valid_merge_key_signature(&key_info, &sig)
+        {
+            debug!(
+                "{} - Ignoring unexpected MergeKeySignature {:?} from {:?}.",
+                self, key_info, src_prefix
+            );
+            return;
+        }
+
+        self.vote_for_event(AccumulatingEvent::MergeKeySignature(key_info, sig));
+    }
+
+    fn handle_neighbour_stats(&mut self, stats: NetworkStats, src_prefix: Prefix<XorName>) {
+        if stats.section.prefix != src_prefix {
+            debug!(
+                "{} - Ignoring NetworkStats of {:?} sent by {:?}.",
+                self, stats.section.prefix, src_prefix
+            );
+            return;
+        }
+
+        if self.chain.is_new_neighbour_stats(&stats) {
+            self.vote_for_event(AccumulatingEvent::NeighbourStats(stats));
+        } else {
+            trace!(
+                "{} Ignore not new NetworkStats of {:?}, round {}.",
+                self,
+                stats.section.prefix,
+                stats.round
+            );
+        }
+    }
+
+    fn handle_neighbour_info(
+        &mut self,
+        elders_info: EldersInfo,
+        version: u16,
+        src: SrcAuthority,
+        dst: DstLocation,
+    ) -> Result<()> {
+        if self.chain.is_new_neighbour(&elders_info) {
+            let _ = self
+                .pending_voted_msgs
+                .entry(PendingMessageKey::NeighbourInfo {
+                    version: elders_info.version(),
+                    prefix: *elders_info.prefix(),
                 })
                 .or_insert_with(|| Message {
+                    version,
                     src,
                     dst,
                     variant: Variant::NeighbourInfo(elders_info.clone()),
@@ -1065,6 +1911,21 @@ impl Elder {
         self.vote_for_network_event(event.into_network_event())
     }
 
+    // Votes to switch to the newest protocol version all our members speak, if it is newer than
+    // the one our section currently uses.
+    fn vote_for_protocol_upgrade(&mut self) {
+        let version = match self.chain.newest_common_protocol_version() {
+            Some(version) if version > self.chain.protocol_version() => version,
+            _ => return,
+        };
+
+        info!(
+            "{} - Voting to switch to protocol version {}.",
+            self, version
+        );
+        self.vote_for_event(AccumulatingEvent::ProtocolVersion(version));
+    }
+
     fn vote_for_relocate(&mut self, details: RelocateDetails) {
         self.vote_for_network_event(details.into_accumulating_event().into_network_event())
     }
@@ -1127,7 +1988,7 @@ impl Elder {
 
         // If the source is single, we don't even need to send signatures, so let's cut this short
         if src.is_single() {
-            let msg = Message::single_src(&self.full_id, dst, variant)?;
+            let msg = Message::single_src(&self.full_id, self.protocol_version(), dst, variant)?;
             let msg = MessageWithBytes::new(msg, &log_ident)?;
             return self.handle_accumulated_message(msg);
         }
@@ -1137,10 +1998,11 @@ impl Elder {
 
         for target in self.get_signature_targets(&dst) {
             if target.name() == self.name() {
-                if let Some(msg) = self
-                    .sig_accumulator
-                    .add_proof(accumulating_msg.clone(), &log_ident)
-                {
+                if let Some(msg) = self.sig_accumulator.add_proof(
+                    accumulating_msg.clone(),
+                    self.chain.network_cfg().accumulation_timeout,
+                    &log_ident,
+                ) {
                     self.handle_accumulated_message(msg)?;
                 }
             } else {
@@ -1175,7 +2037,18 @@ impl Elder {
         let (target_p2p_nodes, dg_size) = if let Some(target) = single_target {
             (vec![target.clone()], 1)
         } else {
-            self.get_targets(dst)?
+            let (mut targets, dg_size) = self.get_targets(dst)?;
+
+            // Re-sent messages awaiting a delivery receipt go through a different delivery group
+            // than the previous attempts.
+            if let Some(attempt) = msg.delivery_attempt() {
+                if !targets.is_empty() {
+                    let offset = (usize::from(attempt) * dg_size) % targets.len();
+                    targets.rotate_left(offset);
+                }
+            }
+
+            (targets, dg_size)
         };
 
         trace!(
@@ -1204,11 +2077,77 @@ impl Elder {
         Ok(())
     }
 
+    // Sends the given attempt of a message and waits for its delivery receipt.
+    fn send_message_with_receipt(
+        &mut self,
+        src: SrcLocation,
+        dst: DstLocation,
+        id: MessageId,
+        content: Vec<u8>,
+        attempt: u8,
+    ) -> Result<()> {
+        let variant = Variant::UserMessageWithReceipt {
+            id,
+            attempt,
+            content: content.clone(),
+        };
+        self.send_routing_message(src, dst, variant, None)?;
+
+        let timer_token = self
+            .timer
+            .schedule(self.chain.network_cfg().delivery_receipt_timeout);
+        self.pending_deliveries
+            .insert(id, src, dst, content, attempt, timer_token);
+
+        Ok(())
+    }
+
+    // Confirms the delivery of a message from `src` to `dst` back to its source. Messages to a
+    // section are confirmed by the section, so the receipt is signed by the section too.
+    fn send_delivery_receipt(
+        &mut self,
+        id: MessageId,
+        src: SrcLocation,
+        dst: DstLocation,
+    ) -> Result<()> {
+        let receipt_src = if dst.is_single() {
+            SrcLocation::Node(*self.id())
+        } else {
+            SrcLocation::Section(*self.our_prefix())
+        };
+        let receipt_dst = match src {
+            SrcLocation::Node(public_id) => DstLocation::Node(*public_id.name()),
+            SrcLocation::Section(prefix) => DstLocation::Prefix(prefix),
+        };
+
+        self.send_routing_message(receipt_src, receipt_dst, Variant::DeliveryReceipt(id), None)
+    }
+
     /// Vote for a user-defined event.
     pub fn vote_for_user_event(&mut self, event: Vec<u8>) {
         self.vote_for_event(AccumulatingEvent::User(event));
     }
 
+    /// Vote for the load metrics of our section, consulted by the split policy. The values are
+    /// quantised first, so elders measuring slightly different loads still agree.
+    pub fn vote_for_load_metrics(&mut self, metrics: LoadMetrics) {
+        self.vote_for_event(AccumulatingEvent::LoadMetrics(metrics.quantised()));
+    }
+
+    /// Vote to record a misbehaviour of one of our members.
+    pub fn report_misbehaviour(
+        &mut self,
+        offender: PublicId,
+        kind: Misbehaviour,
+    ) -> Result<(), RoutingError> {
+        if !self.chain.is_peer_our_member(&offender) {
+            return Err(RoutingError::PeerNotFound(*offender.name()));
+        }
+
+        self.vote_for_misbehaviour(offender, kind);
+        Ok(())
+    }
+
     /// Returns the set of peers that are responsible for collecting signatures to verify a message;
     /// this may contain us or only other nodes. If our signature is not required, this returns
     /// `None`.
@@ -1277,6 +2216,7 @@ impl Elder {
         let secret_key = self.chain.our_section_bls_secret_key_share()?;
 
         let content = PlainMessage {
+            version: self.protocol_version(),
             src: *self.our_prefix(),
             dst,
             variant,
@@ -1329,6 +2269,33 @@ impl Base for Elder {
         self.chain.closest_names(&name, count, &conn_peers)
     }
 
+    fn our_section_info(&self) -> Option<(&EldersInfo, &SectionProofChain)> {
+        Some((self.chain.our_info(), self.chain.our_history()))
+    }
+
+    fn signed_section_info(&self) -> Option<&Message> {
+        self.signed_section_info.as_ref()
+    }
+
+    fn set_signed_section_info(&mut self, msg: Message) {
+        self.signed_section_info = Some(msg);
+    }
+
+    fn request_signed_section_info(&mut self) {
+        let src = SrcLocation::Node(*self.name());
+        let dst = DstLocation::Section(*self.name());
+        if let Err(error) = self.send_routing_message(src, dst, Variant::SectionInfoRequest, None) {
+            debug!(
+                "{} - Failed to send SectionInfoRequest to our section: {:?}",
+                self, error
+            );
+        }
+    }
+
+    fn protocol_version(&self) -> u16 {
+        self.chain.protocol_version()
+    }
+
     fn peer_map(&self) -> &PeerMap {
         &self.network_service().peer_map
     }
@@ -1341,6 +2308,10 @@ impl Base for Elder {
         &mut self.timer
     }
 
+    fn join_config_mut(&mut self) -> &mut JoinConfig {
+        &mut self.join_config
+    }
+
     fn rng(&mut self) -> &mut MainRng {
         &mut self.rng
     }
@@ -1371,9 +2342,71 @@ impl Base for Elder {
         self.send_routing_message(src, dst, Variant::UserMessage(content), None)
     }
 
+    fn handle_send_message_with_receipt(
+        &mut self,
+        src: SrcLocation,
+        dst: DstLocation,
+        id: MessageId,
+        content: Vec<u8>,
+    ) -> Result<(), RoutingError> {
+        if let DstLocation::Direct = dst {
+            return Err(RoutingError::BadLocation);
+        }
+
+        self.send_message_with_receipt(src, dst, id, content, 0)
+    }
+
+    fn handle_send_request(
+        &mut self,
+        src: SrcLocation,
+        dst: DstLocation,
+        id: MessageId,
+        content: Vec<u8>,
+        timeout: Duration,
+    ) -> Result<(), RoutingError> {
+        if let DstLocation::Direct = dst {
+            return Err(RoutingError::BadLocation);
+        }
+
+        self.send_routing_message(src, dst, Variant::UserRequest { id, content }, None)?;
+
+        let timer_token = self.timer.schedule(timeout);
+        self.pending_requests.insert(id, dst, timer_token);
+
+        Ok(())
+    }
+
+    fn handle_send_response(
+        &mut self,
+        src: SrcLocation,
+        dst: DstLocation,
+        id: MessageId,
+        content: Vec<u8>,
+    ) -> Result<(), RoutingError> {
+        if let DstLocation::Direct = dst {
+            return Err(RoutingError::BadLocation);
+        }
+
+        self.send_routing_message(src, dst, Variant::UserResponse { id, content }, None)
+    }
+
+    fn handle_send_message_to_client_from_section(
+        &mut self,
+        proxy: XorName,
+        client: XorName,
+        content: Vec<u8>,
+    ) -> Result<(), RoutingError> {
+        let src = SrcLocation::Section(*self.our_prefix());
+        let variant = Variant::UserMessageToClient { client, content };
+
+        // We don't know which of our keys the client trusts, so prove our key all the way from
+        // the start of our history.
+        self.send_routing_message(src, DstLocation::Node(proxy), variant, Some(0))
+    }
+
     fn handle_timeout(&mut self, token: u64, outbox: &mut dyn EventBox) -> Transition {
         if self.gossip_timer_token == token {
-            self.gossip_timer_token = self.timer.schedule(GOSSIP_TIMEOUT);
+            self.gossip_timer_token = self.timer.schedule(self.chain.network_cfg().gossip_timeout);
 
             // If we're the only node then invoke parsec_poll directly
             if self.chain.our_info().len() == 1 {
@@ -1382,6 +2415,65 @@ impl Base for Elder {
 
             self.send_parsec_gossip(None);
             self.maintain_parsec();
+        } else if self.section_map_timer_token == Some(token) {
+            self.section_map_timer_token = Some(
+                self.timer
+                    .schedule(self.chain.network_cfg().section_map_query_interval),
+            );
+            self.send_section_info_request();
+        } else if self.stats_timer_token == Some(token) {
+            self.stats_timer_token = self
+                .chain
+                .network_cfg()
+                .network_stats_interval
+                .map(|interval| self.timer.schedule(interval));
+            self.vote_for_event(AccumulatingEvent::StatsRound(self.chain.next_stats_round()));
+        } else if let Some((id, dst)) = self.pending_requests.handle_timeout(token) {
+            outbox.send_event(Event::RequestTimedOut { id, dst });
+        } else if let Some((id, delivery)) = self.pending_deliveries.handle_timeout(token) {
+            let attempt = delivery.attempt + 1;
+            if attempt < self.chain.network_cfg().max_delivery_attempts {
+                debug!(
+                    "{} - No delivery receipt for {:?} - re-sending (attempt {}).",
+                    self, id, attempt
+                );
+                if let Err(error) = self.send_message_with_receipt(
+                    delivery.src,
+                    delivery.dst,
+                    id,
+                    delivery.content,
+                    attempt,
+                ) {
+                    debug!("{} - Failed to re-send {:?}: {:?}", self, id, error);
+                    outbox.send_event(Event::MessageDeliveryFailed {
+                        id,
+                        dst: delivery.dst,
+                    });
+                }
+            } else {
+                outbox.send_event(Event::MessageDeliveryFailed {
+                    id,
+                    dst: delivery.dst,
+                });
+            }
+        } else if let Some(pub_id) = self
+            .pending_challenges
+            .iter()
+            .find(|(_, challenge)| challenge.timer_token == token)
+            .map(|(pub_id, _)| *pub_id)
+        {
+            debug!(
+                "{} - Rejecting joining peer {} - resource proof timed out.",
+                self, pub_id
+            );
+            if let Some(challenge) = self.pending_challenges.remove(&pub_id) {
+                self.send_direct_message(
+                    challenge.payload.p2p_node.connection_info(),
+                    Variant::BootstrapResponse(BootstrapResponse::Rejected(
+                        "resource proof timed out".to_string(),
+                    )),
+                );
+            }
         }
 
         Transition::Stay
@@ -1399,6 +2491,36 @@ impl Base for Elder {
         Transition::Stay
     }
 
+    fn handle_leave(&mut self, outbox: &mut dyn EventBox) -> Result<Transition, RoutingError> {
+        if self.leaving {
+            return Ok(Transition::Stay);
+        }
+
+        if self.chain.our_joined_members().count() <= 1 {
+            info!("{} - Leaving the network - no one to hand over to.", self);
+            outbox.send_event(Event::Left);
+            return Ok(Transition::Terminate);
+        }
+
+        info!("{} - Leaving the network.", self);
+        self.leaving = true;
+
+        let our_id = *self.id();
+        let targets: Vec<_> = self
+            .chain
+            .our_elders()
+            .filter(|p2p_node| p2p_node.public_id() != &our_id)
+            .map(|p2p_node| p2p_node.connection_info().clone())
+            .collect();
+        for conn_info in targets {
+            self.send_direct_message(&conn_info, Variant::Leave);
+        }
+
+        // We terminate once the section handed our elder role over to someone else.
+        self.vote_for_event(AccumulatingEvent::Offline(our_id));
+        Ok(Transition::Stay)
+    }
+
     fn handle_peer_lost(&mut self, peer_addr: SocketAddr, outbox: &mut dyn EventBox) -> Transition {
         debug!("{} - Lost peer {}", self, peer_addr);
 
@@ -1462,6 +2584,52 @@ impl Base for Elder {
             error
         })
     }
+
+    fn count_incoming_message(&mut self, src_addr: &SocketAddr, msg: &MessageWithBytes) {
+        let pub_id = match msg.message_src() {
+            Ok(SrcAuthority::Node { public_id, .. }) => public_id,
+            Ok(SrcAuthority::Section { .. }) | Err(_) => return,
+        };
+
+        // Only count the messages a member sent itself, not the ones it relays for others.
+        let is_sent_by_member = self.chain.is_peer_our_member(&pub_id)
+            && self
+                .chain
+                .get_member_p2p_node(pub_id.name())
+                .map_or(false, |p2p_node| p2p_node.peer_addr() == src_addr);
+        if !is_sent_by_member {
+            return;
+        }
+
+        let network_cfg = self.chain.network_cfg();
+        if self.rate_limiter.count(*pub_id.name(), &network_cfg) {
+            debug!("{} - {} exceeded the message rate limit.", self, pub_id);
+            self.vote_for_misbehaviour(pub_id, Misbehaviour::Spam);
+        }
+    }
+
+    fn handle_unverified_message(
+        &mut self,
+        sender: Option<&ConnectionInfo>,
+        msg: &Message,
+        error: &RoutingError,
+    ) {
+        let pub_id = match (&msg.src, error) {
+            (SrcAuthority::Node { public_id, .. }, RoutingError::FailedSignature) => *public_id,
+            _ => return,
+        };
+
+        // Anyone can claim to be the signer, so only blame our member if it sent the message to
+        // us itself.
+        let is_from_signer = self
+            .chain
+            .get_member_p2p_node(pub_id.name())
+            .map(|p2p_node| sender.map(|sender| sender.peer_addr) == Some(*p2p_node.peer_addr()))
+            .unwrap_or(false);
+        if is_from_signer && self.chain.is_peer_our_member(&pub_id) {
+            self.vote_for_misbehaviour(pub_id, Misbehaviour::InvalidSignature);
+        }
+    }
 }
 
 #[cfg(feature = "mock_base")]
@@ -1559,6 +2727,8 @@ impl Approved for Elder {
     ) -> Result<(), RoutingError> {
         self.handle_candidate_approval(payload.p2p_node, payload.their_knowledge, outbox);
         self.print_rt_size();
+        self.vote_for_protocol_upgrade();
+        self.update_merge();
         Ok(())
     }
 
@@ -1568,6 +2738,8 @@ impl Approved for Elder {
         _outbox: &mut dyn EventBox,
     ) -> Result<(), RoutingError> {
         let _ = self.members_knowledge.remove(pub_id.name());
+        self.vote_for_protocol_upgrade();
+        self.update_merge();
         Ok(())
     }
 
@@ -1578,6 +2750,7 @@ impl Approved for Elder {
         _outbox: &mut dyn EventBox,
     ) -> Result<(), RoutingError> {
         let _ = self.members_knowledge.remove(details.pub_id.name());
+        self.update_merge();
 
         if &details.pub_id == self.id() {
             // Do not send the message to ourselves.
@@ -1609,7 +2782,14 @@ impl Approved for Elder {
     ) -> Result<(), RoutingError> {
         if let Some(info) = self.dkg_cache.remove(participants) {
             info!("{} - handle DkgResult: {:?}", self, participants);
-            self.vote_for_section_info(info, dkg_result.0.public_key_set.public_key())?;
+            let key = dkg_result.0.public_key_set.public_key();
+            if self.our_prefix().is_extension_of(info.prefix()) {
+                // Our sibling needs to sign the key first, to link its history to ours.
+                self.merge_dkg_result = Some((info, key));
+                self.send_merge_key();
+            } else {
+                self.vote_for_section_info(info, key)?;
+            }
         } else {
             log_or_panic!(
                 LogLevel::Error,
@@ -1622,6 +2802,72 @@ impl Approved for Elder {
         Ok(())
     }
 
+    fn handle_stats_round_event(&mut self, outbox: &mut dyn EventBox) {
+        if let Some(stats) = self.chain.network_stats().cloned() {
+            info!(
+                "{} - Agreed on network stats, round {}: {:?}",
+                self,
+                stats.round,
+                stats.network_summary()
+            );
+            self.send_network_stats(&stats);
+            outbox.send_event(Event::NetworkStats(stats));
+        }
+    }
+
+    fn handle_propose_merge_event(&mut self) {
+        info!(
+            "{} - Agreed to merge with our sibling {:?}.",
+            self,
+            self.our_prefix().sibling()
+        );
+        self.send_merge_request();
+    }
+
+    fn handle_withdraw_merge_event(&mut self) {
+        info!(
+            "{} - Withdrew our proposal to merge with our sibling {:?}.",
+            self,
+            self.our_prefix().sibling()
+        );
+        self.send_merge_request();
+    }
+
+    fn handle_sibling_merge_event(&mut self) {
+        if self.chain.is_being_absorbed() {
+            self.send_merge_request();
+        } else {
+            // Our sibling may have missed our key while it withdrew its proposal.
+            self.send_merge_key();
+        }
+    }
+
+    fn handle_sign_merge_key_event(&mut self) {
+        let (key_info, sig) = match self.chain.merge_key_signature() {
+            Some((key_info, sig)) => (key_info.clone(), sig.clone()),
+            None => return,
+        };
+        info!(
+            "{} - Signed the key of our sibling absorbing us: {:?}",
+            self, key_info
+        );
+
+        let src = SrcLocation::Section(*self.our_prefix());
+        let dst = DstLocation::Prefix(self.our_prefix().sibling());
+        let variant = Variant::MergeKeySignature(key_info, sig);
+
+        if let Err(err) = self.send_routing_message(src, dst, variant, None) {
+            debug!("{} Failed to send MergeKeySignature: {:?}.", self, err);
+        }
+    }
+
+    fn handle_merge_key_signature_event(&mut self) -> Result<(), RoutingError> {
+        if let Some((info, key)) = self.merge_dkg_result.take() {
+            self.vote_for_section_info(info, key)?;
+        }
+        Ok(())
+    }
+
     fn handle_prune_event(&mut self) -> Result<(), RoutingError> {
         if self.chain.split_in_progress() {
             log_or_panic!(
@@ -1660,14 +2906,17 @@ impl Approved for Elder {
         let complete_data = if info_prefix.is_extension_of(&old_pfx) {
             self.prepare_finalise_split()?
         } else if old_pfx.is_extension_of(&info_prefix) {
-            panic!(
-                "{} - Merge not supported: {:?} -> {:?}",
-                self, old_pfx, info_prefix,
-            );
+            self.prepare_finalise_merge()?
         } else {
             self.prepare_reset_parsec()?
         };
 
+        if !is_member && self.leaving {
+            info!("{} - Handed our elder role over. Leaving.", self);
+            self.send_event(Event::Left, outbox);
+            return Ok(Transition::Terminate);
+        }
+
         if !is_member {
             // Demote after the parsec reset, i.e genesis prefix info is for the new parsec,
             // i.e the one that would be received with NodeApproval.
@@ -1692,6 +2941,7 @@ impl Approved for Elder {
         });
 
         self.print_rt_size();
+        self.update_merge();
         if let Some(to_send) = complete_data.event_to_send {
             self.send_event(to_send, outbox);
         }
@@ -1760,6 +3010,14 @@ impl Display for Elder {
     }
 }
 
+// Nonce of the resource proof challenge of the joining peer with the given name. It only depends on
+// what all the elders of the section agree on, so they all send the same challenge.
+fn resource_challenge_nonce(name: &XorName, elders_info: &EldersInfo) -> [u8; 32] {
+    let mut bytes = name.0.to_vec();
+    bytes.extend_from_slice(elders_info.hash());
+    crypto::sha3_256(&bytes)
+}
+
 // Create `EldersInfo` for the first node.
 fn create_first_elders_info(p2p_node: P2pNode) -> Result<EldersInfo, RoutingError> {
     let name = *p2p_node.name();
@@ -20,7 +20,7 @@ use crate::{
     messages::Variant,
     rng::{self, MainRng},
     state_machine::Transition,
-    unwrap, utils, ELDER_SIZE,
+    unwrap, utils, ELDER_SIZE, PROTOCOL_VERSION,
 };
 use mock_quic_p2p::Network;
 use std::{iter, net::SocketAddr};
@@ -156,6 +156,7 @@ impl ElderUnderTest {
         let request = parsec::Request::new();
         let message = unwrap!(Message::single_src(
             other_full_id,
+            PROTOCOL_VERSION,
             DstLocation::Direct,
             Variant::ParsecRequest(parsec_version, request)
         ));
@@ -182,6 +183,7 @@ impl ElderUnderTest {
                 p2p_node,
                 age: MIN_AGE,
                 their_knowledge: None,
+                versions: VersionRange::OURS,
             })),
         );
     }
@@ -408,6 +410,7 @@ fn new_elder_state(
         parsec_map,
         msg_filter: RoutingMessageFilter::new(),
         timer: test_utils::create_timer(),
+        join_config: Default::default(),
         rng: rng::new_from(rng),
     };
 
@@ -561,6 +564,64 @@ fn send_genesis_update() {
     verify_proof_chain_does_not_contain(proof, orig_elders_version);
 }
 
+#[test]
+fn relayed_messages_do_not_count_as_spam() {
+    let mut elder_test = ElderUnderTest::new(ELDER_SIZE - 1);
+    let spam_message_limit = elder_test.elder.chain.network_cfg().spam_message_limit;
+
+    let relayer = elder_test.other_ids[0].0.clone();
+    let relayer_addr = *unwrap!(elder_test
+        .elder
+        .chain
+        .get_member_p2p_node(relayer.public_id().name()))
+    .peer_addr();
+    let originator = elder_test.other_ids[1].0.clone();
+
+    // Messages of another member relayed to us don't count against the relayer.
+    let relayed_msg = gen_message_with_bytes(&originator);
+    for _ in 0..=spam_message_limit {
+        elder_test
+            .elder
+            .count_incoming_message(&relayer_addr, &relayed_msg);
+    }
+    assert!(!is_voted_spam(&elder_test.elder, relayer.public_id()));
+    assert!(!is_voted_spam(&elder_test.elder, originator.public_id()));
+
+    // But the ones the relayer sends itself do.
+    let own_msg = gen_message_with_bytes(&relayer);
+    for _ in 0..=spam_message_limit {
+        elder_test
+            .elder
+            .count_incoming_message(&relayer_addr, &own_msg);
+    }
+    assert!(is_voted_spam(&elder_test.elder, relayer.public_id()));
+}
+
+fn gen_message_with_bytes(src: &FullId) -> MessageWithBytes {
+    let msg = unwrap!(Message::single_src(
+        src,
+        PROTOCOL_VERSION,
+        DstLocation::Direct,
+        Variant::UserMessage(vec![]),
+    ));
+    unwrap!(MessageWithBytes::new(msg, &utils::LogIdent::new("test")))
+}
+
+fn is_voted_spam(elder: &Elder, pub_id: &PublicId) -> bool {
+    elder
+        .parsec_map
+        .our_unpolled_observations()
+        .any(|observation| match observation {
+            parsec::Observation::OpaquePayload(event) => match event.payload {
+                AccumulatingEvent::Misbehaviour(ref report) => {
+                    report.offender == *pub_id && report.kind == Misbehaviour::Spam
+                }
+                _ => false,
+            },
+            _ => false,
+        })
+}
+
 fn verify_proof_chain_contains(proof_chain: &SectionProofSlice, expected_version: u64) {
     assert!(
         proof_chain
@@ -12,14 +12,15 @@ use super::{
     common::Base,
 };
 use crate::{
-    chain::{EldersInfo, GenesisPfxInfo, NetworkParams, SectionKeyInfo},
+    chain::{quorum_count, EldersInfo, GenesisPfxInfo, NetworkParams, SectionKeyInfo, Strategies},
     error::{Result, RoutingError},
     event::{Connected, Event},
-    id::FullId,
+    id::{FullId, P2pNode, PublicId},
+    join_policy::JoinConfig,
     location::{DstLocation, SrcLocation},
     messages::{
         BootstrapResponse, JoinRequest, Message, MessageWithBytes, QueuedMessage, Variant,
-        VerifyStatus,
+        VerifyStatus, VersionRange,
     },
     network_service::NetworkService,
     outbox::EventBox,
@@ -33,18 +34,22 @@ use crate::{
     ConnectionInfo,
 };
 use log::LogLevel;
+use resource_proof::ResourceProof;
 use std::{
+    collections::BTreeSet,
     fmt::{self, Display, Formatter},
     time::Duration,
 };
 
-/// Time after which bootstrap is cancelled (and possibly retried).
+/// Default time after which joining is cancelled (and possibly retried).
 pub const JOIN_TIMEOUT: Duration = Duration::from_secs(600);
 
 pub struct JoiningPeerDetails {
     pub network_service: NetworkService,
     pub full_id: FullId,
     pub network_cfg: NetworkParams,
+    pub strategies: Strategies,
+    pub join_config: JoinConfig,
     pub timer: Timer,
     pub rng: MainRng,
     pub elders_info: EldersInfo,
@@ -62,6 +67,15 @@ pub struct JoiningPeer {
     elders_info: EldersInfo,
     join_type: JoinType,
     network_cfg: NetworkParams,
+    strategies: Strategies,
+    join_config: JoinConfig,
+    // Set while waiting to re-send our join requests after the section refused them.
+    retry_timer_token: Option<u64>,
+    // Elders of the section which rejected our join requests. We give up once they are a quorum.
+    rejected_by: BTreeSet<PublicId>,
+    // Nonce and solution of the last resource proof challenge we solved. The elders of the section
+    // all send us the same one.
+    resource_proof: Option<([u8; 32], u64)>,
 }
 
 impl JoiningPeer {
@@ -69,14 +83,17 @@ impl JoiningPeer {
         let join_type = match details.relocate_payload {
             Some(payload) => JoinType::Relocate(payload),
             None => {
-                let timeout_token = details.timer.schedule(JOIN_TIMEOUT);
+                let timeout_token = details.timer.schedule(details.network_cfg.join_timeout);
                 JoinType::First { timeout_token }
             }
         };
 
         let mut joining_peer = Self {
             network_service: details.network_service,
-            msg_filter: RoutingMessageFilter::new(),
+            msg_filter: RoutingMessageFilter::with_expiry_durations(
+                details.network_cfg.incoming_filter_expiry,
+                details.network_cfg.outgoing_filter_expiry,
+            ),
             msg_backlog: vec![],
             full_id: details.full_id,
             timer: details.timer,
@@ -84,6 +101,11 @@ impl JoiningPeer {
             elders_info: details.elders_info,
             join_type,
             network_cfg: details.network_cfg,
+            strategies: details.strategies,
+            join_config: details.join_config,
+            retry_timer_token: None,
+            rejected_by: BTreeSet::new(),
+            resource_proof: None,
         };
 
         joining_peer.send_join_requests();
@@ -106,6 +128,8 @@ impl JoiningPeer {
             timer: self.timer,
             rng: self.rng,
             network_cfg: self.network_cfg,
+            strategies: self.strategies,
+            join_config: self.join_config,
         };
         let adult = Adult::new(details, Default::default(), outbox).map(State::Adult);
 
@@ -125,6 +149,8 @@ impl JoiningPeer {
                 network_service: self.network_service,
                 full_id,
                 network_cfg: self.network_cfg,
+                strategies: self.strategies,
+                join_config: self.join_config,
                 timer: self.timer,
                 rng: self.rng,
             },
@@ -143,6 +169,9 @@ impl JoiningPeer {
             let join_request = JoinRequest {
                 elders_version,
                 relocate_payload,
+                network_params_digest: self.network_cfg.consensus_digest(),
+                app_payload: self.join_config.join_payload.clone(),
+                versions: VersionRange::OURS,
             };
 
             self.send_direct_message(
@@ -152,12 +181,138 @@ impl JoiningPeer {
         }
     }
 
-    fn handle_node_approval(&mut self, gen_pfx_info: GenesisPfxInfo) -> Transition {
+    fn handle_retry_later(&mut self, p2p_node: P2pNode, delay: Duration) {
+        if !self.elders_info.is_member(p2p_node.public_id()) {
+            debug!(
+                "{} - Ignoring RetryLater from {} - not an elder of our section.",
+                self, p2p_node
+            );
+            return;
+        }
+
+        if self.retry_timer_token.is_some() {
+            return;
+        }
+
+        info!(
+            "{} - Join refused by {} - retrying in {:?}.",
+            self, p2p_node, delay
+        );
+        self.retry_timer_token = Some(self.timer.schedule(delay));
+    }
+
+    fn handle_rejected(
+        &mut self,
+        p2p_node: P2pNode,
+        reason: String,
+        outbox: &mut dyn EventBox,
+    ) -> Transition {
+        if !self.elders_info.is_member(p2p_node.public_id()) {
+            debug!(
+                "{} - Ignoring Rejected from {} - not an elder of our section.",
+                self, p2p_node
+            );
+            return Transition::Stay;
+        }
+
+        let _ = self.rejected_by.insert(*p2p_node.public_id());
+        let quorum = quorum_count(self.elders_info.len());
+        if self.rejected_by.len() < quorum {
+            info!(
+                "{} - Join rejected by {} ({}/{}): {}.",
+                self,
+                p2p_node,
+                self.rejected_by.len(),
+                quorum,
+                reason
+            );
+            return Transition::Stay;
+        }
+
+        info!(
+            "{} - Join rejected by {}: {}. Terminating.",
+            self, p2p_node, reason
+        );
+        outbox.send_event(Event::JoinRejected(reason));
+        Transition::Terminate
+    }
+
+    // Counts towards the quorum of rejections, like any other reason of the elders to reject us.
+    fn handle_unsupported_version(
+        &mut self,
+        p2p_node: P2pNode,
+        version: u16,
+        outbox: &mut dyn EventBox,
+    ) -> Transition {
+        let reason = format!(
+            "the section speaks protocol version {}, we speak {}",
+            version,
+            VersionRange::OURS
+        );
+        self.handle_rejected(p2p_node, reason, outbox)
+    }
+
+    fn handle_resource_challenge(
+        &mut self,
+        p2p_node: P2pNode,
+        target_size: usize,
+        difficulty: u8,
+        nonce: [u8; 32],
+    ) {
+        if !self.elders_info.is_member(p2p_node.public_id()) {
+            debug!(
+                "{} - Ignoring ResourceChallenge from {} - not an elder of our section.",
+                self, p2p_node
+            );
+            return;
+        }
+
+        // The network parameters of the section match ours, so no honest elder asks for more work
+        // than they set.
+        if target_size != self.network_cfg.resource_proof_target_size
+            || difficulty != self.network_cfg.resource_proof_difficulty
+        {
+            debug!(
+                "{} - Ignoring ResourceChallenge from {} - size {} and difficulty {} don't match \
+                 our network parameters.",
+                self, p2p_node, target_size, difficulty
+            );
+            return;
+        }
+
+        let proof = match self.resource_proof {
+            Some((solved_nonce, proof)) if solved_nonce == nonce => proof,
+            _ => {
+                debug!("{} - Solving ResourceChallenge from {}.", self, p2p_node);
+
+                let resource_proof = ResourceProof::new(target_size, difficulty);
+                let data = resource_proof.create_proof_data(&nonce);
+                let proof = resource_proof.create_prover(data).solve();
+                self.resource_proof = Some((nonce, proof));
+                proof
+            }
+        };
+
+        self.send_direct_message(
+            p2p_node.connection_info(),
+            Variant::ResourceProofResponse { nonce, proof },
+        );
+    }
+
+    fn handle_node_approval(
+        &mut self,
+        gen_pfx_info: GenesisPfxInfo,
+        app_payload: Vec<u8>,
+        outbox: &mut dyn EventBox,
+    ) -> Transition {
         info!(
             "{} - This node has been approved to join the network at {:?}!",
             self,
             gen_pfx_info.latest_info.prefix(),
         );
+        if !app_payload.is_empty() {
+            outbox.send_event(Event::ApprovalPayload(app_payload));
+        }
         Transition::IntoAdult { gen_pfx_info }
     }
 
@@ -215,6 +370,10 @@ impl Base for JoiningPeer {
         &mut self.timer
     }
 
+    fn join_config_mut(&mut self) -> &mut JoinConfig {
+        &mut self.join_config
+    }
+
     fn rng(&mut self) -> &mut MainRng {
         &mut self.rng
     }
@@ -232,6 +391,12 @@ impl Base for JoiningPeer {
     }
 
     fn handle_timeout(&mut self, token: u64, _: &mut dyn EventBox) -> Transition {
+        if self.retry_timer_token == Some(token) {
+            self.retry_timer_token = None;
+            self.send_join_requests();
+            return Transition::Stay;
+        }
+
         let join_token = match self.join_type {
             JoinType::First { timeout_token } => timeout_token,
             JoinType::Relocate(_) => return Transition::Stay,
@@ -250,7 +415,7 @@ impl Base for JoiningPeer {
         &mut self,
         sender: Option<ConnectionInfo>,
         msg: Message,
-        _outbox: &mut dyn EventBox,
+        outbox: &mut dyn EventBox,
     ) -> Result<Transition, RoutingError> {
         match msg.variant {
             Variant::BootstrapResponse(BootstrapResponse::Join(info)) => {
@@ -263,6 +428,7 @@ impl Base for JoiningPeer {
                             self, info, p2p_node
                         );
                         self.elders_info = info;
+                        self.rejected_by.clear();
                         self.send_join_requests();
                     } else {
                         log_or_panic!(
@@ -275,12 +441,35 @@ impl Base for JoiningPeer {
                     }
                 }
             }
-            Variant::NodeApproval(gen_info) => {
+            Variant::BootstrapResponse(BootstrapResponse::RetryLater(delay)) => {
+                let p2p_node = msg.src.to_sender_node(sender)?;
+                self.handle_retry_later(p2p_node, delay);
+            }
+            Variant::BootstrapResponse(BootstrapResponse::Rejected(reason)) => {
+                let p2p_node = msg.src.to_sender_node(sender)?;
+                return Ok(self.handle_rejected(p2p_node, reason, outbox));
+            }
+            Variant::BootstrapResponse(BootstrapResponse::UnsupportedVersion(version)) => {
+                let p2p_node = msg.src.to_sender_node(sender)?;
+                return Ok(self.handle_unsupported_version(p2p_node, version, outbox));
+            }
+            Variant::ResourceChallenge {
+                target_size,
+                difficulty,
+                nonce,
+            } => {
+                let p2p_node = msg.src.to_sender_node(sender)?;
+                self.handle_resource_challenge(p2p_node, target_size, difficulty, nonce);
+            }
+            Variant::NodeApproval {
+                gen_pfx_info,
+                app_payload,
+            } => {
                 // Ensure src and dst are what we expect.
                 let _: &Prefix<_> = msg.src.as_section()?;
                 let _: &XorName = msg.dst.as_node()?;
 
-                return Ok(self.handle_node_approval(*gen_info));
+                return Ok(self.handle_node_approval(*gen_pfx_info, app_payload, outbox));
             }
             _ => unreachable!(),
         }
@@ -300,20 +489,37 @@ impl Base for JoiningPeer {
 
     fn should_handle_message(&self, msg: &Message) -> bool {
         match msg.variant {
-            Variant::BootstrapResponse(BootstrapResponse::Join(_)) | Variant::NodeApproval(_) => {
-                true
-            }
+            Variant::BootstrapResponse(BootstrapResponse::Join(_))
+            | Variant::BootstrapResponse(BootstrapResponse::RetryLater(_))
+            | Variant::BootstrapResponse(BootstrapResponse::Rejected(_))
+            | Variant::BootstrapResponse(BootstrapResponse::UnsupportedVersion(_))
+            | Variant::ResourceChallenge { .. }
+            | Variant::NodeApproval { .. } => true,
             Variant::NeighbourInfo(_)
             | Variant::UserMessage(_)
+            | Variant::UserRequest { .. }
+            | Variant::UserResponse { .. }
+            | Variant::UserMessageWithReceipt { .. }
+            | Variant::DeliveryReceipt(_)
+            | Variant::UserMessageToClient { .. }
             | Variant::AckMessage { .. }
             | Variant::GenesisUpdate(_)
             | Variant::Relocate(_)
             | Variant::MessageSignature(_)
-            | Variant::BootstrapRequest(_)
+            | Variant::BootstrapRequest { .. }
             | Variant::BootstrapResponse(_)
             | Variant::JoinRequest(_)
+            | Variant::ResourceProofResponse { .. }
             | Variant::ConnectionResponse
             | Variant::MemberKnowledge { .. }
+            | Variant::Leave
+            | Variant::ParsecRejoin
+            | Variant::SectionInfoRequest
+            | Variant::SectionInfoResponse(_)
+            | Variant::NetworkStats(_)
+            | Variant::Merge(_)
+            | Variant::MergeKey(_)
+            | Variant::MergeKeySignature(..)
             | Variant::ParsecRequest(..)
             | Variant::ParsecResponse(..) => false,
         }
@@ -331,19 +537,21 @@ impl Base for JoiningPeer {
 
     fn verify_message(&self, msg: &Message) -> Result<bool> {
         match (&msg.variant, &self.join_type) {
-            (Variant::NodeApproval(_), JoinType::Relocate(payload)) => {
+            (Variant::NodeApproval { .. }, JoinType::Relocate(payload)) => {
                 let details = payload.relocate_details();
                 let key_info = &details.destination_key_info;
                 self.verify_message_full(msg, Some(key_info))
             }
-            (Variant::NodeApproval(_), JoinType::First { .. }) => {
+            (Variant::NodeApproval { .. }, JoinType::First { .. }) => {
                 // We don't have any trusted keys to verify this message, but we still need to
                 // handle it.
                 Ok(true)
             }
-            (Variant::BootstrapResponse(BootstrapResponse::Join(_)), _) => {
-                self.verify_message_full(msg, None)
-            }
+            (Variant::BootstrapResponse(BootstrapResponse::Join(_)), _)
+            | (Variant::BootstrapResponse(BootstrapResponse::RetryLater(_)), _)
+            | (Variant::BootstrapResponse(BootstrapResponse::Rejected(_)), _)
+            | (Variant::BootstrapResponse(BootstrapResponse::UnsupportedVersion(_)), _)
+            | (Variant::ResourceChallenge { .. }, _) => self.verify_message_full(msg, None),
             _ => unreachable!(),
         }
     }
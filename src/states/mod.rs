@@ -21,8 +21,15 @@ pub use self::{
     joining_peer::JoiningPeer,
 };
 
-#[cfg(feature = "mock_base")]
-pub use self::{bootstrapping_peer::BOOTSTRAP_TIMEOUT, joining_peer::JOIN_TIMEOUT};
+pub use self::{
+    adult::{LEAVE_TIMEOUT, MAX_LEAVE_ATTEMPTS},
+    bootstrapping_peer::BOOTSTRAP_TIMEOUT,
+    elder::{
+        GOSSIP_TIMEOUT, RESOURCE_PROOF_DIFFICULTY, RESOURCE_PROOF_TARGET_SIZE,
+        RESOURCE_PROOF_TIMEOUT,
+    },
+    joining_peer::JOIN_TIMEOUT,
+};
 
 // # The state machine
 //
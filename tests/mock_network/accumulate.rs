@@ -19,6 +19,7 @@ fn messages_accumulate_with_quorum() {
     let env = Environment::new(NetworkParams {
         elder_size,
         safe_section_size: elder_size,
+        ..Default::default()
     });
     let mut rng = env.new_rng();
     let mut nodes = create_connected_nodes(&env, section_size);
@@ -0,0 +1,82 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::gen_vec;
+use futures::{
+    executor::{block_on, LocalPool},
+    task::LocalSpawnExt,
+    FutureExt, StreamExt,
+};
+use routing::{
+    event::{Connected, Event},
+    mock::Environment,
+    AsyncNode, DstLocation, NetworkParams, Node, SrcLocation,
+};
+
+#[test]
+fn send_message_and_stream_events() {
+    let env = Environment::new(NetworkParams::default());
+    let mut rng = env.new_rng();
+    let (node, node_event_rx) = unwrap!(Node::builder()
+        .first(true)
+        .network_cfg(env.network_cfg())
+        .rng(&mut env.new_rng())
+        .create());
+    let id = unwrap!(node.id());
+
+    let (node, mut events, mut driver) = AsyncNode::new(node, node_event_rx);
+    let _ = unwrap!(driver.run_until_idle());
+    loop {
+        match events.next().now_or_never() {
+            Some(Some(Event::Connected(Connected::First))) => break,
+            Some(Some(_)) => (),
+            _ => panic!("Event::Connected not received"),
+        }
+    }
+
+    let content = gen_vec(&mut rng, 32);
+    let dst = DstLocation::Section(*id.name());
+    let result = node.send_message(SrcLocation::Node(id), dst, content.clone());
+    assert!(unwrap!(driver.run_until_idle()));
+    unwrap!(block_on(result));
+
+    loop {
+        match events.next().now_or_never() {
+            Some(Some(Event::MessageReceived {
+                content: received_content,
+                ..
+            })) if received_content == content => break,
+            Some(Some(_)) => (),
+            _ => panic!("Event::MessageReceived not received"),
+        }
+    }
+
+    // Once the driver is gone, sending fails and the event stream ends.
+    drop(driver);
+    assert!(block_on(node.send_message(SrcLocation::Node(id), dst, vec![])).is_err());
+    while block_on(events.next()).is_some() {}
+}
+
+#[test]
+fn driver_runs_as_future() {
+    let env = Environment::new(NetworkParams::default());
+    let (node, node_event_rx) = unwrap!(Node::builder()
+        .first(true)
+        .network_cfg(env.network_cfg())
+        .rng(&mut env.new_rng())
+        .create());
+    let id = unwrap!(node.id());
+
+    let (node, _events, driver) = AsyncNode::new(node, node_event_rx);
+    let mut pool = LocalPool::new();
+    unwrap!(pool.spawner().spawn_local(driver));
+
+    assert_eq!(unwrap!(pool.run_until(node.id())), id);
+    let dst = DstLocation::Section(*id.name());
+    unwrap!(pool.run_until(node.send_message(SrcLocation::Node(id), dst, vec![])));
+}
@@ -59,6 +59,7 @@ fn remove_unresponsive_node() {
     let env = Environment::new(NetworkParams {
         elder_size,
         safe_section_size,
+        ..Default::default()
     });
 
     let mut nodes = create_connected_nodes(&env, safe_section_size);
@@ -159,6 +160,7 @@ impl Default for Params {
             network: NetworkParams {
                 elder_size: 4,
                 safe_section_size: 4,
+                ..Default::default()
             },
             initial_prefix_lens: vec![],
             message_schedule: MessageSchedule::AfterChurn,
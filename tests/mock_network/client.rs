@@ -0,0 +1,190 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    create_connected_nodes, gen_vec, poll_all_with_client, TestClient, LOWERED_ELDER_SIZE,
+};
+use bytes::Bytes;
+use rand::Rng;
+use routing::{
+    event::{self, Connected, Event},
+    generate_bls_threshold_secret_key,
+    mock::Environment,
+    DstLocation, NetworkParams, SrcLocation,
+};
+
+#[test]
+fn client_connects_and_sends_message() {
+    let env = Environment::new(NetworkParams {
+        elder_size: LOWERED_ELDER_SIZE,
+        safe_section_size: LOWERED_ELDER_SIZE,
+        ..Default::default()
+    });
+    let mut rng = env.new_rng();
+    let mut nodes = create_connected_nodes(&env, LOWERED_ELDER_SIZE);
+
+    let mut client = TestClient::new(&env, nodes[0].endpoint());
+    let _ = poll_all_with_client(&mut nodes, &mut client);
+
+    expect_next_event!(client, Event::Connected(Connected::First));
+    assert_eq!(client.inner.our_prefix(), Some(nodes[0].our_prefix()));
+    assert_eq!(
+        unwrap!(client.inner.our_elders_info()).count(),
+        LOWERED_ELDER_SIZE
+    );
+
+    let dst = DstLocation::Section(rng.gen());
+    let content = gen_vec(&mut rng, 32);
+    unwrap!(client.inner.send_message(dst, content.clone()));
+    let _ = poll_all_with_client(&mut nodes, &mut client);
+
+    let expected_src = SrcLocation::Node(client.id());
+    for node in nodes.iter().filter(|node| node.in_dst_location(&dst)) {
+        loop {
+            match node.try_recv_event() {
+                Some(Event::MessageReceived {
+                    content: ref received_content,
+                    ref src,
+                    ..
+                }) if *received_content == content => {
+                    assert_eq!(*src, expected_src);
+                    break;
+                }
+                Some(_) => (),
+                None => panic!("{} - Event::MessageReceived not received", node.inner),
+            }
+        }
+    }
+}
+
+#[test]
+fn client_connects_through_adult() {
+    let env = Environment::new(NetworkParams {
+        elder_size: LOWERED_ELDER_SIZE,
+        safe_section_size: LOWERED_ELDER_SIZE,
+        ..Default::default()
+    });
+    let mut nodes = create_connected_nodes(&env, LOWERED_ELDER_SIZE + 1);
+    let adult = unwrap!(nodes.iter().find(|node| !node.inner.is_elder()));
+
+    // The adult has the elders of its section sign them for the client.
+    let mut client = TestClient::new(&env, adult.endpoint());
+    let _ = poll_all_with_client(&mut nodes, &mut client);
+
+    expect_next_event!(client, Event::Connected(Connected::First));
+    assert_eq!(
+        unwrap!(client.inner.our_elders_info()).count(),
+        LOWERED_ELDER_SIZE
+    );
+}
+
+#[test]
+fn client_receives_message_from_section() {
+    let env = Environment::new(NetworkParams {
+        elder_size: LOWERED_ELDER_SIZE,
+        safe_section_size: LOWERED_ELDER_SIZE,
+        ..Default::default()
+    });
+    let mut rng = env.new_rng();
+    let mut nodes = create_connected_nodes(&env, LOWERED_ELDER_SIZE);
+
+    let mut client = TestClient::new(&env, nodes[0].endpoint());
+    let _ = poll_all_with_client(&mut nodes, &mut client);
+    expect_next_event!(client, Event::Connected(Connected::First));
+
+    // The proxy learns the name of the client from the first message the client sends.
+    let proxy = *unwrap!(client.inner.proxy_id()).name();
+    let request = gen_vec(&mut rng, 8);
+    unwrap!(client
+        .inner
+        .send_message(DstLocation::Section(proxy), request));
+    let _ = poll_all_with_client(&mut nodes, &mut client);
+
+    let prefix = *nodes[0].our_prefix();
+    let content = gen_vec(&mut rng, 32);
+    for node in nodes.iter_mut().filter(|node| node.inner.is_elder()) {
+        unwrap!(node.inner.send_message_to_client_from_section(
+            proxy,
+            client.name(),
+            content.clone()
+        ));
+    }
+    let _ = poll_all_with_client(&mut nodes, &mut client);
+
+    match client.try_recv_event() {
+        Some(Event::MessageReceived {
+            content: received_content,
+            src: SrcLocation::Section(src_prefix),
+            ..
+        }) => {
+            assert_eq!(received_content, content);
+            assert_eq!(src_prefix, prefix);
+        }
+        event => panic!("Expected Event::MessageReceived, got {:?}", event),
+    }
+    assert!(client.try_recv_event().is_none());
+}
+
+#[test]
+fn client_receives_app_payload_resembling_client_message() {
+    let env = Environment::new(NetworkParams {
+        elder_size: LOWERED_ELDER_SIZE,
+        safe_section_size: LOWERED_ELDER_SIZE,
+        ..Default::default()
+    });
+    let mut nodes = create_connected_nodes(&env, LOWERED_ELDER_SIZE);
+
+    let mut client = TestClient::new(&env, nodes[0].endpoint());
+    let _ = poll_all_with_client(&mut nodes, &mut client);
+    expect_next_event!(client, Event::Connected(Connected::First));
+
+    let client_addr = loop {
+        match nodes[0].try_recv_event() {
+            Some(Event::Client(event::Client::Connected { peer_addr })) => break peer_addr,
+            Some(_) => (),
+            None => panic!("{} - Client not connected", nodes[0].inner),
+        }
+    };
+
+    // Would deserialise as a `GetSectionInfo` client message, if those were not tagged.
+    let payload = Bytes::from(vec![0, 0, 0, 0, 1, 2, 3]);
+    unwrap!(nodes[0]
+        .inner
+        .send_message_to_client(client_addr, payload.clone(), 0));
+    let _ = poll_all_with_client(&mut nodes, &mut client);
+
+    match client.try_recv_event() {
+        Some(Event::Client(event::Client::NewMessage { msg, .. })) => assert_eq!(msg, payload),
+        event => panic!("Expected Client::NewMessage, got {:?}", event),
+    }
+}
+
+#[test]
+fn client_requires_proof_from_genesis_key() {
+    let env = Environment::new(NetworkParams {
+        elder_size: LOWERED_ELDER_SIZE,
+        safe_section_size: LOWERED_ELDER_SIZE,
+        ..Default::default()
+    });
+    let mut rng = env.new_rng();
+    let mut nodes = create_connected_nodes(&env, LOWERED_ELDER_SIZE);
+    let genesis_key = *unwrap!(nodes[0].inner.genesis_key());
+
+    let mut client = TestClient::with_genesis_key(&env, nodes[0].endpoint(), genesis_key);
+    let _ = poll_all_with_client(&mut nodes, &mut client);
+    expect_next_event!(client, Event::Connected(Connected::First));
+
+    // A client configured with another genesis key does not trust the section of its proxy.
+    let other_key = generate_bls_threshold_secret_key(&mut rng, 1)
+        .public_keys()
+        .public_key();
+    let mut client = TestClient::with_genesis_key(&env, nodes[0].endpoint(), other_key);
+    let _ = poll_all_with_client(&mut nodes, &mut client);
+    assert!(client.try_recv_event().is_none());
+    assert!(client.inner.our_prefix().is_none());
+}
@@ -9,8 +9,9 @@
 use super::{
     create_connected_nodes, poll_all, poll_and_resend, verify_invariant_for_all_nodes, TestNode,
 };
+use fake_clock::FakeClock;
 use rand::Rng;
-use routing::{event::Event, mock::Environment, NetworkParams};
+use routing::{event::Event, mock::Environment, test_consts, Misbehaviour, NetworkParams};
 
 // Drop node at index and verify its own section detected it.
 fn drop_node(nodes: &mut Vec<TestNode>, index: usize) {
@@ -36,6 +37,7 @@ fn node_drops() {
     let env = Environment::new(NetworkParams {
         elder_size,
         safe_section_size,
+        ..Default::default()
     });
     let mut nodes = create_connected_nodes(&env, elder_size + 2);
     drop_node(&mut nodes, 0);
@@ -55,6 +57,7 @@ fn node_restart() {
     let env = Environment::new(NetworkParams {
         elder_size,
         safe_section_size,
+        ..Default::default()
     });
     let mut rng = env.new_rng();
     let mut nodes = create_connected_nodes(&env, elder_size);
@@ -67,3 +70,111 @@ fn node_restart() {
 
     expect_next_event!(nodes[0], Event::RestartRequired);
 }
+
+// Make the node at index leave the network and verify its section removed it.
+fn leave_node(env: &Environment, nodes: &mut Vec<TestNode>, index: usize) {
+    let id = unwrap!(nodes[index].inner.id());
+    unwrap!(nodes[index].inner.leave());
+    poll_and_resend(nodes);
+
+    expect_any_event!(nodes[index], Event::Left);
+    let _ = nodes.remove(index);
+    poll_and_resend(nodes);
+    verify_invariant_for_all_nodes(env, nodes);
+
+    for node in nodes.iter() {
+        assert!(!node.inner.is_peer_our_member(&id));
+    }
+}
+
+#[test]
+fn adult_leaves() {
+    let elder_size = 5;
+    let safe_section_size = 5;
+    let env = Environment::new(NetworkParams {
+        elder_size,
+        safe_section_size,
+        ..Default::default()
+    });
+    let mut nodes = create_connected_nodes(&env, elder_size + 2);
+
+    let index = unwrap!(nodes.iter().position(|node| !node.inner.is_elder()));
+    leave_node(&env, &mut nodes, index);
+}
+
+#[test]
+fn adult_leaves_without_response() {
+    let elder_size = 5;
+    let safe_section_size = 5;
+    let env = Environment::new(NetworkParams {
+        elder_size,
+        safe_section_size,
+        ..Default::default()
+    });
+    let mut nodes = create_connected_nodes(&env, elder_size + 2);
+
+    let index = unwrap!(nodes.iter().position(|node| !node.inner.is_elder()));
+    unwrap!(nodes[index].inner.leave());
+
+    // Only the leaving node is polled, so its section never votes it offline.
+    for _ in 0..test_consts::MAX_LEAVE_ATTEMPTS {
+        let _ = nodes[index].poll();
+        FakeClock::advance_time(test_consts::LEAVE_TIMEOUT.as_millis() as u64 + 1);
+    }
+
+    let _ = nodes[index].poll();
+    expect_any_event!(nodes[index], Event::Left);
+}
+
+#[test]
+fn elder_leaves() {
+    let elder_size = 5;
+    let safe_section_size = 5;
+    let env = Environment::new(NetworkParams {
+        elder_size,
+        safe_section_size,
+        ..Default::default()
+    });
+    let mut nodes = create_connected_nodes(&env, elder_size + 2);
+
+    let index = unwrap!(nodes.iter().position(|node| node.inner.is_elder()));
+    leave_node(&env, &mut nodes, index);
+}
+
+#[test]
+fn misbehaving_adult_is_evicted() {
+    let elder_size = 5;
+    let safe_section_size = 5;
+    let env = Environment::new(NetworkParams {
+        elder_size,
+        safe_section_size,
+        ..Default::default()
+    });
+    let mut nodes = create_connected_nodes(&env, elder_size + 2);
+
+    let index = unwrap!(nodes.iter().position(|node| !node.inner.is_elder()));
+    let offender = unwrap!(nodes[index].inner.id());
+
+    // With the default params, the first invalid vote demotes the offender and the second one
+    // evicts it.
+    for _ in 0..2 {
+        for node in nodes.iter_mut().filter(|node| node.inner.is_elder()) {
+            unwrap!(node
+                .inner
+                .report_misbehaviour(offender, Misbehaviour::InvalidVote));
+        }
+        poll_and_resend(&mut nodes);
+    }
+
+    for node in nodes.iter_mut().filter(|node| node.inner.is_elder()) {
+        expect_any_event!(node, Event::MemberEvicted(pub_id) if pub_id == offender);
+    }
+
+    let _ = nodes.remove(index);
+    poll_and_resend(&mut nodes);
+    verify_invariant_for_all_nodes(&env, &mut nodes);
+
+    for node in nodes.iter() {
+        assert!(!node.inner.is_peer_our_member(&offender));
+    }
+}
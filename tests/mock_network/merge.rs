@@ -0,0 +1,88 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    create_connected_nodes_until_split, current_sections, poll_and_resend,
+    verify_invariant_for_all_nodes, TestNode, LOWERED_ELDER_SIZE,
+};
+use itertools::Itertools;
+use routing::{event::Event, mock::Environment, NetworkParams, Prefix, XorName};
+use std::collections::BTreeSet;
+
+#[test]
+fn sibling_sections_merge_below_threshold() {
+    let merge_threshold = LOWERED_ELDER_SIZE + 2;
+    let env = Environment::new(NetworkParams {
+        elder_size: LOWERED_ELDER_SIZE,
+        safe_section_size: merge_threshold,
+        merge_threshold,
+        ..Default::default()
+    });
+    let mut nodes = create_connected_nodes_until_split(&env, vec![1, 1]);
+    let elders: BTreeSet<_> = nodes
+        .iter()
+        .filter(|node| node.inner.is_elder())
+        .map(TestNode::name)
+        .collect();
+
+    // Drop adults of both sections until they are below the merge threshold.
+    for prefix in current_sections(&nodes).collect_vec() {
+        while nodes
+            .iter()
+            .filter(|node| *node.our_prefix() == prefix)
+            .count()
+            >= merge_threshold
+        {
+            let index = unwrap!(nodes
+                .iter()
+                .position(|node| *node.our_prefix() == prefix && !node.inner.is_elder()));
+            drop(nodes.remove(index));
+        }
+    }
+    poll_and_resend(&mut nodes);
+
+    // The elders of both siblings saw them merge.
+    for node in nodes
+        .iter_mut()
+        .filter(|node| elders.contains(&node.name()))
+    {
+        loop {
+            match node.try_recv_event() {
+                Some(Event::SectionMerged(prefix)) => {
+                    assert_eq!(prefix, Prefix::default());
+                    break;
+                }
+                Some(_) => (),
+                None => panic!("{} - Event::SectionMerged not received", node.inner),
+            }
+        }
+    }
+
+    // All the nodes agree on the single section of the parent prefix and its elders.
+    let expected_prefixes: BTreeSet<Prefix<XorName>> =
+        vec![Prefix::default()].into_iter().collect();
+    for node in &nodes {
+        assert_eq!(*node.our_prefix(), Prefix::default());
+        assert_eq!(node.inner.prefixes(), expected_prefixes);
+    }
+
+    let elders_infos: BTreeSet<(u64, BTreeSet<XorName>)> = nodes
+        .iter()
+        .filter(|node| node.inner.is_elder())
+        .map(|node| {
+            let version = node.inner.section_elder_info_version(&Prefix::default());
+            let elders = unwrap!(node.inner.our_elders_info())
+                .map(|p2p_node| *p2p_node.name())
+                .collect();
+            (version, elders)
+        })
+        .collect();
+    assert_eq!(elders_infos.len(), 1);
+
+    verify_invariant_for_all_nodes(&env, &mut nodes);
+}
@@ -7,10 +7,13 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{create_connected_nodes, gen_elder_index, gen_vec, poll_all};
+use fake_clock::FakeClock;
 use rand::Rng;
 use routing::{
-    event::Event, mock::Environment, quorum_count, DstLocation, NetworkParams, SrcLocation,
+    event::Event, mock::Environment, quorum_count, DstLocation, MessageId, NetworkParams,
+    SrcLocation,
 };
+use std::time::Duration;
 
 #[test]
 fn send() {
@@ -20,6 +23,7 @@ fn send() {
     let env = Environment::new(NetworkParams {
         elder_size,
         safe_section_size,
+        ..Default::default()
     });
     let mut rng = env.new_rng();
     let mut nodes = create_connected_nodes(&env, elder_size + 1);
@@ -68,6 +72,7 @@ fn send_and_receive() {
     let env = Environment::new(NetworkParams {
         elder_size,
         safe_section_size,
+        ..Default::default()
     });
     let mut rng = env.new_rng();
     let mut nodes = create_connected_nodes(&env, elder_size + 1);
@@ -139,3 +144,245 @@ fn send_and_receive() {
 
     assert_eq!(response_received_count, 1);
 }
+
+#[test]
+fn request_and_response() {
+    let elder_size = 8;
+    let safe_section_size = 8;
+    let env = Environment::new(NetworkParams {
+        elder_size,
+        safe_section_size,
+        ..Default::default()
+    });
+    let mut rng = env.new_rng();
+    let mut nodes = create_connected_nodes(&env, elder_size + 1);
+
+    let sender_index = gen_elder_index(&mut rng, &nodes);
+    let src = SrcLocation::Node(nodes[sender_index].id());
+    let dst = DstLocation::Section(rng.gen());
+    let id = MessageId::gen(&mut rng);
+
+    let req_content = gen_vec(&mut rng, 10);
+    let res_content = gen_vec(&mut rng, 11);
+
+    unwrap!(nodes[sender_index].inner.send_request(
+        src,
+        dst,
+        id,
+        req_content.clone(),
+        Duration::from_secs(60)
+    ));
+    let _ = poll_all(&mut nodes);
+
+    for node in nodes
+        .iter_mut()
+        .filter(|n| n.inner.is_elder() && n.in_dst_location(&dst))
+    {
+        loop {
+            match node.try_recv_event() {
+                Some(Event::RequestReceived {
+                    id: req_id,
+                    content,
+                    src: req_src,
+                    ..
+                }) => {
+                    assert_eq!(req_id, id);
+                    assert_eq!(content, req_content);
+                    assert_eq!(req_src, src);
+
+                    let res_src = SrcLocation::Section(*node.our_prefix());
+                    let res_dst = match req_src {
+                        SrcLocation::Node(id) => DstLocation::Node(*id.name()),
+                        _ => panic!("Unexpected src location: {:?}", req_src),
+                    };
+                    unwrap!(node
+                        .inner
+                        .send_response(res_src, res_dst, id, res_content.clone()));
+                    break;
+                }
+                Some(_) => (),
+                None => panic!("{} - Event::RequestReceived not received", node.inner),
+            }
+        }
+    }
+
+    let _ = poll_all(&mut nodes);
+
+    let mut response_received_count = 0;
+    while let Some(event) = nodes[sender_index].try_recv_event() {
+        match event {
+            Event::ResponseReceived {
+                id: res_id,
+                content,
+                ..
+            } => {
+                assert_eq!(res_id, id);
+                assert_eq!(content, res_content);
+                response_received_count += 1;
+            }
+            Event::RequestTimedOut { .. } => panic!("Unexpected Event::RequestTimedOut"),
+            _ => (),
+        }
+    }
+
+    assert_eq!(response_received_count, 1);
+}
+
+#[test]
+fn request_timeout() {
+    let elder_size = 8;
+    let safe_section_size = 8;
+    let env = Environment::new(NetworkParams {
+        elder_size,
+        safe_section_size,
+        ..Default::default()
+    });
+    let mut rng = env.new_rng();
+    let mut nodes = create_connected_nodes(&env, elder_size + 1);
+
+    // Nobody has this name, so nobody responds.
+    let sender_index = gen_elder_index(&mut rng, &nodes);
+    let src = SrcLocation::Node(nodes[sender_index].id());
+    let dst = DstLocation::Node(rng.gen());
+    let id = MessageId::gen(&mut rng);
+    let timeout = Duration::from_secs(20);
+
+    unwrap!(nodes[sender_index]
+        .inner
+        .send_request(src, dst, id, gen_vec(&mut rng, 10), timeout));
+    let _ = poll_all(&mut nodes);
+
+    FakeClock::advance_time(timeout.as_secs() * 1000 + 1);
+    let _ = poll_all(&mut nodes);
+
+    loop {
+        match nodes[sender_index].try_recv_event() {
+            Some(Event::RequestTimedOut {
+                id: timed_out_id,
+                dst: timed_out_dst,
+            }) => {
+                assert_eq!(timed_out_id, id);
+                assert_eq!(timed_out_dst, dst);
+                break;
+            }
+            Some(Event::ResponseReceived { .. }) => panic!("Unexpected Event::ResponseReceived"),
+            Some(_) => (),
+            None => panic!("Event::RequestTimedOut not received"),
+        }
+    }
+}
+
+#[test]
+fn message_with_receipt_delivered() {
+    let elder_size = 8;
+    let safe_section_size = 8;
+    let env = Environment::new(NetworkParams {
+        elder_size,
+        safe_section_size,
+        ..Default::default()
+    });
+    let mut rng = env.new_rng();
+    let mut nodes = create_connected_nodes(&env, elder_size + 1);
+
+    let sender_index = gen_elder_index(&mut rng, &nodes);
+    let src = SrcLocation::Node(nodes[sender_index].id());
+    let dst = DstLocation::Section(rng.gen());
+    let id = MessageId::gen(&mut rng);
+    let content = gen_vec(&mut rng, 10);
+
+    unwrap!(nodes[sender_index]
+        .inner
+        .send_message_with_receipt(src, dst, id, content.clone()));
+    let _ = poll_all(&mut nodes);
+
+    for node in nodes
+        .iter_mut()
+        .filter(|n| n.inner.is_elder() && n.in_dst_location(&dst))
+    {
+        loop {
+            match node.try_recv_event() {
+                Some(Event::MessageReceived {
+                    content: ref received_content,
+                    ..
+                }) if *received_content == content => break,
+                Some(_) => (),
+                None => panic!("{} - Event::MessageReceived not received", node.inner),
+            }
+        }
+    }
+
+    loop {
+        match nodes[sender_index].try_recv_event() {
+            Some(Event::MessageDelivered {
+                id: delivered_id,
+                dst: delivered_dst,
+            }) => {
+                assert_eq!(delivered_id, id);
+                assert_eq!(delivered_dst, dst);
+                break;
+            }
+            Some(Event::MessageDeliveryFailed { .. }) => {
+                panic!("Unexpected Event::MessageDeliveryFailed")
+            }
+            Some(_) => (),
+            None => panic!("Event::MessageDelivered not received"),
+        }
+    }
+}
+
+#[test]
+fn message_with_receipt_failed() {
+    let elder_size = 8;
+    let safe_section_size = 8;
+    let delivery_receipt_timeout = Duration::from_secs(10);
+    let env = Environment::new(NetworkParams {
+        elder_size,
+        safe_section_size,
+        delivery_receipt_timeout,
+        max_delivery_attempts: 2,
+        ..Default::default()
+    });
+    let mut rng = env.new_rng();
+    let mut nodes = create_connected_nodes(&env, elder_size + 1);
+
+    // Nobody has this name, so nobody confirms the delivery.
+    let sender_index = gen_elder_index(&mut rng, &nodes);
+    let src = SrcLocation::Node(nodes[sender_index].id());
+    let dst = DstLocation::Node(rng.gen());
+    let id = MessageId::gen(&mut rng);
+
+    unwrap!(nodes[sender_index].inner.send_message_with_receipt(
+        src,
+        dst,
+        id,
+        gen_vec(&mut rng, 10)
+    ));
+
+    let mut failed = false;
+    for _ in 0..10 {
+        let _ = poll_all(&mut nodes);
+
+        while let Some(event) = nodes[sender_index].try_recv_event() {
+            match event {
+                Event::MessageDeliveryFailed {
+                    id: failed_id,
+                    dst: failed_dst,
+                } => {
+                    assert_eq!(failed_id, id);
+                    assert_eq!(failed_dst, dst);
+                    failed = true;
+                }
+                Event::MessageDelivered { .. } => panic!("Unexpected Event::MessageDelivered"),
+                _ => (),
+            }
+        }
+
+        if failed {
+            break;
+        }
+
+        FakeClock::advance_time(delivery_receipt_timeout.as_millis() as u64 + 1);
+    }
+
+    assert!(failed, "Event::MessageDeliveryFailed not received");
+}
@@ -7,21 +7,28 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod accumulate;
+#[cfg(feature = "async")]
+mod async_node;
 mod churn;
+mod client;
 mod drop;
+mod merge;
 mod messages;
 mod node_ageing;
 mod secure_message_delivery;
 mod utils;
 
 pub use self::utils::*;
+use fake_clock::FakeClock;
 use itertools::Itertools;
 use rand::{seq::SliceRandom, Rng};
 use routing::{
-    event::Event, mock::Environment, FullId, NetworkConfig, NetworkParams, Prefix,
-    RelocationOverrides, XorName,
+    event::{Connected, Event},
+    mock::Environment,
+    FullId, JoinDecision, JoinPolicy, NetworkConfig, NetworkParams, Node, P2pNode, PausedState,
+    Prefix, RelocationOverrides, RoutingError, XorName, MAX_APP_PAYLOAD_SIZE,
 };
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, time::Duration};
 
 pub const LOWERED_ELDER_SIZE: usize = 3;
 
@@ -40,6 +47,7 @@ fn test_nodes(percentage_size: usize) {
     let env = Environment::new(NetworkParams {
         elder_size: LOWERED_ELDER_SIZE,
         safe_section_size: LOWERED_ELDER_SIZE,
+        ..Default::default()
     });
     let mut nodes = create_connected_nodes(&env, size);
     verify_invariant_for_all_nodes(&env, &mut nodes);
@@ -102,6 +110,7 @@ fn disconnect_on_rebootstrap() {
     let env = Environment::new(NetworkParams {
         elder_size: LOWERED_ELDER_SIZE,
         safe_section_size: LOWERED_ELDER_SIZE,
+        ..Default::default()
     });
     let mut nodes = create_connected_nodes(&env, 2);
 
@@ -174,6 +183,7 @@ fn single_section() {
     let env = Environment::new(NetworkParams {
         elder_size: sec_size,
         safe_section_size: sec_size,
+        ..Default::default()
     });
     let mut nodes = create_connected_nodes(&env, sec_size);
     verify_invariant_for_all_nodes(&env, &mut nodes);
@@ -199,6 +209,7 @@ fn node_joins_in_front() {
     let env = Environment::new(NetworkParams {
         elder_size: LOWERED_ELDER_SIZE,
         safe_section_size: LOWERED_ELDER_SIZE,
+        ..Default::default()
     });
     let mut nodes = create_connected_nodes(&env, 2 * LOWERED_ELDER_SIZE);
     let network_config = NetworkConfig::node().with_hard_coded_contact(nodes[0].endpoint());
@@ -213,11 +224,157 @@ fn node_joins_in_front() {
     verify_invariant_for_all_nodes(&env, &mut nodes);
 }
 
+#[test]
+fn nodes_join_with_harder_resource_proof() {
+    let env = Environment::new(NetworkParams {
+        elder_size: LOWERED_ELDER_SIZE,
+        safe_section_size: LOWERED_ELDER_SIZE,
+        resource_proof_difficulty: 4,
+        resource_proof_target_size: 4096,
+        ..Default::default()
+    });
+    let mut nodes = create_connected_nodes(&env, 2 * LOWERED_ELDER_SIZE);
+    verify_invariant_for_all_nodes(&env, &mut nodes);
+}
+
+// Only accepts the nodes which attached the expected payload to their join request.
+struct PayloadPolicy;
+
+impl JoinPolicy for PayloadPolicy {
+    fn decide(&self, _: &P2pNode, _: bool, payload: &[u8]) -> JoinDecision {
+        if payload == b"allowed" {
+            JoinDecision::Accept
+        } else {
+            JoinDecision::Reject("unexpected payload".to_string())
+        }
+    }
+}
+
+#[test]
+fn join_policy_rejects_nodes() {
+    let env = Environment::new(NetworkParams {
+        elder_size: LOWERED_ELDER_SIZE,
+        safe_section_size: LOWERED_ELDER_SIZE,
+        ..Default::default()
+    });
+    let mut nodes = vec![TestNode::builder(&env)
+        .first()
+        .join_policy(PayloadPolicy)
+        .create()];
+    let _ = nodes[0].poll();
+    expect_next_event!(nodes[0], Event::Connected(Connected::First));
+
+    let config = NetworkConfig::node().with_hard_coded_contact(nodes[0].endpoint());
+    // The policy of the section is the same on all its elders, as a quorum of them must reject.
+    nodes.push(
+        TestNode::builder(&env)
+            .network_config(config.clone())
+            .join_policy(PayloadPolicy)
+            .join_payload(b"allowed".to_vec())
+            .create(),
+    );
+    nodes.push(
+        TestNode::builder(&env)
+            .network_config(config)
+            .join_payload(b"denied".to_vec())
+            .create(),
+    );
+    poll_and_resend(&mut nodes);
+
+    expect_any_event!(nodes[1], Event::Connected(Connected::First));
+    expect_any_event!(
+        nodes[2],
+        Event::JoinRejected(ref reason) if reason == "unexpected payload"
+    );
+}
+
+#[test]
+fn invalid_network_params_are_refused() {
+    let env = Environment::new(NetworkParams {
+        elder_size: LOWERED_ELDER_SIZE,
+        safe_section_size: LOWERED_ELDER_SIZE,
+        ..Default::default()
+    });
+    let result = TestNode::builder(&env)
+        .first()
+        .network_cfg(NetworkParams {
+            elder_size: LOWERED_ELDER_SIZE,
+            safe_section_size: LOWERED_ELDER_SIZE - 1,
+            ..Default::default()
+        })
+        .try_create();
+    match result {
+        Err(RoutingError::InvalidNetworkParams) => (),
+        Err(error) => panic!("Unexpected error: {:?}", error),
+        Ok(_) => panic!("Node created with invalid network parameters"),
+    }
+}
+
+#[test]
+fn mismatched_network_params_reject_joiner() {
+    let env = Environment::new(NetworkParams {
+        elder_size: LOWERED_ELDER_SIZE,
+        safe_section_size: LOWERED_ELDER_SIZE,
+        ..Default::default()
+    });
+    let mut nodes = vec![TestNode::builder(&env).first().create()];
+    let _ = nodes[0].poll();
+    expect_next_event!(nodes[0], Event::Connected(Connected::First));
+
+    let config = NetworkConfig::node().with_hard_coded_contact(nodes[0].endpoint());
+    nodes.push(
+        TestNode::builder(&env)
+            .network_config(config)
+            .network_cfg(NetworkParams {
+                elder_size: LOWERED_ELDER_SIZE,
+                safe_section_size: LOWERED_ELDER_SIZE + 1,
+                ..Default::default()
+            })
+            .create(),
+    );
+    poll_and_resend(&mut nodes);
+
+    expect_any_event!(
+        nodes[1],
+        Event::JoinRejected(ref reason) if reason == "network parameters don't match ours"
+    );
+}
+
+#[test]
+fn approval_payload_is_handed_over() {
+    let env = Environment::new(NetworkParams {
+        elder_size: LOWERED_ELDER_SIZE,
+        safe_section_size: LOWERED_ELDER_SIZE,
+        ..Default::default()
+    });
+    let mut nodes = vec![TestNode::builder(&env)
+        .first()
+        .approval_payload(b"bootstrap data".to_vec())
+        .create()];
+    let _ = nodes[0].poll();
+    expect_next_event!(nodes[0], Event::Connected(Connected::First));
+
+    let config = NetworkConfig::node().with_hard_coded_contact(nodes[0].endpoint());
+    nodes.push(TestNode::builder(&env).network_config(config).create());
+    poll_and_resend(&mut nodes);
+
+    expect_any_event!(
+        nodes[1],
+        Event::ApprovalPayload(ref payload) if payload == b"bootstrap data"
+    );
+    expect_next_event!(nodes[1], Event::Connected(Connected::First));
+
+    assert!(Node::builder()
+        .join_payload(vec![0; MAX_APP_PAYLOAD_SIZE + 1])
+        .is_err());
+}
+
 #[test]
 fn multiple_joining_nodes() {
     let env = Environment::new(NetworkParams {
         elder_size: LOWERED_ELDER_SIZE,
         safe_section_size: LOWERED_ELDER_SIZE,
+        ..Default::default()
     });
     let mut nodes = create_connected_nodes(&env, LOWERED_ELDER_SIZE);
 
@@ -275,6 +432,7 @@ fn multi_split() {
     let env = Environment::new(NetworkParams {
         elder_size: LOWERED_ELDER_SIZE,
         safe_section_size: LOWERED_ELDER_SIZE,
+        ..Default::default()
     });
     let mut nodes = create_connected_nodes_until_split(&env, vec![2, 2, 2, 2]);
     verify_invariant_for_all_nodes(&env, &mut nodes);
@@ -380,6 +538,7 @@ fn simultaneous_joining_nodes_two_sections() {
     let env = Environment::new(NetworkParams {
         elder_size: LOWERED_ELDER_SIZE,
         safe_section_size: LOWERED_ELDER_SIZE,
+        ..Default::default()
     });
     let nodes = create_connected_nodes_until_split(&env, vec![1, 1]);
 
@@ -408,6 +567,7 @@ fn simultaneous_joining_nodes_two_sections_switch_section() {
     let env = Environment::new(NetworkParams {
         elder_size: LOWERED_ELDER_SIZE,
         safe_section_size: LOWERED_ELDER_SIZE,
+        ..Default::default()
     });
     let nodes = create_connected_nodes_until_split(&env, vec![1, 1]);
 
@@ -441,6 +601,7 @@ fn simultaneous_joining_nodes_three_section_with_one_ready_to_split() {
     let env = Environment::new(NetworkParams {
         elder_size,
         safe_section_size,
+        ..Default::default()
     });
     let mut nodes = create_connected_nodes_until_split(&env, vec![1, 2, 2]);
 
@@ -486,6 +647,7 @@ fn check_close_names_for_elder_size_nodes() {
         &Environment::new(NetworkParams {
             elder_size: LOWERED_ELDER_SIZE,
             safe_section_size: LOWERED_ELDER_SIZE,
+            ..Default::default()
         }),
         LOWERED_ELDER_SIZE,
     );
@@ -504,6 +666,7 @@ fn check_section_info_ack() {
     let env = Environment::new(NetworkParams {
         elder_size,
         safe_section_size,
+        ..Default::default()
     });
 
     // Act
@@ -529,6 +692,64 @@ fn check_section_info_ack() {
     assert_eq!(node_with_sibling_knowledge, expected_all_elder);
 }
 
+#[test]
+fn elders_learn_distant_sections() {
+    let network_cfg = NetworkParams {
+        elder_size: LOWERED_ELDER_SIZE,
+        safe_section_size: LOWERED_ELDER_SIZE,
+        section_map_capacity: 4,
+        ..Default::default()
+    };
+    let query_interval = network_cfg.section_map_query_interval;
+    let env = Environment::new(network_cfg);
+    let mut nodes = create_connected_nodes_until_split(&env, vec![2, 2, 2, 2]);
+
+    // Each elder learns about the section opposite to its own, which is not its neighbour.
+    for _ in 0..3 {
+        FakeClock::advance_time(query_interval.as_millis() as u64 + 1);
+        let _ = poll_all(&mut nodes);
+    }
+
+    for node in nodes.iter().filter(|node| node.inner.is_elder()) {
+        let our_prefix = *node.our_prefix();
+        let opposite = our_prefix.with_flipped_bit(0).with_flipped_bit(1);
+        let (prefix, elders) = unwrap!(node.inner.section_for(&opposite.lower_bound()));
+        assert_eq!(prefix, opposite);
+        assert!(elders
+            .iter()
+            .all(|elder| opposite.matches(elder.public_id().name())));
+    }
+}
+
+#[test]
+fn sections_agree_on_network_stats() {
+    let stats_interval = Duration::from_secs(60);
+    let env = Environment::new(NetworkParams {
+        elder_size: LOWERED_ELDER_SIZE,
+        safe_section_size: LOWERED_ELDER_SIZE,
+        network_stats_interval: Some(stats_interval),
+        ..Default::default()
+    });
+    let mut nodes = create_connected_nodes_until_split(&env, vec![1, 2, 2]);
+
+    // The stats of each section need a round to reach its neighbours, and another one to be
+    // passed on to their own neighbours.
+    for _ in 0..4 {
+        FakeClock::advance_time(stats_interval.as_millis() as u64 + 1);
+        let _ = poll_all(&mut nodes);
+    }
+
+    for node in nodes.iter().filter(|node| node.inner.is_elder()) {
+        let stats = unwrap!(node.inner.network_stats());
+        assert_eq!(stats.section.prefix, *node.our_prefix());
+        assert!(stats.is_complete());
+
+        let summary = stats.network_summary();
+        assert_eq!(summary.section_count, 3);
+        assert_eq!(summary.node_count, nodes.len() as u64);
+    }
+}
+
 #[test]
 fn carry_out_parsec_pruning() {
     let init_network_size = 7;
@@ -537,6 +758,7 @@ fn carry_out_parsec_pruning() {
     let env = Environment::new(NetworkParams {
         elder_size,
         safe_section_size,
+        ..Default::default()
     });
     let mut nodes = create_connected_nodes(&env, init_network_size);
     poll_and_resend(&mut nodes);
@@ -587,37 +809,109 @@ fn carry_out_parsec_pruning() {
 
 // The paused node does not participate until resumed, so we need enough elders to reach
 // consensus even without it.
-const NODE_PAUSE_AND_RESUME_PARAMS: NetworkParams = NetworkParams {
-    elder_size: 4,
-    safe_section_size: 4,
-};
+fn node_pause_and_resume_params() -> NetworkParams {
+    NetworkParams {
+        elder_size: 4,
+        safe_section_size: 4,
+        ..Default::default()
+    }
+}
 
 #[test]
 fn node_pause_and_resume_simple() {
-    let env = Environment::new(NODE_PAUSE_AND_RESUME_PARAMS);
+    let env = Environment::new(node_pause_and_resume_params());
+    let nodes = create_connected_nodes(&env, 2 * env.safe_section_size() - 2);
+    let index = env.new_rng().gen_range(0, nodes.len());
+    let new_node_id = FullId::gen(&mut env.new_rng());
+    node_pause_and_resume(env, nodes, index, new_node_id, false)
+}
+
+// An elder loses its parsec graph in the round-trip through bytes, so this verifies it rejoins
+// parsec without forking the graph of its section.
+#[test]
+fn node_pause_and_resume_from_bytes() {
+    let env = Environment::new(node_pause_and_resume_params());
     let nodes = create_connected_nodes(&env, 2 * env.safe_section_size() - 2);
+    let index = unwrap!(nodes.iter().position(|node| node.inner.is_elder()));
     let new_node_id = FullId::gen(&mut env.new_rng());
-    node_pause_and_resume(env, nodes, new_node_id)
+    node_pause_and_resume(env, nodes, index, new_node_id, true)
 }
 
 #[test]
 fn node_pause_and_resume_during_split() {
-    let env = Environment::new(NODE_PAUSE_AND_RESUME_PARAMS);
+    let env = Environment::new(node_pause_and_resume_params());
 
     let mut nodes = create_connected_nodes(&env, env.safe_section_size());
     let prefix =
         add_connected_nodes_until_one_away_from_split(&env, &mut nodes, &[Prefix::default()])[0];
 
+    let index = env.new_rng().gen_range(0, nodes.len());
     let new_node_id = FullId::within_range(&mut env.new_rng(), &prefix.range_inclusive());
-    node_pause_and_resume(env, nodes, new_node_id)
+    node_pause_and_resume(env, nodes, index, new_node_id, false)
 }
 
-// Pause a random node, then add new node with the given id, then resume the paused node and verify
-// everything still works as expected.
-fn node_pause_and_resume(env: Environment, mut nodes: Nodes, new_node_id: FullId) {
-    let index = env.new_rng().gen_range(0, nodes.len());
+#[test]
+fn node_restart_from_snapshot() {
+    let env = Environment::new(node_pause_and_resume_params());
+    let nodes = create_connected_nodes(&env, env.safe_section_size());
+    node_restart_from_snapshot_and_verify(env, nodes, false)
+}
+
+// The restarted elder has no parsec graph, so this verifies it rejoins parsec without forking the
+// graph of its section.
+#[test]
+fn elder_restart_from_snapshot() {
+    // Snapshot often enough for the latest one to be taken after the node became an elder.
+    let env = Environment::new(NetworkParams {
+        snapshot_interval: Duration::from_secs(1),
+        ..node_pause_and_resume_params()
+    });
+    let nodes = create_connected_nodes(&env, env.elder_size() - 1);
+    node_restart_from_snapshot_and_verify(env, nodes, true)
+}
+
+// Add a node which takes snapshots, crash it, then restart it from the same snapshot directory and
+// verify it comes back with its previous identity instead of joining as a new node.
+fn node_restart_from_snapshot_and_verify(env: Environment, mut nodes: Nodes, is_elder: bool) {
+    let snapshot_dir = unwrap!(tempfile::tempdir());
+
+    let config = NetworkConfig::node().with_hard_coded_contact(nodes[0].endpoint());
+    let node = TestNode::builder(&env)
+        .network_config(config)
+        .snapshot_dir(snapshot_dir.path().to_path_buf())
+        .create();
+    nodes.push(node);
+    poll_and_resend(&mut nodes);
+    assert_eq!(unwrap!(nodes.last()).inner.is_elder(), is_elder);
+
+    let crashed_id = unwrap!(nodes.pop()).id();
+
+    let node = TestNode::builder(&env)
+        .snapshot_dir(snapshot_dir.path().to_path_buf())
+        .create();
+    assert_eq!(node.id(), crashed_id);
+    nodes.push(node);
+
+    poll_and_resend(&mut nodes);
+    verify_invariant_for_all_nodes(&env, &mut nodes);
+    assert_eq!(unwrap!(nodes.last()).inner.is_elder(), is_elder);
+}
+
+// Pause the node at `index`, then add new node with the given id, then resume the paused node and
+// verify everything still works as expected. If `via_bytes` is set, the paused state is serialised
+// and deserialised before resuming.
+fn node_pause_and_resume(
+    env: Environment,
+    mut nodes: Nodes,
+    index: usize,
+    new_node_id: FullId,
+    via_bytes: bool,
+) {
     let paused_id = nodes[index].id();
-    let state = unwrap!(nodes.remove(index).inner.pause());
+    let mut state = unwrap!(nodes.remove(index).inner.pause());
+    if via_bytes {
+        state = unwrap!(PausedState::from_bytes(&unwrap!(state.to_bytes())));
+    }
 
     // Verify the other nodes do not see the node as going offline.
     poll_and_resend(&mut nodes);
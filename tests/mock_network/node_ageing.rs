@@ -24,14 +24,17 @@ use std::{iter, slice};
 
 // These params are selected such that there can be a section size which allows relocation and at the same time
 // allows churn to happen which doesn't trigger split or allow churn to not increase age.
-const NETWORK_PARAMS: NetworkParams = NetworkParams {
-    elder_size: LOWERED_ELDER_SIZE,
-    safe_section_size: LOWERED_ELDER_SIZE + 4,
-};
+fn network_params() -> NetworkParams {
+    NetworkParams {
+        elder_size: LOWERED_ELDER_SIZE,
+        safe_section_size: LOWERED_ELDER_SIZE + 4,
+        ..Default::default()
+    }
+}
 
 #[test]
 fn relocate_without_split() {
-    let env = Environment::new(NETWORK_PARAMS);
+    let env = Environment::new(network_params());
     let mut overrides = RelocationOverrides::new();
 
     let mut rng = env.new_rng();
@@ -74,7 +77,7 @@ fn relocate_causing_split() {
     // sub-interval, but the test is still useful as is for soak testing.
 
     // Relocate node into a section which is one node shy of splitting.
-    let env = Environment::new(NETWORK_PARAMS);
+    let env = Environment::new(network_params());
     let mut overrides = RelocationOverrides::new();
 
     let mut rng = env.new_rng();
@@ -130,7 +133,7 @@ fn relocate_causing_split() {
 #[test]
 fn relocate_during_split() {
     // Relocate node into a section which is undergoing split.
-    let env = Environment::new(NETWORK_PARAMS);
+    let env = Environment::new(network_params());
     let mut overrides = RelocationOverrides::new();
 
     let mut rng = env.new_rng();
@@ -242,11 +245,11 @@ fn section_churn_allowing_relocate(
     prefix: &Prefix<XorName>,
 ) {
     // Keep the section size such that relocations can happen but splits can't.
-    // We need NETWORK_PARAMS.elder_size + 1 excluding relocating node for it to be demoted.
-    let min_size = (NETWORK_PARAMS.elder_size + 1) + 1;
+    // We need network_params().elder_size + 1 excluding relocating node for it to be demoted.
+    let min_size = (network_params().elder_size + 1) + 1;
 
     // Ensure we are increasing age at each churn event.
-    let max_size = NETWORK_PARAMS.safe_section_size - 1;
+    let max_size = network_params().safe_section_size - 1;
 
     section_churn(count, &env, nodes, &prefix, min_size, max_size)
 }
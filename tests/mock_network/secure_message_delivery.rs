@@ -11,6 +11,7 @@ use routing::{
     elders_info_for_test, generate_bls_threshold_secret_key, mock::Environment,
     section_proof_slice_for_test, AccumulatingMessage, ConnectionInfo, DstLocation, FullId,
     Message, NetworkParams, P2pNode, PlainMessage, Prefix, SectionKeyShare, Variant, XorName,
+    PROTOCOL_VERSION,
 };
 use std::{collections::BTreeMap, iter, net::SocketAddr};
 
@@ -49,6 +50,7 @@ fn message_with_invalid_security(fail_type: FailType) {
     let mut env = Environment::new(NetworkParams {
         elder_size,
         safe_section_size,
+        ..Default::default()
     });
     env.expect_panic();
     let mut rng = env.new_rng();
@@ -75,6 +77,7 @@ fn message_with_invalid_security(fail_type: FailType) {
     let new_info = unwrap!(elders_info_for_test(members, our_prefix, 10001));
 
     let content = PlainMessage {
+        version: PROTOCOL_VERSION,
         src: our_prefix,
         dst: DstLocation::Prefix(their_prefix),
         variant: Variant::NeighbourInfo(new_info),
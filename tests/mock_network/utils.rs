@@ -16,8 +16,9 @@ use rand::{
 use routing::{
     event::{Connected, Event},
     mock::Environment,
-    test_consts, Builder, DstLocation, FullId, NetworkConfig, Node, PausedState, Prefix, PublicId,
-    RelocationOverrides, SrcLocation, XorName, Xorable,
+    test_consts, Builder, Client, ClientBuilder, DstLocation, FullId, JoinPolicy, NetworkConfig,
+    NetworkParams, Node, PausedState, Prefix, PublicId, RelocationOverrides, RoutingError,
+    SrcLocation, XorName, Xorable,
 };
 use std::{
     cmp,
@@ -25,6 +26,7 @@ use std::{
     iter,
     net::SocketAddr,
     ops::{Deref, DerefMut},
+    path::PathBuf,
 };
 
 // Maximum number of times to try and poll in a loop.  This is several orders higher than the
@@ -78,13 +80,13 @@ pub struct TestNode {
 impl TestNode {
     pub fn builder(env: &Environment) -> TestNodeBuilder {
         TestNodeBuilder {
-            inner: Node::builder(),
+            inner: Node::builder().network_cfg(env.network_cfg()),
             env,
         }
     }
 
     pub fn resume(env: &Environment, state: PausedState) -> Self {
-        let (inner, user_event_rx) = Node::resume(state);
+        let (inner, user_event_rx) = unwrap!(Node::resume(state));
         Self {
             inner,
             env: env.clone(),
@@ -183,6 +185,13 @@ impl<'a> TestNodeBuilder<'a> {
         }
     }
 
+    pub fn network_cfg(self, network_cfg: NetworkParams) -> Self {
+        Self {
+            inner: self.inner.network_cfg(network_cfg),
+            ..self
+        }
+    }
+
     pub fn full_id(self, full_id: FullId) -> Self {
         Self {
             inner: self.inner.full_id(full_id),
@@ -190,18 +199,123 @@ impl<'a> TestNodeBuilder<'a> {
         }
     }
 
+    pub fn snapshot_dir(self, dir: PathBuf) -> Self {
+        Self {
+            inner: self.inner.snapshot_dir(dir),
+            ..self
+        }
+    }
+
+    pub fn join_policy<P: JoinPolicy + 'static>(self, policy: P) -> Self {
+        Self {
+            inner: self.inner.join_policy(policy),
+            ..self
+        }
+    }
+
+    pub fn join_payload(self, payload: Vec<u8>) -> Self {
+        Self {
+            inner: unwrap!(self.inner.join_payload(payload)),
+            ..self
+        }
+    }
+
+    pub fn approval_payload(self, payload: Vec<u8>) -> Self {
+        Self {
+            inner: unwrap!(self.inner.approval_payload(payload)),
+            ..self
+        }
+    }
+
     pub fn create(self) -> TestNode {
-        let (inner, user_event_rx) = self
-            .inner
-            .network_cfg(self.env.network_cfg())
-            .rng(&mut self.env.new_rng())
-            .create();
+        unwrap!(self.try_create())
+    }
 
-        TestNode {
+    pub fn try_create(self) -> Result<TestNode, RoutingError> {
+        let (inner, user_event_rx) = self.inner.rng(&mut self.env.new_rng()).create()?;
+
+        Ok(TestNode {
             inner,
             env: self.env.clone(),
             user_event_rx,
+        })
+    }
+}
+
+// -----  TestClient  -----
+
+pub struct TestClient {
+    pub inner: Client,
+    user_event_rx: mpmc::Receiver<Event>,
+}
+
+impl TestClient {
+    pub fn new(env: &Environment, bootstrap_contact: SocketAddr) -> Self {
+        Self::with_builder(env, bootstrap_contact, Client::builder())
+    }
+
+    pub fn with_genesis_key(
+        env: &Environment,
+        bootstrap_contact: SocketAddr,
+        genesis_key: bls::PublicKey,
+    ) -> Self {
+        Self::with_builder(
+            env,
+            bootstrap_contact,
+            Client::builder().genesis_key(genesis_key),
+        )
+    }
+
+    fn with_builder(
+        env: &Environment,
+        bootstrap_contact: SocketAddr,
+        builder: ClientBuilder,
+    ) -> Self {
+        let config = NetworkConfig::default().with_hard_coded_contact(bootstrap_contact);
+        let (inner, user_event_rx) = unwrap!(builder
+            .network_config(config)
+            .rng(&mut env.new_rng())
+            .create());
+
+        Self {
+            inner,
+            user_event_rx,
+        }
+    }
+
+    pub fn id(&self) -> PublicId {
+        *self.inner.id()
+    }
+
+    pub fn name(&self) -> XorName {
+        *self.id().name()
+    }
+
+    pub fn poll(&mut self) -> bool {
+        let mut result = false;
+
+        loop {
+            let mut sel = mpmc::Select::new();
+            self.inner.register(&mut sel);
+
+            if let Ok(op_index) = sel.try_ready() {
+                if self
+                    .inner
+                    .handle_selected_operation(op_index)
+                    .unwrap_or(false)
+                {
+                    result = true;
+                }
+            } else {
+                break;
+            }
         }
+
+        result
+    }
+
+    pub fn try_recv_event(&self) -> Option<Event> {
+        self.user_event_rx.try_recv().ok()
     }
 }
 
@@ -229,6 +343,25 @@ pub fn poll_all(nodes: &mut [TestNode]) -> bool {
     panic!("poll_all has been called {} times.", MAX_POLL_CALLS);
 }
 
+/// Process all events of the nodes and the client. Returns whether there were any events.
+pub fn poll_all_with_client(nodes: &mut [TestNode], client: &mut TestClient) -> bool {
+    let mut result = false;
+    for _ in 0..MAX_POLL_CALLS {
+        let handled_message = poll_all(nodes);
+        let handled_message = client.poll() || handled_message;
+
+        if !handled_message {
+            return result;
+        }
+
+        result = true;
+    }
+    panic!(
+        "poll_all_with_client has been called {} times.",
+        MAX_POLL_CALLS
+    );
+}
+
 /// Polls and processes all events, until there are no unacknowledged messages left.
 pub fn poll_and_resend(nodes: &mut [TestNode]) {
     poll_and_resend_with_options(nodes, PollOptions::default())